@@ -0,0 +1,13 @@
+#!/bin/sh
+
+# Builds the benchmarks with the wasm-bindgen in this checkout into the
+# directory given as the first argument, `pkg` by default.
+
+set -ex
+
+out=${1:-pkg}
+
+cargo +nightly build --release --target wasm32-unknown-unknown
+cargo +nightly run --manifest-path ../crates/cli/Cargo.toml \
+  --bin wasm-bindgen -- \
+  ../target/wasm32-unknown-unknown/release/benchmarks.wasm --nodejs --out-dir $out
@@ -0,0 +1,57 @@
+// Compares the throughput of common `JsValue` operations between builds of
+// this crate, for example with and without a change to wasm-bindgen:
+//
+//     node index.js before after
+//
+// where each argument is a directory generated by `build.sh`.
+
+const path = require('path');
+
+const obj = { a: 1 };
+global.bench_noop = function(a) {};
+global.bench_object = function() { return obj; };
+
+const ITERATIONS = 100000;
+const ROUNDS = 20;
+
+const benchmarks = {
+  clone_drop: wasm => wasm.clone_drop(obj, ITERATIONS),
+  clone_many: wasm => wasm.clone_many(obj, ITERATIONS),
+  create_drop: wasm => wasm.create_drop(ITERATIONS),
+  create_strings: wasm => wasm.create_strings(ITERATIONS),
+  pass_to_js: wasm => wasm.pass_to_js(obj, ITERATIONS),
+  take_from_js: wasm => wasm.take_from_js(ITERATIONS),
+};
+
+// Returns the fastest of `ROUNDS` runs of `f` in operations per second, after
+// a warmup round.
+function measure(f) {
+  f();
+  let best = Infinity;
+  for (let i = 0; i < ROUNDS; i++) {
+    const start = process.hrtime();
+    f();
+    const [s, ns] = process.hrtime(start);
+    best = Math.min(best, s + ns / 1e9);
+  }
+  return ITERATIONS / best;
+}
+
+const dirs = process.argv.slice(2);
+if (dirs.length === 0) {
+  console.error('usage: node index.js <dir>...');
+  process.exit(1);
+}
+const builds = dirs.map(dir => require(path.resolve(dir, 'benchmarks.js')));
+
+const width = Math.max(...Object.keys(benchmarks).map(name => name.length));
+console.log(['benchmark'.padEnd(width), ...dirs.map(d => d.padStart(14))].join('  '));
+for (const name of Object.keys(benchmarks)) {
+  const results = builds.map(wasm => measure(() => benchmarks[name](wasm)));
+  const row = results.map((r, i) => {
+    let s = `${(r / 1e6).toFixed(2)}M/s`;
+    if (i > 0) s += ` (${(r / results[0]).toFixed(2)}x)`;
+    return s.padStart(14);
+  });
+  console.log([name.padEnd(width), ...row].join('  '));
+}
@@ -0,0 +1,60 @@
+#![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+extern crate wasm_bindgen;
+
+use wasm_bindgen::prelude::*;
+
+// Imported from the global scope, `index.js` defines these before loading the
+// module.
+#[wasm_bindgen]
+extern {
+    fn bench_noop(a: &JsValue);
+    fn bench_object() -> JsValue;
+}
+
+/// Clones and drops `obj` `n` times.
+#[wasm_bindgen]
+pub fn clone_drop(obj: &JsValue, n: u32) {
+    for _ in 0..n {
+        drop(obj.clone());
+    }
+}
+
+/// Clones `obj` `n` times, keeping all of the clones alive until the end.
+#[wasm_bindgen]
+pub fn clone_many(obj: &JsValue, n: u32) {
+    let clones = (0..n).map(|_| obj.clone()).collect::<Vec<_>>();
+    drop(clones);
+}
+
+/// Creates and drops `n` numbers.
+#[wasm_bindgen]
+pub fn create_drop(n: u32) {
+    for i in 0..n {
+        drop(JsValue::from(i));
+    }
+}
+
+/// Creates and drops `n` strings.
+#[wasm_bindgen]
+pub fn create_strings(n: u32) {
+    for _ in 0..n {
+        drop(JsValue::from_str("hello, world"));
+    }
+}
+
+/// Passes `obj` by reference to JS `n` times.
+#[wasm_bindgen]
+pub fn pass_to_js(obj: &JsValue, n: u32) {
+    for _ in 0..n {
+        bench_noop(obj);
+    }
+}
+
+/// Receives and drops `n` objects from JS.
+#[wasm_bindgen]
+pub fn take_from_js(n: u32) {
+    for _ in 0..n {
+        drop(bench_object());
+    }
+}
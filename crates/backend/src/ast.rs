@@ -9,6 +9,7 @@ pub struct Program {
     pub imports: Vec<Import>,
     pub enums: Vec<Enum>,
     pub structs: Vec<Struct>,
+    pub objects: Vec<Object>,
     pub type_aliases: Vec<TypeAlias>,
 }
 
@@ -106,6 +107,16 @@ pub struct Function {
     pub ret: Option<syn::Type>,
     pub rust_attrs: Vec<syn::Attribute>,
     pub rust_vis: syn::Visibility,
+    pub location: Option<Location>,
+}
+
+/// Where an item was defined in Rust source, used to generate source maps.
+#[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
+#[derive(Clone)]
+pub struct Location {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
 }
 
 #[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
@@ -126,6 +137,21 @@ pub struct StructField {
     pub comments: Vec<String>,
 }
 
+#[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
+pub struct Object {
+    pub name: Ident,
+    pub fields: Vec<ObjectField>,
+    pub comments: Vec<String>,
+}
+
+#[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
+pub struct ObjectField {
+    pub name: Ident,
+    pub ty: syn::Type,
+    pub describe: Ident,
+    pub comments: Vec<String>,
+}
+
 #[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
 pub struct Enum {
     pub name: Ident,
@@ -166,6 +192,7 @@ impl Program {
         shared::Program {
             exports: self.exports.iter().map(|a| a.shared()).collect(),
             structs: self.structs.iter().map(|a| a.shared()).collect(),
+            objects: self.objects.iter().map(|a| a.shared()).collect(),
             enums: self.enums.iter().map(|a| a.shared()).collect(),
             imports: self.imports.iter().map(|a| a.shared()).collect(),
             version: shared::version(),
@@ -178,6 +205,11 @@ impl Function {
     fn shared(&self) -> shared::Function {
         shared::Function {
             name: self.name.to_string(),
+            location: self.location.as_ref().map(|l| shared::Location {
+                file: l.file.clone(),
+                line: l.line,
+                column: l.column,
+            }),
         }
     }
 }
@@ -383,3 +415,22 @@ impl StructField {
         }
     }
 }
+
+impl Object {
+    fn shared(&self) -> shared::Object {
+        shared::Object {
+            name: self.name.to_string(),
+            fields: self.fields.iter().map(|f| f.shared()).collect(),
+            comments: self.comments.clone(),
+        }
+    }
+}
+
+impl ObjectField {
+    fn shared(&self) -> shared::ObjectField {
+        shared::ObjectField {
+            name: self.name.to_string(),
+            comments: self.comments.clone(),
+        }
+    }
+}
@@ -37,6 +37,9 @@ impl ToTokens for ast::Program {
         for s in self.structs.iter() {
             s.to_tokens(tokens);
         }
+        for o in self.objects.iter() {
+            o.to_tokens(tokens);
+        }
         let mut types = HashSet::new();
         for i in self.imports.iter() {
             if let ast::ImportKind::Type(t) = &i.kind {
@@ -233,6 +236,100 @@ impl ToTokens for ast::Struct {
     }
 }
 
+impl ToTokens for ast::Object {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.name;
+        let name_str = name.to_string();
+        let name_len = name_str.len() as u32;
+        let name_chars = name_str.chars().map(|c| c as u32);
+        let invalid = format!("expected a `{}` object", name_str);
+        let fields = self.fields.iter().map(|f| &f.name).collect::<Vec<_>>();
+        let fields2 = fields.clone();
+        let keys = fields.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        let keys2 = keys.clone();
+        (quote! {
+            impl ::wasm_bindgen::convert::ObjectField for #name {
+                fn describe_field() {
+                    <#name as ::wasm_bindgen::describe::WasmDescribe>::describe();
+                }
+
+                fn into_js(self) -> ::wasm_bindgen::JsValue {
+                    use wasm_bindgen::__rt::{object_new, object_set};
+                    use wasm_bindgen::convert::ObjectField;
+
+                    let obj = object_new();
+                    #(object_set(&obj, #keys, ObjectField::into_js(self.#fields));)*
+                    obj
+                }
+
+                fn from_js(js: ::wasm_bindgen::JsValue) -> Option<#name> {
+                    use wasm_bindgen::__rt::object_get;
+                    use wasm_bindgen::convert::ObjectField;
+
+                    if !js.is_object() {
+                        return None;
+                    }
+                    Some(#name {
+                        #(#fields2: ObjectField::from_js(object_get(&js, #keys2))?,)*
+                    })
+                }
+            }
+
+            impl ::wasm_bindgen::describe::WasmDescribe for #name {
+                fn describe() {
+                    use wasm_bindgen::describe::*;
+                    inform(OBJECT);
+                    inform(#name_len);
+                    #(inform(#name_chars);)*
+                }
+            }
+
+            impl ::wasm_bindgen::convert::IntoWasmAbi for #name {
+                type Abi = u32;
+
+                fn into_abi(self, extra: &mut ::wasm_bindgen::convert::Stack) -> u32 {
+                    use wasm_bindgen::convert::{IntoWasmAbi, ObjectField};
+                    ObjectField::into_js(self).into_abi(extra)
+                }
+            }
+
+            impl ::wasm_bindgen::convert::FromWasmAbi for #name {
+                type Abi = u32;
+
+                unsafe fn from_abi(js: u32, extra: &mut ::wasm_bindgen::convert::Stack) -> #name {
+                    use wasm_bindgen::convert::{FromWasmAbi, ObjectField};
+                    let js = ::wasm_bindgen::JsValue::from_abi(js, extra);
+                    match ObjectField::from_js(js) {
+                        Some(obj) => obj,
+                        None => ::wasm_bindgen::throw(#invalid),
+                    }
+                }
+            }
+
+            impl From<#name> for ::wasm_bindgen::JsValue {
+                fn from(obj: #name) -> ::wasm_bindgen::JsValue {
+                    ::wasm_bindgen::convert::ObjectField::into_js(obj)
+                }
+            }
+        }).to_tokens(tokens);
+
+        for field in self.fields.iter() {
+            let ty = &field.ty;
+            let describe = Ident::new(
+                &format!("__wbindgen_describe_{}", field.describe),
+                Span::call_site(),
+            );
+            (quote! {
+                #[no_mangle]
+                #[doc(hidden)]
+                pub extern fn #describe() {
+                    <#ty as ::wasm_bindgen::convert::ObjectField>::describe_field();
+                }
+            }).to_tokens(tokens);
+        }
+    }
+}
+
 impl ToTokens for ast::StructField {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let name = &self.name;
@@ -577,6 +674,12 @@ impl ToTokens for ast::ImportType {
                     obj.obj
                 }
             }
+
+            impl AsRef<::wasm_bindgen::JsValue> for #name {
+                fn as_ref(&self) -> &::wasm_bindgen::JsValue {
+                    &self.obj
+                }
+            }
         }).to_tokens(tokens);
     }
 }
@@ -0,0 +1,655 @@
+//! Support for `--anyref`, passing JS values to and from wasm as native
+//! `anyref`s rather than as indices into a heap of objects kept in JS.
+//!
+//! Rust code still refers to JS values with indices, but these are now slots
+//! in a wasm table of `anyref`s which is exported to JS, with the slots handed
+//! out by the `wasm-bindgen` crate. Imports and exports which take or return
+//! JS values get wasm shims converting between the two: an import shim looks
+//! values up in the table before calling the real import, which now takes
+//! `anyref`s, and an export shim takes `anyref`s, stores them in the table and
+//! passes their indices on to the real export.
+//!
+//! This happens in two steps. `prepare` adds placeholder shims and points
+//! calls and exports at them while the module is still a `parity_wasm`
+//! module, and then `finish` fills in the parts `parity_wasm` can't represent
+//! (the `anyref` types, the table and the bodies of the shims) directly in the
+//! serialized module, so nothing may parse the module in between.
+
+use std::collections::HashMap;
+
+use failure::Error;
+use parity_wasm::elements::*;
+
+use binary::{self, append_entries, byte, read_limits, read_u32, write_i32, write_u32};
+
+/// How a JS value is passed across the boundary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pass {
+    /// Ownership is transferred, so the receiver is responsible for the slot.
+    Owned,
+    /// The value is only borrowed for the duration of the call.
+    Borrowed,
+}
+
+/// The JS values in the signature of an import or export.
+#[derive(Debug, Clone, Default)]
+pub struct Signature {
+    /// The wasm parameters which are JS values.
+    pub args: Vec<(usize, Pass)>,
+    /// Whether a JS value is returned.
+    pub ret: Option<Pass>,
+}
+
+impl Signature {
+    pub fn is_empty(&self) -> bool {
+        self.args.len() == 0 && self.ret.is_none()
+    }
+}
+
+/// The imports and exports to rewrite, registered while generating JS.
+#[derive(Default)]
+pub struct Transform {
+    pub imports: HashMap<String, Signature>,
+    pub exports: HashMap<String, Signature>,
+    prepared: Option<Prepared>,
+}
+
+/// Everything `finish` needs to know about what `prepare` did.
+struct Prepared {
+    imported_functions: u32,
+    table: u32,
+    alloc: u32,
+    dealloc: u32,
+    init: u32,
+    get: u32,
+    add: u32,
+    drop: u32,
+    shims: Vec<Shim>,
+}
+
+struct Shim {
+    /// Index of the placeholder function for the shim.
+    func: u32,
+    kind: ShimKind,
+    params: Vec<ValueType>,
+    ret: Option<ValueType>,
+    sig: Signature,
+}
+
+enum ShimKind {
+    /// Shim for the import at the given position in the import section, with
+    /// the given function index.
+    Import { entry: usize, func: u32 },
+    /// Shim for an export of the given function.
+    Export { func: u32 },
+}
+
+const ANYREF: u8 = 0x6f;
+
+/// Name of the export of the table of JS values.
+pub const TABLE_EXPORT: &str = "__wbindgen_anyref_table";
+
+impl Transform {
+    /// Adds placeholders for the shims and the helpers they use, points calls
+    /// to rewritten imports and rewritten exports at them, and exports the
+    /// table which will be added by `finish`.
+    pub fn prepare(&mut self, module: &mut Module) -> Result<(), Error> {
+        let mut imported_functions = 0;
+        let mut tables = 0;
+        let mut import_types = Vec::new();
+        if let Some(imports) = module.import_section() {
+            for (i, entry) in imports.entries().iter().enumerate() {
+                match *entry.external() {
+                    External::Function(ty) => {
+                        let name = entry.field().to_string();
+                        import_types.push((i, name, imported_functions, ty));
+                        imported_functions += 1;
+                    }
+                    External::Table(_) => tables += 1,
+                    _ => {}
+                }
+            }
+        }
+        tables += module
+            .table_section()
+            .map(|s| s.entries().len() as u32)
+            .unwrap_or(0);
+
+        let function_types = module
+            .function_section()
+            .map(|s| s.entries().iter().map(|f| f.type_ref()).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let types = module
+            .type_section()
+            .map(|s| s.types().to_vec())
+            .unwrap_or_default();
+        let signature = |ty: u32| -> Result<FunctionType, Error> {
+            match types.get(ty as usize) {
+                Some(&Type::Function(ref f)) => Ok(f.clone()),
+                None => bail!("invalid type index {}", ty),
+            }
+        };
+        let type_of = |func: u32| -> Result<u32, Error> {
+            if func < imported_functions {
+                Ok(import_types[func as usize].3)
+            } else {
+                match function_types.get((func - imported_functions) as usize) {
+                    Some(ty) => Ok(*ty),
+                    None => bail!("invalid function index {}", func),
+                }
+            }
+        };
+
+        let mut exported = HashMap::new();
+        if let Some(exports) = module.export_section() {
+            for entry in exports.entries() {
+                if let Internal::Function(f) = *entry.internal() {
+                    exported.insert(entry.field().to_string(), f);
+                }
+            }
+        }
+        let export = |name: &str| -> Result<u32, Error> {
+            match exported.get(name) {
+                Some(f) => Ok(*f),
+                None => bail!(
+                    "the exported function `{}` is required for `--anyref`",
+                    name
+                ),
+            }
+        };
+
+        // Placeholders are added after all the existing functions.
+        let first = imported_functions + function_types.len() as u32;
+        let mut placeholders = Vec::new();
+        fn placeholder(first: u32, placeholders: &mut Vec<u32>, ty: u32) -> u32 {
+            placeholders.push(ty);
+            first + placeholders.len() as u32 - 1
+        }
+
+        let dealloc = export("__wbindgen_anyref_table_dealloc")?;
+        let mut prepared = Prepared {
+            imported_functions,
+            table: tables,
+            alloc: export("__wbindgen_anyref_table_alloc")?,
+            init: export("__wbindgen_anyref_table_init")?,
+            dealloc,
+            // The types of these are filled in by `finish`, the type of
+            // `__wbindgen_anyref_table_dealloc` is just a stand in.
+            get: placeholder(first, &mut placeholders, type_of(dealloc)?),
+            add: placeholder(first, &mut placeholders, type_of(dealloc)?),
+            drop: placeholder(first, &mut placeholders, type_of(dealloc)?),
+            shims: Vec::new(),
+        };
+
+        let mut redirect = HashMap::new();
+        for &(entry, ref name, func, ty) in import_types.iter() {
+            let sig = match self.imports.get(name) {
+                Some(sig) if !sig.is_empty() => sig.clone(),
+                _ => continue,
+            };
+            let f = signature(ty)?;
+            let shim = placeholder(first, &mut placeholders, ty);
+            redirect.insert(func, shim);
+            prepared.shims.push(Shim {
+                func: shim,
+                kind: ShimKind::Import { entry, func },
+                params: f.params().to_vec(),
+                ret: f.return_type(),
+                sig,
+            });
+        }
+
+        // Calls to rewritten imports from Rust now go through their shims,
+        // which must happen before the shims themselves are added.
+        if let Some(code) = module.code_section_mut() {
+            for body in code.bodies_mut() {
+                for instr in body.code_mut().elements_mut() {
+                    if let Instruction::Call(ref mut f) = *instr {
+                        if let Some(shim) = redirect.get(f) {
+                            *f = *shim;
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(elements) = module.elements_section_mut() {
+            for segment in elements.entries_mut() {
+                for f in segment.members_mut() {
+                    if let Some(shim) = redirect.get(f) {
+                        *f = *shim;
+                    }
+                }
+            }
+        }
+
+        if let Some(exports) = module.export_section_mut() {
+            for entry in exports.entries_mut() {
+                let sig = match self.exports.get(entry.field()) {
+                    Some(sig) if !sig.is_empty() => sig.clone(),
+                    _ => continue,
+                };
+                let func = match *entry.internal() {
+                    Internal::Function(f) => f,
+                    _ => continue,
+                };
+                let ty = type_of(func)?;
+                let f = signature(ty)?;
+                let shim = placeholder(first, &mut placeholders, ty);
+                *entry.internal_mut() = Internal::Function(shim);
+                prepared.shims.push(Shim {
+                    func: shim,
+                    kind: ShimKind::Export { func },
+                    params: f.params().to_vec(),
+                    ret: f.return_type(),
+                    sig,
+                });
+            }
+            exports.entries_mut().push(ExportEntry::new(
+                TABLE_EXPORT.to_string(),
+                Internal::Table(tables),
+            ));
+        }
+
+        match module.function_section_mut() {
+            Some(functions) => {
+                for ty in placeholders.iter() {
+                    functions.entries_mut().push(Func::new(*ty));
+                }
+            }
+            None => bail!("wasm module has no function section"),
+        }
+        match module.code_section_mut() {
+            Some(code) => {
+                for _ in placeholders.iter() {
+                    code.bodies_mut().push(FuncBody::new(
+                        Vec::new(),
+                        Instructions::new(vec![Instruction::Unreachable, Instruction::End]),
+                    ));
+                }
+            }
+            None => bail!("wasm module has no code section"),
+        }
+
+        self.prepared = Some(prepared);
+        Ok(())
+    }
+
+    /// Adds the `anyref` types and table and fills in the shims added by
+    /// `prepare` in the serialized `wasm`.
+    pub fn finish(&self, wasm: &[u8]) -> Result<Vec<u8>, Error> {
+        let prepared = match self.prepared {
+            Some(ref p) => p,
+            None => bail!("`prepare` must be called before `finish`"),
+        };
+        let mut sections = binary::sections(wasm)?;
+
+        // New types are appended after the existing ones.
+        let mut new_types = Vec::new();
+        let type_count = match sections.iter().find(|s| s.0 == 1) {
+            Some(s) => read_u32(&s.1, &mut 0)?,
+            None => 0,
+        };
+        fn add_type(
+            new_types: &mut Vec<Vec<u8>>,
+            first: u32,
+            params: Vec<u8>,
+            results: Vec<u8>,
+        ) -> u32 {
+            let mut ty = vec![0x60];
+            write_u32(&mut ty, params.len() as u32);
+            ty.extend(params);
+            write_u32(&mut ty, results.len() as u32);
+            ty.extend(results);
+            new_types.push(ty);
+            first + new_types.len() as u32 - 1
+        }
+
+        let mut function_types = HashMap::new();
+        let mut import_types = HashMap::new();
+        let mut bodies = HashMap::new();
+
+        let get = add_type(&mut new_types, type_count, vec![I32], vec![ANYREF]);
+        let add = add_type(&mut new_types, type_count, vec![ANYREF], vec![I32]);
+        let drop = add_type(&mut new_types, type_count, vec![I32], vec![]);
+        function_types.insert(prepared.get, get);
+        function_types.insert(prepared.add, add);
+        function_types.insert(prepared.drop, drop);
+        bodies.insert(prepared.get, prepared.get_body());
+        bodies.insert(prepared.add, prepared.add_body());
+        bodies.insert(prepared.drop, prepared.drop_body());
+
+        for shim in prepared.shims.iter() {
+            let mut params = shim
+                .params
+                .iter()
+                .map(|t| value_type(*t))
+                .collect::<Vec<_>>();
+            for &(i, _) in shim.sig.args.iter() {
+                params[i] = ANYREF;
+            }
+            let results = match (shim.ret, shim.sig.ret) {
+                (_, Some(_)) => vec![ANYREF],
+                (Some(t), None) => vec![value_type(t)],
+                (None, None) => vec![],
+            };
+            let ty = add_type(&mut new_types, type_count, params, results);
+            match shim.kind {
+                ShimKind::Import { entry, func } => {
+                    import_types.insert(entry, ty);
+                    bodies.insert(shim.func, prepared.import_shim_body(shim, func));
+                }
+                ShimKind::Export { func } => {
+                    function_types.insert(shim.func, ty);
+                    bodies.insert(shim.func, prepared.export_shim_body(shim, func));
+                }
+            }
+        }
+
+        let mut table_added = false;
+        for &mut (id, ref mut payload) in sections.iter_mut() {
+            *payload = match id {
+                1 => append_entries(payload, &new_types)?,
+                2 => rewrite_imports(payload, &import_types)?,
+                3 => rewrite_functions(payload, prepared, &function_types)?,
+                4 => {
+                    table_added = true;
+                    append_entries(payload, &[table_type()])?
+                }
+                10 => rewrite_code(payload, prepared, &bodies)?,
+                _ => continue,
+            };
+        }
+        if !table_added {
+            let table = append_entries(&[0], &[table_type()])?;
+            let at = sections
+                .iter()
+                .position(|s| s.0 > 4 && s.0 != 0)
+                .unwrap_or(sections.len());
+            sections.insert(at, (4, table));
+        }
+        if new_types.len() > 0 && !sections.iter().any(|s| s.0 == 1) {
+            bail!("wasm module has no type section");
+        }
+
+        Ok(binary::module(wasm, sections))
+    }
+}
+
+const I32: u8 = 0x7f;
+
+const BLOCK: u8 = 0x02;
+const IF: u8 = 0x04;
+const END: u8 = 0x0b;
+const BR_IF: u8 = 0x0d;
+const CALL: u8 = 0x10;
+const LOCAL_GET: u8 = 0x20;
+const LOCAL_SET: u8 = 0x21;
+const LOCAL_TEE: u8 = 0x22;
+const TABLE_GET: u8 = 0x25;
+const TABLE_SET: u8 = 0x26;
+const I32_CONST: u8 = 0x41;
+const I32_EQZ: u8 = 0x45;
+const I32_LT_U: u8 = 0x49;
+const I32_SHL: u8 = 0x74;
+const I32_SHR_U: u8 = 0x76;
+const REF_NULL: u8 = 0xd0;
+const EMPTY_BLOCK: u8 = 0x40;
+
+/// A function body under construction.
+struct Body {
+    locals: Vec<u8>,
+    code: Vec<u8>,
+}
+
+impl Body {
+    fn new(locals: &[(u32, u8)]) -> Body {
+        let mut body = Body {
+            locals: Vec::new(),
+            code: Vec::new(),
+        };
+        write_u32(&mut body.locals, locals.len() as u32);
+        for &(count, ty) in locals {
+            write_u32(&mut body.locals, count);
+            body.locals.push(ty);
+        }
+        body
+    }
+
+    fn op(&mut self, op: u8) -> &mut Body {
+        self.code.push(op);
+        self
+    }
+
+    fn op_u32(&mut self, op: u8, imm: u32) -> &mut Body {
+        self.code.push(op);
+        write_u32(&mut self.code, imm);
+        self
+    }
+
+    fn i32_const(&mut self, val: i32) -> &mut Body {
+        self.code.push(I32_CONST);
+        write_i32(&mut self.code, val);
+        self
+    }
+
+    fn table_size(&mut self, table: u32) -> &mut Body {
+        self.code.extend(&[0xfc, 16]);
+        write_u32(&mut self.code, table);
+        self
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        let mut ret = self.locals.clone();
+        ret.extend(&self.code);
+        ret.push(END);
+        ret
+    }
+}
+
+impl Prepared {
+    /// `get(idx: i32) -> anyref`, looking up the value at `idx` in the table,
+    /// creating it first if need be.
+    fn get_body(&self) -> Vec<u8> {
+        Body::new(&[])
+            .table_size(self.table)
+            .op(I32_EQZ)
+            .op(IF)
+            .op(EMPTY_BLOCK)
+            .op_u32(CALL, self.init)
+            .op(END)
+            .op_u32(LOCAL_GET, 0)
+            .i32_const(1)
+            .op(I32_SHR_U)
+            .op_u32(TABLE_GET, self.table)
+            .finish()
+    }
+
+    /// `add(val: anyref) -> i32`, storing `val` in a new slot and returning its
+    /// index.
+    fn add_body(&self) -> Vec<u8> {
+        Body::new(&[(1, I32)])
+            .op_u32(CALL, self.alloc)
+            .op_u32(LOCAL_TEE, 1)
+            .op_u32(LOCAL_GET, 0)
+            .op_u32(TABLE_SET, self.table)
+            .op_u32(LOCAL_GET, 1)
+            .i32_const(1)
+            .op(I32_SHL)
+            .finish()
+    }
+
+    /// `drop(idx: i32)`, freeing the slot at `idx` unless it's reserved.
+    fn drop_body(&self) -> Vec<u8> {
+        Body::new(&[])
+            .op(BLOCK)
+            .op(EMPTY_BLOCK)
+            .op_u32(LOCAL_GET, 0)
+            .i32_const(8)
+            .op(I32_LT_U)
+            .op_u32(BR_IF, 0)
+            .op_u32(LOCAL_GET, 0)
+            .i32_const(1)
+            .op(I32_SHR_U)
+            .op(REF_NULL)
+            .op(ANYREF)
+            .op_u32(TABLE_SET, self.table)
+            .op_u32(LOCAL_GET, 0)
+            .i32_const(1)
+            .op(I32_SHR_U)
+            .op_u32(CALL, self.dealloc)
+            .op(END)
+            .finish()
+    }
+
+    /// Takes the arguments Rust passes to an import, looking up JS values in
+    /// the table, and calls the real import `func`.
+    fn import_shim_body(&self, shim: &Shim, func: u32) -> Vec<u8> {
+        let mut body = Body::new(&[]);
+        for i in 0..shim.params.len() {
+            body.op_u32(LOCAL_GET, i as u32);
+            if shim.sig.args.iter().any(|a| a.0 == i) {
+                body.op_u32(CALL, self.get);
+            }
+        }
+        body.op_u32(CALL, func);
+        if shim.sig.ret.is_some() {
+            body.op_u32(CALL, self.add);
+        }
+        for &(i, pass) in shim.sig.args.iter() {
+            if pass == Pass::Owned {
+                body.op_u32(LOCAL_GET, i as u32).op_u32(CALL, self.drop);
+            }
+        }
+        body.finish()
+    }
+
+    /// Takes the arguments JS passes to an export, storing JS values in the
+    /// table, and calls the real export `func`.
+    fn export_shim_body(&self, shim: &Shim, func: u32) -> Vec<u8> {
+        let params = shim.params.len() as u32;
+        let slots = shim.sig.args.len() as u32;
+        // One local for the index of each argument, and one for the result.
+        let mut body = Body::new(&[(slots + 1, I32)]);
+        for (k, &(i, _)) in shim.sig.args.iter().enumerate() {
+            body.op_u32(LOCAL_GET, i as u32)
+                .op_u32(CALL, self.add)
+                .op_u32(LOCAL_SET, params + k as u32);
+        }
+        for i in 0..shim.params.len() {
+            match shim.sig.args.iter().position(|a| a.0 == i) {
+                Some(k) => body.op_u32(LOCAL_GET, params + k as u32),
+                None => body.op_u32(LOCAL_GET, i as u32),
+            };
+        }
+        body.op_u32(CALL, func);
+        match shim.sig.ret {
+            Some(Pass::Owned) => {
+                body.op_u32(LOCAL_TEE, params + slots)
+                    .op_u32(CALL, self.get)
+                    .op_u32(LOCAL_GET, params + slots)
+                    .op_u32(CALL, self.drop);
+            }
+            Some(Pass::Borrowed) => {
+                body.op_u32(CALL, self.get);
+            }
+            None => {}
+        }
+        for (k, &(_, pass)) in shim.sig.args.iter().enumerate() {
+            if pass == Pass::Borrowed {
+                body.op_u32(LOCAL_GET, params + k as u32)
+                    .op_u32(CALL, self.drop);
+            }
+        }
+        body.finish()
+    }
+}
+
+fn table_type() -> Vec<u8> {
+    // A table of `anyref`s with no initial size and no maximum.
+    vec![ANYREF, 0x00, 0x00]
+}
+
+fn value_type(ty: ValueType) -> u8 {
+    match ty {
+        ValueType::I32 => 0x7f,
+        ValueType::I64 => 0x7e,
+        ValueType::F32 => 0x7d,
+        ValueType::F64 => 0x7c,
+    }
+}
+
+/// Changes the type of the function imports at the given positions.
+fn rewrite_imports(payload: &[u8], types: &HashMap<usize, u32>) -> Result<Vec<u8>, Error> {
+    let mut pos = 0;
+    let count = read_u32(payload, &mut pos)?;
+    let mut ret = Vec::new();
+    write_u32(&mut ret, count);
+    for i in 0..count as usize {
+        let start = pos;
+        for _ in 0..2 {
+            let len = read_u32(payload, &mut pos)? as usize;
+            pos += len;
+        }
+        let kind = *byte(payload, pos)?;
+        pos += 1;
+        ret.extend(&payload[start..pos]);
+        let desc = pos;
+        match kind {
+            0 => {
+                let ty = read_u32(payload, &mut pos)?;
+                write_u32(&mut ret, *types.get(&i).unwrap_or(&ty));
+                continue;
+            }
+            1 => {
+                pos += 1;
+                read_limits(payload, &mut pos)?;
+            }
+            2 => read_limits(payload, &mut pos)?,
+            3 => pos += 2,
+            _ => bail!("unknown import kind {}", kind),
+        }
+        ret.extend(&payload[desc..pos]);
+    }
+    Ok(ret)
+}
+
+/// Sets the types of functions in the function section.
+fn rewrite_functions(
+    payload: &[u8],
+    prepared: &Prepared,
+    types: &HashMap<u32, u32>,
+) -> Result<Vec<u8>, Error> {
+    let mut pos = 0;
+    let count = read_u32(payload, &mut pos)?;
+    let mut ret = Vec::new();
+    write_u32(&mut ret, count);
+    for i in 0..count {
+        let ty = read_u32(payload, &mut pos)?;
+        let func = prepared.imported_functions + i;
+        write_u32(&mut ret, *types.get(&func).unwrap_or(&ty));
+    }
+    Ok(ret)
+}
+
+/// Replaces the bodies of functions in the code section.
+fn rewrite_code(
+    payload: &[u8],
+    prepared: &Prepared,
+    bodies: &HashMap<u32, Vec<u8>>,
+) -> Result<Vec<u8>, Error> {
+    let mut pos = 0;
+    let count = read_u32(payload, &mut pos)?;
+    let mut ret = Vec::new();
+    write_u32(&mut ret, count);
+    for i in 0..count {
+        let len = read_u32(payload, &mut pos)? as usize;
+        let body = match bodies.get(&(prepared.imported_functions + i)) {
+            Some(body) => &body[..],
+            None => &payload[pos..pos + len],
+        };
+        write_u32(&mut ret, body.len() as u32);
+        ret.extend(body);
+        pos += len;
+    }
+    Ok(ret)
+}
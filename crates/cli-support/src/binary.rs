@@ -0,0 +1,116 @@
+//! Helpers for editing serialized wasm modules directly, for the changes made
+//! by `--anyref` and `--threads` which `parity_wasm` can't represent.
+
+use failure::Error;
+
+/// Splits `wasm` into the ids and payloads of its sections.
+pub fn sections(wasm: &[u8]) -> Result<Vec<(u8, Vec<u8>)>, Error> {
+    if wasm.len() < 8 {
+        bail!("invalid wasm module");
+    }
+    let mut sections = Vec::new();
+    let mut pos = 8;
+    while pos < wasm.len() {
+        let id = wasm[pos];
+        pos += 1;
+        let len = read_u32(wasm, &mut pos)? as usize;
+        if pos + len > wasm.len() {
+            bail!("invalid wasm section");
+        }
+        sections.push((id, wasm[pos..pos + len].to_vec()));
+        pos += len;
+    }
+    Ok(sections)
+}
+
+/// Puts `sections` back together into a module with the header of `wasm`.
+pub fn module(wasm: &[u8], sections: Vec<(u8, Vec<u8>)>) -> Vec<u8> {
+    let mut ret = wasm[..8].to_vec();
+    for (id, payload) in sections {
+        ret.push(id);
+        write_u32(&mut ret, payload.len() as u32);
+        ret.extend(payload);
+    }
+    ret
+}
+
+/// Appends already encoded `entries` to the vector of entries in `payload`.
+pub fn append_entries(payload: &[u8], entries: &[Vec<u8>]) -> Result<Vec<u8>, Error> {
+    let mut pos = 0;
+    let count = read_u32(payload, &mut pos)?;
+    let mut ret = Vec::new();
+    write_u32(&mut ret, count + entries.len() as u32);
+    ret.extend(&payload[pos..]);
+    for entry in entries {
+        ret.extend(entry);
+    }
+    Ok(ret)
+}
+
+pub fn byte(bytes: &[u8], pos: usize) -> Result<&u8, Error> {
+    match bytes.get(pos) {
+        Some(b) => Ok(b),
+        None => bail!("unexpected end of wasm module"),
+    }
+}
+
+pub fn read_name<'a>(bytes: &'a [u8], pos: &mut usize) -> Result<&'a [u8], Error> {
+    let len = read_u32(bytes, pos)? as usize;
+    let start = *pos;
+    if start + len > bytes.len() {
+        bail!("unexpected end of wasm module");
+    }
+    *pos += len;
+    Ok(&bytes[start..start + len])
+}
+
+pub fn read_limits(bytes: &[u8], pos: &mut usize) -> Result<(), Error> {
+    let flags = *byte(bytes, *pos)?;
+    *pos += 1;
+    read_u32(bytes, pos)?;
+    if flags & 1 != 0 {
+        read_u32(bytes, pos)?;
+    }
+    Ok(())
+}
+
+pub fn read_u32(bytes: &[u8], pos: &mut usize) -> Result<u32, Error> {
+    let mut ret = 0u32;
+    let mut shift = 0;
+    loop {
+        let b = *byte(bytes, *pos)?;
+        *pos += 1;
+        ret |= ((b & 0x7f) as u32) << shift;
+        if b & 0x80 == 0 {
+            return Ok(ret);
+        }
+        shift += 7;
+        if shift > 28 {
+            bail!("invalid LEB128 integer in wasm module");
+        }
+    }
+}
+
+pub fn write_u32(dst: &mut Vec<u8>, mut val: u32) {
+    loop {
+        let b = (val & 0x7f) as u8;
+        val >>= 7;
+        if val == 0 {
+            dst.push(b);
+            return;
+        }
+        dst.push(b | 0x80);
+    }
+}
+
+pub fn write_i32(dst: &mut Vec<u8>, mut val: i32) {
+    loop {
+        let b = (val & 0x7f) as u8;
+        val >>= 7;
+        if (val == 0 && b & 0x40 == 0) || (val == -1 && b & 0x40 != 0) {
+            dst.push(b);
+            return;
+        }
+        dst.push(b | 0x80);
+    }
+}
@@ -33,6 +33,9 @@ tys! {
     ENUM
     RUST_STRUCT
     CHAR
+    OPTIONAL
+    OBJECT
+    CLAMPED
 }
 
 #[derive(Debug)]
@@ -59,6 +62,9 @@ pub enum Descriptor {
     Enum,
     RustStruct(String),
     Char,
+    Optional(Box<Descriptor>),
+    Object(String),
+    Clamped(Box<Descriptor>),
 }
 
 #[derive(Debug)]
@@ -85,6 +91,7 @@ pub enum VectorKind {
     U64,
     F32,
     F64,
+    ClampedU8,
     String,
     Anyref,
 }
@@ -125,6 +132,14 @@ impl Descriptor {
                 Descriptor::RustStruct(name)
             }
             CHAR => Descriptor::Char,
+            OPTIONAL => Descriptor::Optional(Box::new(Descriptor::_decode(data))),
+            OBJECT => {
+                let name = (0..get(data))
+                    .map(|_| char::from_u32(get(data)).unwrap())
+                    .collect();
+                Descriptor::Object(name)
+            }
+            CLAMPED => Descriptor::Clamped(Box::new(Descriptor::_decode(data))),
             other => panic!("unknown descriptor: {}", other),
         }
     }
@@ -187,9 +202,24 @@ impl Descriptor {
         }
     }
 
+    /// The name of a `#[derive(JsObject)]` struct, which is passed like an
+    /// owned `JsValue` but typed as its interface in TypeScript.
+    pub fn object(&self) -> Option<&str> {
+        match *self {
+            Descriptor::Object(ref name) => Some(name),
+            _ => None,
+        }
+    }
+
     pub fn vector_kind(&self) -> Option<VectorKind> {
         let inner = match *self {
             Descriptor::String => return Some(VectorKind::String),
+            Descriptor::Clamped(ref d) => {
+                return match d.vector_kind() {
+                    Some(VectorKind::U8) => Some(VectorKind::ClampedU8),
+                    _ => None,
+                }
+            }
             Descriptor::Vector(ref d) => &**d,
             Descriptor::Ref(ref d) => match **d {
                 Descriptor::Slice(ref d) => &**d,
@@ -245,6 +275,7 @@ impl Descriptor {
     pub fn is_by_ref(&self) -> bool {
         match *self {
             Descriptor::Ref(_) | Descriptor::RefMut(_) => true,
+            Descriptor::Clamped(ref d) => d.is_by_ref(),
             _ => false,
         }
     }
@@ -252,6 +283,7 @@ impl Descriptor {
     pub fn is_mut_ref(&self) -> bool {
         match *self {
             Descriptor::RefMut(_) => true,
+            Descriptor::Clamped(ref d) => d.is_mut_ref(),
             _ => false,
         }
     }
@@ -302,6 +334,7 @@ impl VectorKind {
             VectorKind::U64 => "BigUint64Array",
             VectorKind::F32 => "Float32Array",
             VectorKind::F64 => "Float64Array",
+            VectorKind::ClampedU8 => "Uint8ClampedArray",
             VectorKind::Anyref => "any[]",
         }
     }
@@ -319,6 +352,7 @@ impl VectorKind {
             VectorKind::U64 => 8,
             VectorKind::F32 => 4,
             VectorKind::F64 => 8,
+            VectorKind::ClampedU8 => 1,
             VectorKind::Anyref => 4,
         }
     }
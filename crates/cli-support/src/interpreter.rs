@@ -0,0 +1,253 @@
+//! A tiny interpreter for the `__wbindgen_describe_*` functions.
+//!
+//! Each `#[wasm_bindgen]` export and import comes with a descriptor function
+//! which, when executed, describes the types involved by calling the
+//! `__wbindgen_describe` import with one `u32` at a time. Descriptor functions
+//! are simple straight-line code, so rather than instantiating the whole
+//! module in a full interpreter we only support the handful of instructions
+//! they're compiled to: constants, locals, the stack pointer global, stack
+//! frame loads and stores, and calls.
+
+use std::collections::HashMap;
+
+use failure::Error;
+use parity_wasm::elements::*;
+
+const DESCRIBE_MODULE: &str = "__wbindgen_placeholder__";
+const DESCRIBE_IMPORT: &str = "__wbindgen_describe";
+const DESCRIBE_PREFIX: &str = "__wbindgen_describe_";
+
+/// Calls within descriptors are nested, but only as deep as the types
+/// involved, so anything deeper is certainly runaway recursion.
+const MAX_CALL_DEPTH: usize = 512;
+
+/// Runs every descriptor function exported from `module`, returning what each
+/// of them described keyed by export name.
+pub fn interpret_descriptors(module: &Module) -> Result<HashMap<String, Vec<u32>>, Error> {
+    let mut interpreter = Interpreter::new(module)?;
+    let mut ret = HashMap::new();
+    let exports = match module.export_section() {
+        Some(s) => s.entries(),
+        None => return Ok(ret),
+    };
+    for export in exports {
+        if !export.field().starts_with(DESCRIBE_PREFIX) {
+            continue;
+        }
+        let idx = match *export.internal() {
+            Internal::Function(idx) => idx,
+            _ => continue,
+        };
+        interpreter.descriptor.clear();
+        interpreter.mem.clear();
+        interpreter
+            .call(idx, Vec::new(), 0)
+            .map_err(|e| format_err!("failed to run descriptor `{}`: {}", export.field(), e))?;
+        ret.insert(export.field().to_string(), interpreter.descriptor.clone());
+    }
+    Ok(ret)
+}
+
+struct Interpreter<'a> {
+    module: &'a Module,
+    /// The number of imported functions, which precede those defined in the
+    /// module in the function index space.
+    imported_functions: u32,
+    /// The function index of the `__wbindgen_describe` import.
+    describe: Option<u32>,
+    /// The number of imported globals, which precede those defined in the
+    /// module in the global index space.
+    imported_globals: u32,
+    globals: Vec<i32>,
+    /// The only memory descriptors use is their stack frames, so memory is
+    /// modeled as a sparse map of 32-bit words. Everything else reads as zero.
+    mem: HashMap<u32, i32>,
+    descriptor: Vec<u32>,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(module: &'a Module) -> Result<Interpreter<'a>, Error> {
+        let mut imported_functions = 0;
+        let mut imported_globals = 0;
+        let mut describe = None;
+        if let Some(s) = module.import_section() {
+            for entry in s.entries() {
+                match *entry.external() {
+                    External::Function(_) => {
+                        if entry.module() == DESCRIBE_MODULE && entry.field() == DESCRIBE_IMPORT {
+                            describe = Some(imported_functions);
+                        }
+                        imported_functions += 1;
+                    }
+                    External::Global(_) => imported_globals += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut globals = Vec::new();
+        if let Some(s) = module.global_section() {
+            for entry in s.entries() {
+                let value = match entry.init_expr().code().first() {
+                    Some(&Instruction::I32Const(x)) => x,
+                    // Globals of other types are never used by descriptors
+                    _ => 0,
+                };
+                globals.push(value);
+            }
+        }
+
+        Ok(Interpreter {
+            module,
+            imported_functions,
+            describe,
+            imported_globals,
+            globals,
+            mem: HashMap::new(),
+            descriptor: Vec::new(),
+        })
+    }
+
+    fn call(&mut self, idx: u32, args: Vec<i32>, depth: usize) -> Result<Option<i32>, Error> {
+        if depth > MAX_CALL_DEPTH {
+            bail!("too many nested calls");
+        }
+        if Some(idx) == self.describe {
+            if args.len() != 1 {
+                bail!("`{}` must be called with one argument", DESCRIBE_IMPORT);
+            }
+            self.descriptor.push(args[0] as u32);
+            return Ok(None);
+        }
+        if idx < self.imported_functions {
+            bail!("calls imported function {}, only `{}` may be called", idx, DESCRIBE_IMPORT);
+        }
+
+        let defined = (idx - self.imported_functions) as usize;
+        let module = self.module;
+        let body = module
+            .code_section()
+            .and_then(|s| s.bodies().get(defined))
+            .ok_or_else(|| format_err!("function {} doesn't exist", idx))?;
+        let ty = self.function_type(defined)?;
+
+        let mut locals = args;
+        for local in body.locals() {
+            for _ in 0..local.count() {
+                locals.push(0);
+            }
+        }
+
+        let mut stack = Vec::new();
+        for instr in body.code().elements() {
+            match *instr {
+                Instruction::I32Const(x) => stack.push(x),
+                Instruction::GetLocal(i) => stack.push(*local(&mut locals, i)?),
+                Instruction::SetLocal(i) => *local(&mut locals, i)? = pop(&mut stack)?,
+                Instruction::TeeLocal(i) => {
+                    let value = *stack.last().ok_or_else(|| format_err!("empty stack"))?;
+                    *local(&mut locals, i)? = value;
+                }
+                Instruction::GetGlobal(i) => stack.push(*self.global(i)?),
+                Instruction::SetGlobal(i) => *self.global(i)? = pop(&mut stack)?,
+                Instruction::I32Add => {
+                    let (a, b) = (pop(&mut stack)?, pop(&mut stack)?);
+                    stack.push(b.wrapping_add(a));
+                }
+                Instruction::I32Sub => {
+                    let (a, b) = (pop(&mut stack)?, pop(&mut stack)?);
+                    stack.push(b.wrapping_sub(a));
+                }
+                Instruction::I32Load(_, offset) => {
+                    let addr = (pop(&mut stack)? as u32).wrapping_add(offset);
+                    stack.push(*self.mem.get(&addr).unwrap_or(&0));
+                }
+                Instruction::I32Store(_, offset) => {
+                    let value = pop(&mut stack)?;
+                    let addr = (pop(&mut stack)? as u32).wrapping_add(offset);
+                    self.mem.insert(addr, value);
+                }
+                Instruction::Call(f) => {
+                    let params = self.function_type_of_index(f)?.params().len();
+                    if stack.len() < params {
+                        bail!("not enough arguments on the stack to call {}", f);
+                    }
+                    let args = stack.split_off(stack.len() - params);
+                    if let Some(ret) = self.call(f, args, depth + 1)? {
+                        stack.push(ret);
+                    }
+                }
+                Instruction::Drop => {
+                    pop(&mut stack)?;
+                }
+                Instruction::Nop => {}
+                Instruction::Return | Instruction::End => break,
+                ref other => bail!("unsupported instruction `{:?}`", other),
+            }
+        }
+
+        Ok(match ty.return_type() {
+            Some(_) => Some(pop(&mut stack)?),
+            None => None,
+        })
+    }
+
+    /// The type of the `defined`th function defined in the module.
+    fn function_type(&self, defined: usize) -> Result<&'a FunctionType, Error> {
+        let module = self.module;
+        let type_ref = module
+            .function_section()
+            .and_then(|s| s.entries().get(defined))
+            .ok_or_else(|| format_err!("function {} has no type", defined))?
+            .type_ref();
+        match module.type_section().and_then(|s| s.types().get(type_ref as usize)) {
+            Some(&Type::Function(ref ty)) => Ok(ty),
+            None => bail!("type {} doesn't exist", type_ref),
+        }
+    }
+
+    /// The type of the function at `idx` in the function index space, which
+    /// includes imports.
+    fn function_type_of_index(&self, idx: u32) -> Result<&'a FunctionType, Error> {
+        if idx >= self.imported_functions {
+            return self.function_type((idx - self.imported_functions) as usize);
+        }
+        let module = self.module;
+        let entry = module
+            .import_section()
+            .into_iter()
+            .flat_map(|s| s.entries())
+            .filter(|e| match *e.external() {
+                External::Function(_) => true,
+                _ => false,
+            })
+            .nth(idx as usize);
+        let type_ref = match entry.map(|e| e.external()) {
+            Some(&External::Function(type_ref)) => type_ref,
+            _ => bail!("imported function {} doesn't exist", idx),
+        };
+        match module.type_section().and_then(|s| s.types().get(type_ref as usize)) {
+            Some(&Type::Function(ref ty)) => Ok(ty),
+            None => bail!("type {} doesn't exist", type_ref),
+        }
+    }
+
+    fn global(&mut self, idx: u32) -> Result<&mut i32, Error> {
+        if idx < self.imported_globals {
+            bail!("uses imported global {}", idx);
+        }
+        self.globals
+            .get_mut((idx - self.imported_globals) as usize)
+            .ok_or_else(|| format_err!("global {} doesn't exist", idx))
+    }
+}
+
+fn local(locals: &mut Vec<i32>, idx: u32) -> Result<&mut i32, Error> {
+    locals
+        .get_mut(idx as usize)
+        .ok_or_else(|| format_err!("local {} doesn't exist", idx))
+}
+
+fn pop(stack: &mut Vec<i32>) -> Result<i32, Error> {
+    stack.pop().ok_or_else(|| format_err!("empty stack"))
+}
@@ -1,6 +1,7 @@
 use failure::Error;
 
 use super::Context;
+use anyref::{Pass, Signature};
 use descriptor::{Descriptor, Function};
 
 /// Helper struct for manufacturing a shim in JS used to translate JS types to
@@ -37,10 +38,21 @@ pub struct Js2Rust<'a, 'b: 'a> {
     /// Name of the JS shim/function that we're generating, primarily for
     /// TypeScript right now.
     js_name: String,
+
+    /// Whether JS values are passed to Rust directly as `anyref`s, see
+    /// `anyref`.
+    anyref: bool,
+
+    /// Rust arguments which are passed as `anyref`s.
+    anyref_args: Vec<(String, Pass)>,
+
+    /// How a JS value returned as an `anyref` is passed, if any.
+    anyref_ret: Option<Pass>,
 }
 
 impl<'a, 'b> Js2Rust<'a, 'b> {
     pub fn new(js_name: &str, cx: &'a mut Context<'b>) -> Js2Rust<'a, 'b> {
+        let anyref = cx.config.anyref;
         Js2Rust {
             cx,
             js_name: js_name.to_string(),
@@ -51,6 +63,9 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
             arg_idx: 0,
             ret_ty: String::new(),
             ret_expr: String::new(),
+            anyref,
+            anyref_args: Vec::new(),
+            anyref_ret: None,
         }
     }
 
@@ -66,7 +81,10 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
 
     /// Flag this shim as a method call into Rust, so the first Rust argument
     /// passed should be `this.ptr`.
-    pub fn method(&mut self, method: bool, consumed: bool) -> &mut Self {
+    ///
+    /// `class` is the name of the exported class, which is used rather than
+    /// `this.constructor.name` as minifiers may rename the JS class.
+    pub fn method(&mut self, class: &str, method: bool, consumed: bool) -> &mut Self {
         if method {
             self.prelude(
                 "if (this.ptr === 0) {
@@ -78,6 +96,14 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
                     const ptr = this.ptr;\n\
                     this.ptr = 0;\n\
                 ");
+                if self.cx.config.debug {
+                    self.cx.expose_live_class_ptrs();
+                    self.prelude(&format!("classPtrs('{}').delete(ptr);", class));
+                }
+                if self.cx.config.weak_refs {
+                    self.cx.expose_class_registry();
+                    self.prelude("classRegistry.unregister(this);");
+                }
                 self.rust_arguments.insert(0, "ptr".to_string());
             } else {
                 self.rust_arguments.insert(0, "this.ptr".to_string());
@@ -86,6 +112,15 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
         self
     }
 
+    /// Whether JS values are passed directly as `anyref`s with `--anyref`,
+    /// which is only possible when invoking an export the wasm module gets a
+    /// shim for. Other functions, like closures invoked through the function
+    /// table, take indices into the table of JS values.
+    pub fn anyref(&mut self, anyref: bool) -> &mut Self {
+        self.anyref = anyref && self.cx.config.anyref;
+        self
+    }
+
     /// Add extra processing to the prelude of this shim.
     pub fn prelude(&mut self, s: &str) -> &mut Self {
         for line in s.lines() {
@@ -191,6 +226,14 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
                     i = i,
                     arg = name
                 ));
+                if self.cx.config.debug {
+                    self.cx.expose_live_class_ptrs();
+                    self.prelude(&format!("classPtrs('{}').delete(ptr{});", s, i));
+                }
+                if self.cx.config.weak_refs {
+                    self.cx.expose_class_registry();
+                    self.prelude(&format!("classRegistry.unregister({});", name));
+                }
                 self.rust_arguments.push(format!("ptr{}", i));
             }
             return Ok(self);
@@ -214,8 +257,6 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
             } else {
                 self.cx.expose_uint64_cvt_shim()
             };
-            self.cx.expose_uint32_memory();
-            self.cx.expose_global_argument_ptr()?;
             self.js_arguments.push((name.clone(), "BigInt".to_string()));
             self.prelude(&format!(
                 "\
@@ -234,8 +275,18 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
 
         if arg.is_ref_anyref() {
             self.js_arguments.push((name.clone(), "any".to_string()));
+            if self.anyref {
+                self.anyref_args.push((name.clone(), Pass::Borrowed));
+                self.rust_arguments.push(name);
+                return Ok(self);
+            }
             self.cx.expose_borrowed_objects();
-            self.finally("stack.pop();");
+            if self.cx.config.anyref {
+                self.cx.expose_drop_ref();
+                self.finally("dropRef(stack.pop());");
+            } else {
+                self.finally("stack.pop();");
+            }
             self.rust_arguments
                 .push(format!("addBorrowedObject({})", name));
             return Ok(self);
@@ -260,8 +311,15 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
                 self.js_arguments.push((name.clone(), "string".to_string()));
                 self.rust_arguments.push(format!("{}.codePointAt(0)", name))
             }
-            Descriptor::Anyref => {
-                self.js_arguments.push((name.clone(), "any".to_string()));
+            Descriptor::Anyref | Descriptor::Object(_) if self.anyref => {
+                let ty = arg.object().unwrap_or("any").to_string();
+                self.js_arguments.push((name.clone(), ty));
+                self.anyref_args.push((name.clone(), Pass::Owned));
+                self.rust_arguments.push(name);
+            }
+            Descriptor::Anyref | Descriptor::Object(_) => {
+                let ty = arg.object().unwrap_or("any").to_string();
+                self.js_arguments.push((name.clone(), ty));
                 self.cx.expose_add_heap_object();
                 self.rust_arguments.push(format!("addHeapObject({})", name));
             }
@@ -280,6 +338,13 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
             }
         };
 
+        if ty.is_ref_anyref() && self.anyref {
+            self.ret_ty = "any".to_string();
+            self.anyref_ret = Some(Pass::Borrowed);
+            self.ret_expr = format!("return RET;");
+            return Ok(self);
+        }
+
         if ty.is_ref_anyref() {
             self.ret_ty = "any".to_string();
             self.cx.expose_get_object();
@@ -294,10 +359,10 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
         if let Some(ty) = ty.vector_kind() {
             self.ret_ty = ty.js_ty().to_string();
             let f = self.cx.expose_get_vector_from_wasm(ty);
-            self.cx.expose_global_argument_ptr()?;
+            self.cx.expose_global_ret_ptr()?;
             self.cx.expose_uint32_memory();
             self.cx.require_internal_export("__wbindgen_free")?;
-            self.prelude("const retptr = globalArgumentPtr();");
+            self.prelude("const retptr = globalRetPtr();");
             self.rust_arguments.insert(0, "retptr".to_string());
             self.ret_expr = format!(
                 "\
@@ -329,7 +394,7 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
 
         if let Some(signed) = ty.get_64bit() {
             self.ret_ty = "BigInt".to_string();
-            self.cx.expose_global_argument_ptr()?;
+            self.cx.expose_global_ret_ptr()?;
             let f = if signed {
                 self.cx.expose_int64_memory();
                 "getInt64Memory"
@@ -337,7 +402,7 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
                 self.cx.expose_uint64_memory();
                 "getUint64Memory"
             };
-            self.prelude("const retptr = globalArgumentPtr();");
+            self.prelude("const retptr = globalRetPtr();");
             self.rust_arguments.insert(0, "retptr".to_string());
             self.ret_expr = format!(
                 "\
@@ -358,8 +423,13 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
                 self.ret_ty = "string".to_string();
                 self.ret_expr = format!("return String.fromCodePoint(RET);")
             }
-            Descriptor::Anyref => {
-                self.ret_ty = "any".to_string();
+            Descriptor::Anyref | Descriptor::Object(_) if self.anyref => {
+                self.ret_ty = ty.object().unwrap_or("any").to_string();
+                self.anyref_ret = Some(Pass::Owned);
+                self.ret_expr = format!("return RET;");
+            }
+            Descriptor::Anyref | Descriptor::Object(_) => {
+                self.ret_ty = ty.object().unwrap_or("any").to_string();
                 self.cx.expose_take_object();
                 self.ret_expr = format!("return takeObject(RET);");
             }
@@ -377,7 +447,28 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
     /// Returns two strings, the first of which is the JS expression for the
     /// generated function shim and the second is a TypeScript signature of the
     /// JS expression.
-    pub fn finish(&self, prefix: &str, invoc: &str) -> (String, String) {
+    ///
+    /// With `--anyref` this also records which arguments of the invoked
+    /// export are passed as `anyref`s.
+    pub fn finish(&mut self, prefix: &str, invoc: &str) -> (String, String) {
+        if self.anyref && invoc.starts_with("wasm.") {
+            let sig = Signature {
+                args: self
+                    .anyref_args
+                    .iter()
+                    .map(|&(ref name, pass)| {
+                        let i = self.rust_arguments.iter().position(|a| a == name);
+                        (i.unwrap(), pass)
+                    })
+                    .collect(),
+                ret: self.anyref_ret,
+            };
+            self.cx
+                .anyref
+                .exports
+                .insert(invoc["wasm.".len()..].to_string(), sig);
+        }
+
         let js_args = self
             .js_arguments
             .iter()
@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use std::mem;
+use std::path::Path;
 
 use failure::{Error, ResultExt};
 use parity_wasm;
@@ -10,7 +11,10 @@ use shared;
 use wasm_gc;
 
 use super::Bindgen;
+use anyref;
 use descriptor::{Descriptor, VectorKind};
+use source_map;
+use threads;
 
 mod js2rust;
 use self::js2rust::Js2Rust;
@@ -31,6 +35,8 @@ pub struct Context<'a> {
     pub function_table_needed: bool,
     pub run_descriptor: &'a Fn(&str) -> Option<Vec<u32>>,
     pub module_versions: Vec<(String, String)>,
+    pub source_locations: Vec<shared::Location>,
+    pub anyref: anyref::Transform,
 }
 
 #[derive(Default)]
@@ -73,13 +79,20 @@ impl<'a> Context<'a> {
                 format!("__exports.{} = {};\n", name, contents)
             }
         } else {
-            if contents.starts_with("function") {
+            let mut global = if contents.starts_with("function") {
                 format!("export function {}{}\n", name, &contents[8..])
             } else if contents.starts_with("class") {
                 format!("export {}\n", contents)
             } else {
                 format!("export const {} = {};\n", name, contents)
+            };
+            // With `--target web` and `--target deno` the module instantiates
+            // wasm itself, so it also needs an object of its exports to use as
+            // the wasm imports.
+            if self.config.web || self.config.deno {
+                global.push_str(&format!("__exports.{0} = {0};\n", name));
             }
+            global
         };
         self.global(&global);
     }
@@ -103,35 +116,51 @@ impl<'a> Context<'a> {
     }
 
     pub fn finalize(&mut self, module_name: &str) -> Result<(String, String), Error> {
+        if self.config.threads {
+            if self.config.anyref {
+                bail!("`--threads` can't be combined with `--anyref`");
+            }
+            if !self.config.web && !self.config.no_modules {
+                bail!(
+                    "`--threads` is only supported with `--target web` and `--target no-modules`"
+                );
+            }
+        }
+        threads::implement_thread_ptr(self.module, self.config.threads)?;
+        if self.config.threads {
+            // The generated JS starts and exits threads through these.
+            self.require_internal_export("__wbindgen_thread_alloc")?;
+            self.require_internal_export("__wbindgen_thread_free")?;
+            self.require_internal_export(threads::THREAD_PTR_SET)?;
+            self.require_internal_export(threads::STACK_POINTER_SET)?;
+            if threads::has_tls(self.module) {
+                self.require_internal_export(threads::TLS_BASE_SET)?;
+            }
+        }
+        if self.config.anyref {
+            // The wasm shims added for `--anyref` manage the table of JS
+            // values through these.
+            self.require_internal_export("__wbindgen_anyref_table_alloc")?;
+            self.require_internal_export("__wbindgen_anyref_table_dealloc")?;
+            self.require_internal_export("__wbindgen_anyref_table_init")?;
+            if self.config.debug {
+                self.require_internal_export("__wbindgen_anyref_heap_live_count")?;
+            }
+        }
         self.write_classes()?;
+        if self.config.debug {
+            self.expose_heap_stats();
+        }
 
         self.bind("__wbindgen_object_clone_ref", &|me| {
             me.expose_add_heap_object();
             me.expose_get_object();
-            let bump_cnt = if me.config.debug {
-                String::from(
-                    "
-                    if (typeof(val) === 'number') throw new Error('corrupt slab');
-                    val.cnt += 1;
-                    ",
-                )
-            } else {
-                String::from("val.cnt += 1;")
-            };
-            Ok(format!(
+            Ok(String::from(
                 "
-                function(idx) {{
-                    // If this object is on the stack promote it to the heap.
-                    if ((idx & 1) === 1) return addHeapObject(getObject(idx));
-
-                    // Otherwise if the object is on the heap just bump the
-                    // refcount and move on
-                    const val = slab[idx >> 1];
-                    {}
-                    return idx;
-                }}
+                function(idx) {
+                    return addHeapObject(getObject(idx));
+                }
                 ",
-                bump_cnt
             ))
         })?;
 
@@ -146,6 +175,21 @@ impl<'a> Context<'a> {
             ))
         })?;
 
+        self.bind("__wbindgen_object_drop_refs", &|me| {
+            me.expose_drop_ref();
+            me.expose_uint32_memory();
+            Ok(String::from(
+                "
+                function(ptr, len) {
+                    const mem = getUint32Memory();
+                    for (let i = ptr / 4; i < ptr / 4 + len; i++) {
+                        dropRef(mem[i]);
+                    }
+                }
+                ",
+            ))
+        })?;
+
         self.bind("__wbindgen_string_new", &|me| {
             me.expose_add_heap_object();
             me.expose_get_string_from_wasm();
@@ -363,9 +407,469 @@ impl<'a> Context<'a> {
             ))
         })?;
 
+        self.bind("__wbindgen_jsval_loose_eq", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(a, b) {
+                    return getObject(a) == getObject(b) ? 1 : 0;
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_is_object", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(i) {
+                    const val = getObject(i);
+                    return typeof(val) === 'object' && val !== null ? 1 : 0;
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_is_function", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(i) {
+                    return typeof(getObject(i)) === 'function' ? 1 : 0;
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_is_string", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(i) {
+                    return typeof(getObject(i)) === 'string' ? 1 : 0;
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_is_array", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(i) {
+                    return Array.isArray(getObject(i)) ? 1 : 0;
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_typeof", &|me| {
+            me.expose_get_object();
+            me.expose_add_heap_object();
+            Ok(String::from(
+                "
+                function(i) {
+                    return addHeapObject(typeof(getObject(i)));
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_has_type", &|me| {
+            me.expose_get_object();
+            me.expose_get_string_from_wasm();
+            Ok(String::from(
+                "
+                function(i, ptr, len) {
+                    return typeof(getObject(i)) === getStringFromWasm(ptr, len) ? 1 : 0;
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_instanceof", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(i, constructor) {
+                    return getObject(i) instanceof getObject(constructor) ? 1 : 0;
+                }
+                ",
+            ))
+        })?;
+
+        for &(name, op) in [
+            ("__wbindgen_lt", "<"),
+            ("__wbindgen_le", "<="),
+            ("__wbindgen_gt", ">"),
+            ("__wbindgen_ge", ">="),
+        ].iter() {
+            self.bind(name, &|me| {
+                me.expose_get_object();
+                Ok(format!(
+                    "
+                    function(a, b) {{
+                        return getObject(a) {} getObject(b) ? 1 : 0;
+                    }}
+                    ",
+                    op
+                ))
+            })?;
+        }
+
+        // Arithmetic can throw, for example when mixing `BigInt` and numbers,
+        // in which case the exception is returned and `threw` is set.
+        for &(name, op) in [
+            ("__wbindgen_add", "+"),
+            ("__wbindgen_sub", "-"),
+            ("__wbindgen_mul", "*"),
+            ("__wbindgen_div", "/"),
+            ("__wbindgen_rem", "%"),
+        ].iter() {
+            self.bind(name, &|me| {
+                me.expose_get_object();
+                me.expose_add_heap_object();
+                me.expose_uint32_memory();
+                Ok(format!(
+                    "
+                    function(a, b, threw) {{
+                        try {{
+                            return addHeapObject(getObject(a) {} getObject(b));
+                        }} catch (e) {{
+                            getUint32Memory()[threw / 4] = 1;
+                            return addHeapObject(e);
+                        }}
+                    }}
+                    ",
+                    op
+                ))
+            })?;
+        }
+
+        self.bind("__wbindgen_neg", &|me| {
+            me.expose_get_object();
+            me.expose_add_heap_object();
+            me.expose_uint32_memory();
+            Ok(String::from(
+                "
+                function(a, threw) {
+                    try {
+                        return addHeapObject(-getObject(a));
+                    } catch (e) {
+                        getUint32Memory()[threw / 4] = 1;
+                        return addHeapObject(e);
+                    }
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_debug_string", &|me| {
+            me.expose_pass_string_to_wasm()?;
+            me.expose_get_object();
+            me.expose_uint32_memory();
+            me.global(
+                "
+                function debugString(val) {
+                    const type = typeof(val);
+                    if (type === 'string') return JSON.stringify(val);
+                    if (type === 'function') {
+                        return val.name ? `Function(${val.name})` : 'Function';
+                    }
+                    if (type !== 'object' || val === null) return String(val);
+                    try {
+                        const json = JSON.stringify(val);
+                        if (json !== undefined) return json;
+                    } catch (_) {}
+                    return String(val);
+                }
+                ",
+            );
+            Ok(String::from(
+                "
+                function(i, len_ptr) {
+                    const [ptr, len] = passStringToWasm(debugString(getObject(i)));
+                    getUint32Memory()[len_ptr / 4] = len;
+                    return ptr;
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_object_new", &|me| {
+            me.expose_add_heap_object();
+            Ok(String::from(
+                "
+                function() {
+                    return addHeapObject({});
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_object_set", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(obj, key, value) {
+                    getObject(obj)[getObject(key)] = getObject(value);
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_object_get", &|me| {
+            me.expose_get_object();
+            me.expose_get_string_from_wasm();
+            me.expose_add_heap_object();
+            Ok(String::from(
+                "
+                function(obj, ptr, len) {
+                    return addHeapObject(getObject(obj)[getStringFromWasm(ptr, len)]);
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_object_entries", &|me| {
+            me.expose_get_object();
+            me.expose_add_heap_object();
+            Ok(String::from(
+                "
+                function(obj) {
+                    return addHeapObject(Object.entries(getObject(obj)));
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_array_new", &|me| {
+            me.expose_add_heap_object();
+            Ok(String::from(
+                "
+                function() {
+                    return addHeapObject([]);
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_array_push", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(array, value) {
+                    getObject(array).push(getObject(value));
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_array_length", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(array) {
+                    return getObject(array).length;
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_array_get", &|me| {
+            me.expose_get_object();
+            me.expose_add_heap_object();
+            Ok(String::from(
+                "
+                function(array, index) {
+                    return addHeapObject(getObject(array)[index]);
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_map_new", &|me| {
+            me.expose_add_heap_object();
+            Ok(String::from(
+                "
+                function() {
+                    return addHeapObject(new Map());
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_map_set", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(map, key, value) {
+                    getObject(map).set(getObject(key), getObject(value));
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_map_entries", &|me| {
+            me.expose_get_object();
+            me.expose_add_heap_object();
+            Ok(String::from(
+                "
+                function(map) {
+                    return addHeapObject(Array.from(getObject(map).entries()));
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_uint8_array_new", &|me| {
+            me.expose_add_heap_object();
+            me.expose_uint8_memory();
+            Ok(String::from(
+                "
+                function(ptr, len) {
+                    return addHeapObject(getUint8Memory().slice(ptr, ptr + len));
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_uint8_array_copy", &|me| {
+            me.expose_get_object();
+            me.expose_uint8_memory();
+            Ok(String::from(
+                "
+                function(array, ptr) {
+                    getUint8Memory().set(getObject(array), ptr);
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_bigint_new", &|me| {
+            me.expose_get_string_from_wasm();
+            me.expose_add_heap_object();
+            Ok(String::from(
+                "
+                function(ptr, len) {
+                    return addHeapObject(BigInt(getStringFromWasm(ptr, len)));
+                }
+                ",
+            ))
+        })?;
+
+        // Must be kept in sync with the `KIND_*` constants in `serde_js.rs`
+        self.bind("__wbindgen_serde_kind", &|me| {
+            me.expose_get_object();
+            Ok(String::from(
+                "
+                function(i) {
+                    const val = getObject(i);
+                    switch (typeof(val)) {
+                        case 'undefined': return 0;
+                        case 'boolean': return 2;
+                        case 'number': return 3;
+                        case 'string': return 4;
+                        case 'bigint': return 5;
+                        case 'object':
+                            if (val === null) return 1;
+                            if (Array.isArray(val)) return 6;
+                            if (val instanceof Uint8Array) return 7;
+                            if (val instanceof Map) return 8;
+                            return 9;
+                        default: return 10;
+                    }
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_to_string", &|me| {
+            me.expose_pass_string_to_wasm()?;
+            me.expose_get_object();
+            me.expose_uint32_memory();
+            Ok(String::from(
+                "
+                function(i, len_ptr) {
+                    const [ptr, len] = passStringToWasm(String(getObject(i)));
+                    getUint32Memory()[len_ptr / 4] = len;
+                    return ptr;
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_anyref_table_grow", &|_| {
+            // The table is created empty, so the first time it's grown the
+            // reserved values are filled in as well.
+            let reserved = SLAB_RESERVED
+                .iter()
+                .enumerate()
+                .map(|(i, val)| format!("table.set({}, {});", i, val))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(format!(
+                "
+                function(delta) {{
+                    const table = wasm.{};
+                    const prev = table.grow(delta);
+                    if (prev === 0) {{
+                        {}
+                    }}
+                    return prev;
+                }}
+                ",
+                anyref::TABLE_EXPORT,
+                reserved,
+            ))
+        })?;
+
+        self.bind("__wbindgen_lock", &|me| {
+            if !me.config.threads {
+                return Ok(String::from("function(ptr) {}"));
+            }
+            me.expose_int32_memory();
+            // The main thread of a browser isn't allowed to block, so it spins
+            // instead.
+            Ok(String::from(
+                "
+                function(ptr) {
+                    const mem = getInt32Memory();
+                    while (Atomics.compareExchange(mem, ptr / 4, 0, 1) !== 0) {
+                        if (threadState !== 0) Atomics.wait(mem, ptr / 4, 1);
+                    }
+                }
+                ",
+            ))
+        })?;
+
+        self.bind("__wbindgen_unlock", &|me| {
+            if !me.config.threads {
+                return Ok(String::from("function(ptr) {}"));
+            }
+            me.expose_int32_memory();
+            Ok(String::from(
+                "
+                function(ptr) {
+                    const mem = getInt32Memory();
+                    Atomics.store(mem, ptr / 4, 0);
+                    Atomics.notify(mem, ptr / 4, 1);
+                }
+                ",
+            ))
+        })?;
+
         self.unexport_unused_internal_exports();
         self.gc()?;
 
+        let memory = if self.config.threads {
+            let memory = threads::Memory::read(self.module)?;
+            self.expose_threads(&memory);
+            Some(memory)
+        } else {
+            None
+        };
+
         // Note that it's important `throw` comes last *after* we gc. The
         // `__wbindgen_malloc` function may call this but we only want to
         // generate code for this if it's actually live (and __wbindgen_malloc
@@ -381,43 +885,198 @@ impl<'a> Context<'a> {
             ))
         })?;
 
+        self.bind("__wbindgen_panic", &|me| {
+            me.expose_get_string_from_wasm();
+            if me.config.rethrow_panics {
+                return Ok(String::from(
+                    "
+                    function(ptr, len) {
+                        throw new Error(getStringFromWasm(ptr, len));
+                    }
+                    ",
+                ));
+            }
+            Ok(String::from(
+                "
+                function(ptr, len) {
+                    const msg = getStringFromWasm(ptr, len);
+                    console.error(`${msg}\\n\\nStack:\\n\\n${new Error().stack}`);
+                }
+                ",
+            ))
+        })?;
+
         self.rewrite_imports(module_name);
 
+        // With `--threads` the module is initialized again on each thread,
+        // which is passed along to the imports and to `startThread`.
+        let (thread_arg, thread_imports, start_thread) = if self.config.threads {
+            (
+                ", thread",
+                ", __wbindgen_threads: threadImports(thread)",
+                "startThread(thread);",
+            )
+        } else {
+            ("", "", "")
+        };
+
         let mut js = if self.config.no_modules {
+            let init = if self.config.threads {
+                format!(
+                    "
+                    function init(input, thread) {{
+                        const imports = {{ './{module}': __exports{thread_imports} }};
+                        let result;
+                        if (input instanceof WebAssembly.Module) {{
+                            result = WebAssembly.instantiate(input, imports)
+                                .then(instance => ({{ instance, module: input }}));
+                        }} else {{
+                            result = fetch(input)
+                                .then(response => response.arrayBuffer())
+                                .then(buffer => WebAssembly.instantiate(buffer, imports));
+                        }}
+                        return result.then(({{instance, module}}) => {{
+                            wasm = init.wasm = instance.exports;
+                            init.__wbindgen_wasm_module = module;
+                            {start_thread}
+                            return;
+                        }});
+                    }};
+                    ",
+                    module = module_name,
+                    thread_imports = thread_imports,
+                    start_thread = start_thread,
+                )
+            } else {
+                format!(
+                    "
+                    function init(wasm_path) {{
+                        return fetch(wasm_path)
+                            .then(response => response.arrayBuffer())
+                            .then(buffer => WebAssembly.instantiate(buffer, {{ './{module}': __exports }}))
+                            .then(({{instance}}) => {{
+                                wasm = init.wasm = instance.exports;
+                                return;
+                            }});
+                    }};
+                    ",
+                    module = module_name,
+                )
+            };
             format!(
                     "
                     (function() {{
                         var wasm;
                         const __exports = {{}};
                         {globals}
-                        function init(wasm_path) {{
-                            return fetch(wasm_path)
-                                .then(response => response.arrayBuffer())
-                                .then(buffer => WebAssembly.instantiate(buffer, {{ './{module}': __exports }}))
-                                .then(({{instance}}) => {{
-                                    wasm = init.wasm = instance.exports;
-                                    return;
-                                }});
-                        }};
+                        {init}
                         self.{global_name} = Object.assign(init, __exports);
                     }})();
                     ",
                     globals = self.globals,
-                    module = module_name,
+                    init = init,
                     global_name = self.config.no_modules_global
                         .as_ref()
                         .map(|s| &**s)
                         .unwrap_or("wasm_bindgen"),
             )
+        } else if self.config.deno {
+            format!(
+                "\
+                /* tslint:disable */\n\
+                {imports}\n\
+                let wasm;\n\
+                const __exports = {{}};\n\
+
+                {globals}\n\
+
+                const file = new URL('./{module}_bg.wasm', import.meta.url);
+                const bytes = file.protocol === 'file:'
+                    ? await Deno.readFile(file)
+                    : new Uint8Array(await (await fetch(file)).arrayBuffer());
+                const wasmModule = new WebAssembly.Module(bytes);
+                const wasmInstance = new WebAssembly.Instance(wasmModule, {{ './{module}': __exports }});
+                wasm = wasmInstance.exports;
+                {footer}",
+                globals = self.globals,
+                imports = self.imports,
+                footer = self.footer,
+                module = module_name,
+            )
+        } else if self.config.web {
+            format!(
+                "\
+                /* tslint:disable */\n\
+                {imports}\n\
+                let wasm;\n\
+                const __exports = {{}};\n\
+
+                {globals}\n\
+
+                function instantiate(response, imports) {{
+                    if (typeof WebAssembly.instantiateStreaming === 'function' &&
+                        response.headers.get('Content-Type') === 'application/wasm') {{
+                        return WebAssembly.instantiateStreaming(response, imports);
+                    }}
+                    return response.arrayBuffer()
+                        .then(bytes => WebAssembly.instantiate(bytes, imports));
+                }}
+
+                function init(input{thread_arg}) {{
+                    if (typeof input === 'undefined') {{
+                        input = import.meta.url.replace(/\\.js$/, '_bg.wasm');
+                    }}
+                    const imports = {{ './{module}': __exports{thread_imports} }};
+                    let result;
+                    if (typeof input === 'string' ||
+                        (typeof URL === 'function' && input instanceof URL) ||
+                        (typeof Request === 'function' && input instanceof Request)) {{
+                        result = fetch(input).then(response => instantiate(response, imports));
+                    }} else if (typeof Response === 'function' && input instanceof Response) {{
+                        result = instantiate(input, imports);
+                    }} else {{
+                        result = WebAssembly.instantiate(input, imports)
+                            .then(result => {{
+                                if (result instanceof WebAssembly.Instance) {{
+                                    return {{ instance: result, module: input }};
+                                }}
+                                return result;
+                            }});
+                    }}
+                    return result.then(({{ instance, module }}) => {{
+                        wasm = instance.exports;
+                        init.__wbindgen_wasm_module = module;
+                        {start_thread}
+                        return wasm;
+                    }});
+                }}
+
+                export default init;\n\
+                {footer}",
+                globals = self.globals,
+                imports = self.imports,
+                footer = self.footer,
+                module = module_name,
+                thread_arg = thread_arg,
+                thread_imports = thread_imports,
+                start_thread = start_thread,
+            )
         } else {
             let import_wasm = if self.globals.len() == 0 {
                 String::new()
             } else if self.use_node_require() {
-                self.footer
-                    .push_str(&format!("wasm = require('./{}_bg');", module_name));
+                self.footer.push_str(&format!(
+                    "wasm = require('./{}_bg{}');",
+                    module_name,
+                    self.config.import_suffix()
+                ));
                 format!("var wasm;")
             } else {
-                format!("import * as wasm from './{}_bg';", module_name)
+                format!(
+                    "import * as wasm from './{}_bg{}';",
+                    module_name,
+                    self.config.import_suffix()
+                )
             };
 
             format!(
@@ -437,12 +1096,26 @@ impl<'a> Context<'a> {
 
         self.export_table();
         self.gc()?;
+        if let Some(ref memory) = memory {
+            threads::share_memory(self.module, memory)?;
+        }
+        if self.config.anyref {
+            self.anyref.prepare(self.module)?;
+        }
         self.add_wasm_pack_section();
 
         while js.contains("\n\n\n") {
             js = js.replace("\n\n\n", "\n\n");
         }
 
+        if self.config.web {
+            self.typescript.push_str(
+                "\nexport default function init \
+                 (input?: RequestInfo | URL | Response | BufferSource | WebAssembly.Module): \
+                 Promise<any>;\n",
+            );
+        }
+
         Ok((js, self.typescript.clone()))
     }
 
@@ -472,35 +1145,65 @@ impl<'a> Context<'a> {
         let mut dst = format!("class {} {{\n", name);
         let mut ts_dst = format!("export {}", dst);
 
+        let register = if self.config.weak_refs {
+            self.expose_class_registry();
+            format!(
+                "classRegistry.register(this, {{ ptr: this.ptr, free: wasm.{}, name: '{}' }}, this);",
+                shared::free_function(&name),
+                name,
+            )
+        } else {
+            String::new()
+        };
+
         if self.config.debug || class.constructor.is_some() {
             self.expose_constructor_token();
 
+            let track = if self.config.debug {
+                self.expose_live_class_ptrs();
+                format!("classPtrs('{}').add(this.ptr);", name)
+            } else {
+                String::new()
+            };
             dst.push_str(&format!(
                 "
                 static __construct(ptr) {{
-                    return new {}(new ConstructorToken(ptr));
+                    return new {name}(new ConstructorToken(ptr));
                 }}
 
                 constructor(...args) {{
                     if (args.length === 1 && args[0] instanceof ConstructorToken) {{
                         this.ptr = args[0].ptr;
+                        {track}
+                        {register}
                         return;
                     }}
                 ",
-                name
+                name = name,
+                track = track,
+                register = register,
             ));
 
             if let Some(ref constructor) = class.constructor {
                 ts_dst.push_str(&format!("constructor(...args: any[]);\n"));
 
+                // The temporary instance mustn't free the Rust value when it's
+                // collected, as it's now owned by `this`.
+                let reregister = if self.config.weak_refs {
+                    format!("classRegistry.unregister(instance);\n{}", register)
+                } else {
+                    String::new()
+                };
                 dst.push_str(&format!(
                     "
                     // This invocation of new will call this constructor with a ConstructorToken
                     let instance = {class}.{constructor}(...args);
                     this.ptr = instance.ptr;
+                    {reregister}
                     ",
                     class = name,
-                    constructor = constructor
+                    constructor = constructor,
+                    reregister = reregister,
                 ));
             } else {
                 dst.push_str(
@@ -519,9 +1222,11 @@ impl<'a> Context<'a> {
 
                 constructor(ptr) {{
                     this.ptr = ptr;
+                    {}
                 }}
                 ",
-                name
+                name,
+                register,
             ));
         }
 
@@ -553,7 +1258,7 @@ impl<'a> Context<'a> {
 
             let set = {
                 let mut cx = Js2Rust::new(&field.name, self);
-                cx.method(true, false).argument(&descriptor)?.ret(&None)?;
+                cx.method(name, true, false).argument(&descriptor)?.ret(&None)?;
                 ts_dst.push_str(&format!(
                     "{}{}: {}\n",
                     if field.readonly { "readonly " } else { "" },
@@ -563,7 +1268,7 @@ impl<'a> Context<'a> {
                 cx.finish("", &format!("wasm.{}", wasm_setter)).0
             };
             let (get, _ts) = Js2Rust::new(&field.name, self)
-                .method(true, false)
+                .method(name, true, false)
                 .ret(&Some(descriptor))?
                 .finish("", &format!("wasm.{}", wasm_getter));
             if !dst.ends_with("\n") {
@@ -581,16 +1286,43 @@ impl<'a> Context<'a> {
             }
         }
 
-        dst.push_str(&format!(
-            "
-            free() {{
-                const ptr = this.ptr;
-                this.ptr = 0;
-                wasm.{}(ptr);
-            }}
-            ",
-            shared::free_function(&name)
-        ));
+        let unregister = if self.config.weak_refs {
+            "classRegistry.unregister(this);"
+        } else {
+            ""
+        };
+        if self.config.debug {
+            dst.push_str(&format!(
+                "
+                free() {{
+                    const ptr = this.ptr;
+                    if (ptr === 0) {{
+                        throw new Error('`{name}` instance used after being freed or moved into Rust');
+                    }}
+                    this.ptr = 0;
+                    {unregister}
+                    classPtrs('{name}').delete(ptr);
+                    wasm.{free}(ptr);
+                }}
+                ",
+                name = name,
+                unregister = unregister,
+                free = shared::free_function(&name)
+            ));
+        } else {
+            dst.push_str(&format!(
+                "
+                free() {{
+                    const ptr = this.ptr;
+                    this.ptr = 0;
+                    {}
+                    wasm.{}(ptr);
+                }}
+                ",
+                unregister,
+                shared::free_function(&name)
+            ));
+        }
         ts_dst.push_str("free(): void;\n");
         dst.push_str(&class.contents);
         ts_dst.push_str(&class.typescript);
@@ -718,46 +1450,52 @@ impl<'a> Context<'a> {
         if !self.exposed_globals.insert("drop_ref") {
             return;
         }
-        self.expose_global_slab();
-        self.expose_global_slab_next();
-        let validate_owned = if self.config.debug {
-            String::from(
+        if self.config.anyref {
+            self.expose_anyref_table();
+            self.global(&format!(
                 "
-                if ((idx & 1) === 1) throw new Error('cannot drop ref of stack objects');
+                function dropRef(idx) {{
+                    const slot = idx >> 1;
+                    if (slot < {}) return;
+                    anyrefTable().set(slot, undefined);
+                    wasm.__wbindgen_anyref_table_dealloc(slot);
+                }}
                 ",
-            )
-        } else {
-            String::new()
-        };
-        let dec_ref = if self.config.debug {
-            String::from(
+                SLAB_RESERVED.len(),
+            ));
+            return;
+        }
+        self.expose_global_slab();
+        self.expose_global_slab_next();
+        if self.config.debug {
+            // Bumping the generation of the slot makes any remaining copies of
+            // `idx` stale, so their use will be caught by `checkHeapIndex`.
+            self.global(&format!(
                 "
-                if (typeof(obj) === 'number') throw new Error('corrupt slab');
-                obj.cnt -= 1;
-                if (obj.cnt > 0) return;
+                function dropRef(idx) {{
+                    if ((idx & 1) === 1) throw new Error('cannot drop ref of stack objects');
+                    const slot = checkHeapIndex(idx);
+                    if (slot < {}) return;
+                    slab[slot] = slab_next;
+                    slab_gen[slot] = slab_gen[slot] % SLAB_MAX_GEN + 1;
+                    slab_next = slot;
+                }}
                 ",
-            )
+                SLAB_RESERVED.len(),
+            ));
         } else {
-            String::from(
+            self.global(&format!(
                 "
-                obj.cnt -= 1;
-                if (obj.cnt > 0) return;
+                function dropRef(idx) {{
+                    idx = idx >> 1;
+                    if (idx < {}) return;
+                    slab[idx] = slab_next;
+                    slab_next = idx;
+                }}
                 ",
-            )
-        };
-        self.global(&format!(
-            "
-            function dropRef(idx) {{
-                {}
-                let obj = slab[idx >> 1];
-                {}
-                // If we hit 0 then free up our space in the slab
-                slab[idx >> 1] = slab_next;
-                slab_next = idx >> 1;
-            }}
-            ",
-            validate_owned, dec_ref
-        ));
+                SLAB_RESERVED.len(),
+            ));
+        }
     }
 
     fn expose_global_stack(&mut self) {
@@ -783,34 +1521,208 @@ impl<'a> Context<'a> {
         }
     }
 
+    /// Exposes the `slab` of JS objects owned by wasm, indexed by `idx >> 1` of
+    /// even indices.
+    ///
+    /// Live slots hold the object itself and free slots hold the index of the
+    /// next free slot, forming a free list starting at `slab_next` and ending
+    /// at `slab.length`. Slots aren't reference counted, cloning a `JsValue`
+    /// simply allocates another slot.
+    ///
+    /// In debug mode indices also carry the generation of their slot above
+    /// the slot number, which is bumped each time the slot is freed. That way
+    /// use of an index after its object has been dropped is detected, rather
+    /// than silently referring to whatever object reused the slot.
     fn expose_global_slab(&mut self) {
         if !self.exposed_globals.insert("slab") {
             return;
         }
-        let initial_values = [
-            "{ obj: null }",
-            "{ obj: undefined }",
-            "{ obj: true }",
-            "{ obj: false }",
-        ];
-        self.global(&format!("const slab = [{}];", initial_values.join(", ")));
-        if self.config.debug {
-            self.export(
-                "assertSlabEmpty",
-                &format!(
-                    "
-                    function() {{
-                        for (let i = {}; i < slab.length; i++) {{
-                            if (typeof(slab[i]) === 'number') continue;
-                            throw new Error('slab is not currently empty');
-                        }}
-                    }}
-                    ",
-                    initial_values.len()
-                ),
-                None,
-            );
+        self.global(&format!("const slab = [{}];", SLAB_RESERVED.join(", ")));
+        if !self.config.debug {
+            return;
+        }
+        self.expose_global_slab_next();
+        self.global(&format!(
+            "
+            const slab_gen = [{}];
+            const SLAB_SLOT_BITS = 20;
+            const SLAB_MAX_SLOTS = 1 << SLAB_SLOT_BITS;
+            const SLAB_MAX_GEN = (1 << (31 - SLAB_SLOT_BITS)) - 1;
+
+            function heapIndex(slot) {{
+                return ((slab_gen[slot] << SLAB_SLOT_BITS) | slot) << 1;
+            }}
+
+            function checkHeapIndex(idx) {{
+                const slot = (idx >>> 1) & (SLAB_MAX_SLOTS - 1);
+                const gen = idx >>> (SLAB_SLOT_BITS + 1);
+                if (slot >= slab.length || gen !== slab_gen[slot]) {{
+                    throw new Error(
+                        `use of a dropped JS object: index ${{idx >>> 0}} refers to generation ` +
+                        `${{gen}} of slot ${{slot}}, but that object has since been dropped`
+                    );
+                }}
+                return slot;
+            }}
+            ",
+            SLAB_RESERVED.iter().map(|_| "0").collect::<Vec<_>>().join(", "),
+        ));
+
+        // Drops of `JsValue`s are batched up in wasm, so make sure they've all
+        // reached us before counting.
+        self.required_internal_exports.insert("__wbindgen_drop_flush");
+        self.global(&format!(
+            "
+            function liveHeapObjects() {{
+                if (wasm.__wbindgen_drop_flush) wasm.__wbindgen_drop_flush();
+                let free = 0;
+                for (let i = slab_next; i < slab.length; i = slab[i]) free++;
+                return slab.length - {} - free;
+            }}
+            ",
+            SLAB_RESERVED.len()
+        ));
+        self.export(
+            "assertSlabEmpty",
+            "
+            function() {
+                const live = liveHeapObjects();
+                if (live === 0) return;
+                throw new Error(`slab is not currently empty, ${live} objects are live`);
+            }
+            ",
+            None,
+        );
+    }
+
+    /// Exposes wherever JS objects owned by wasm are kept, along with
+    /// `liveHeapObjects` in debug mode.
+    fn expose_heap(&mut self) {
+        if self.config.anyref {
+            self.expose_anyref_table();
+        } else {
+            self.expose_global_slab();
+        }
+    }
+
+    /// Exposes the table of `anyref`s which takes the place of the `slab`
+    /// with `--anyref`, indexed by `idx >> 1` like the `slab`. The table
+    /// lives in the wasm module, which also hands out its slots.
+    fn expose_anyref_table(&mut self) {
+        if !self.exposed_globals.insert("anyref_table") {
+            return;
+        }
+        self.global(&format!(
+            "
+            let cachedAnyrefTable = null;
+            function anyrefTable() {{
+                if (cachedAnyrefTable === null) {{
+                    wasm.__wbindgen_anyref_table_init();
+                    cachedAnyrefTable = wasm.{};
+                }}
+                return cachedAnyrefTable;
+            }}
+            ",
+            anyref::TABLE_EXPORT,
+        ));
+        if !self.config.debug {
+            return;
+        }
+        self.required_internal_exports.insert("__wbindgen_drop_flush");
+        self.global(
+            "
+            function liveHeapObjects() {
+                if (wasm.__wbindgen_drop_flush) wasm.__wbindgen_drop_flush();
+                return wasm.__wbindgen_anyref_heap_live_count();
+            }
+            ",
+        );
+        self.export(
+            "assertSlabEmpty",
+            "
+            function() {
+                const live = liveHeapObjects();
+                if (live === 0) return;
+                throw new Error(`slab is not currently empty, ${live} objects are live`);
+            }
+            ",
+            None,
+        );
+    }
+
+    /// Exposes the `__wbindgen_heap_stats` function in debug mode, to help
+    /// track down leaks.
+    fn expose_heap_stats(&mut self) {
+        if !self.exposed_globals.insert("heap_stats") {
+            return;
+        }
+        self.expose_heap();
+        self.expose_live_class_ptrs();
+        self.export(
+            "__wbindgen_heap_stats",
+            "
+            function() {
+                const classes = {};
+                for (const [name, ptrs] of liveClassPtrs) {
+                    if (ptrs.size > 0) classes[name] = ptrs.size;
+                }
+                return { heapObjects: liveHeapObjects(), classes };
+            }
+            ",
+            None,
+        );
+    }
+
+    /// Exposes the `FinalizationRegistry` used with `--weak-refs` to free the
+    /// Rust value owned by an instance of an exported class once the instance
+    /// is collected. Instances are registered with themselves as the token so
+    /// they can be unregistered when freed manually or moved into Rust.
+    ///
+    /// Engines without `FinalizationRegistry` get a registry which does
+    /// nothing, leaving `free()` as the only way to release instances.
+    fn expose_class_registry(&mut self) {
+        if !self.exposed_globals.insert("class_registry") {
+            return;
+        }
+        let untrack = if self.config.debug {
+            self.expose_live_class_ptrs();
+            "classPtrs(held.name).delete(held.ptr);"
+        } else {
+            ""
+        };
+        self.global(&format!(
+            "
+            const classRegistry = typeof FinalizationRegistry === 'undefined'
+                ? {{ register() {{}}, unregister() {{}} }}
+                : new FinalizationRegistry(held => {{
+                    {}
+                    held.free(held.ptr);
+                }});
+            ",
+            untrack
+        ));
+    }
+
+    /// Exposes the pointers to Rust structs owned by instances of exported
+    /// classes in JS, by class name. Only used in debug mode.
+    fn expose_live_class_ptrs(&mut self) {
+        if !self.exposed_globals.insert("live_class_ptrs") {
+            return;
         }
+        self.global(
+            "
+            const liveClassPtrs = new Map();
+
+            function classPtrs(name) {
+                let ptrs = liveClassPtrs.get(name);
+                if (ptrs === undefined) {
+                    ptrs = new Set();
+                    liveClassPtrs.set(name, ptrs);
+                }
+                return ptrs;
+            }
+            ",
+        );
     }
 
     fn expose_global_slab_next(&mut self) {
@@ -829,36 +1741,51 @@ impl<'a> Context<'a> {
         if !self.exposed_globals.insert("get_object") {
             return;
         }
+        if self.config.anyref {
+            // Borrowed objects are in the table too, see `addBorrowedObject`.
+            self.expose_anyref_table();
+            let check = if self.config.debug {
+                "if ((idx & 1) === 1) throw new Error('expected an index into the JS value table');"
+            } else {
+                ""
+            };
+            self.global(&format!(
+                "
+                function getObject(idx) {{
+                    {}
+                    return anyrefTable().get(idx >> 1);
+                }}
+                ",
+                check,
+            ));
+            return;
+        }
         self.expose_global_stack();
         self.expose_global_slab();
-
-        let get_obj = if self.config.debug {
-            String::from(
+        if self.config.debug {
+            self.global(
                 "
-                if (typeof(val) === 'number') throw new Error('corrupt slab');
-                return val.obj;
+                function getObject(idx) {
+                    if ((idx & 1) === 1) {
+                        if ((idx >> 1) >= stack.length) {
+                            throw new Error('use of a borrowed JS object after its borrow ended');
+                        }
+                        return stack[idx >> 1];
+                    }
+                    return slab[checkHeapIndex(idx)];
+                }
                 ",
-            )
+            );
         } else {
-            String::from(
+            self.global(
                 "
-                return val.obj;
+                function getObject(idx) {
+                    if ((idx & 1) === 1) return stack[idx >> 1];
+                    return slab[idx >> 1];
+                }
                 ",
-            )
-        };
-        self.global(&format!(
-            "
-            function getObject(idx) {{
-                if ((idx & 1) === 1) {{
-                    return stack[idx >> 1];
-                }} else {{
-                    const val = slab[idx >> 1];
-                    {}
-                }}
-            }}
-            ",
-            get_obj
-        ));
+            );
+        }
     }
 
     fn expose_assert_num(&mut self) {
@@ -977,7 +1904,7 @@ impl<'a> Context<'a> {
         if !self.exposed_globals.insert("text_encoder") {
             return;
         }
-        if self.config.nodejs_experimental_modules {
+        if self.config.node_esm() {
             self.imports
                 .push_str("import { TextEncoder } from 'util';\n");
         } else if self.config.nodejs {
@@ -986,7 +1913,11 @@ impl<'a> Context<'a> {
                 const TextEncoder = require('util').TextEncoder;
                 ",
             );
-        } else if !(self.config.browser || self.config.no_modules) {
+        } else if !(self.config.browser
+            || self.config.web
+            || self.config.deno
+            || self.config.no_modules)
+        {
             self.global(
                 "
                 const TextEncoder = typeof self === 'object' && self.TextEncoder
@@ -1002,11 +1933,136 @@ impl<'a> Context<'a> {
         );
     }
 
+    /// Exposes the JS starting and exiting threads with `--threads`.
+    ///
+    /// The main thread creates the shared memory when it's initialized, while
+    /// other threads are initialized with what `threadData` returned on a
+    /// thread which is already running. The data segments of the module are
+    /// copied into the new thread's stack when it's instantiated, where they
+    /// don't overwrite anything as the stack isn't in use yet. That copy is
+    /// also where the new thread's TLS block is initialized from, as the main
+    /// thread's may have been modified since.
+    fn expose_threads(&mut self, memory: &threads::Memory) {
+        if !self.exposed_globals.insert("threads") {
+            return;
+        }
+        self.expose_uint32_memory();
+        let offsets = memory
+            .data_offsets
+            .iter()
+            .map(|offset| offset.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (tls_size, tls_align, init_tls) = match memory.tls {
+            Some(ref tls) => (
+                tls.size,
+                tls.align,
+                format!(
+                    "
+                    const tls = thread.stack + {offset};
+                    new Uint8Array(thread.memory.buffer)
+                        .copyWithin(thread.tls, tls, tls + TLS_SIZE);
+                    wasm.{set}(thread.tls);
+                    ",
+                    offset = tls.base - memory.data_start,
+                    set = threads::TLS_BASE_SET,
+                ),
+            ),
+            None => (0, 1, String::new()),
+        };
+        self.global(&format!(
+            "
+            const DATA_OFFSETS = [{}];
+            const DATA_START = {};
+            const THREAD_STACK_SIZE = {};
+            const TLS_SIZE = {};
+            const TLS_ALIGN = {};
+
+            let threadState = 0;
+
+            function threadImports(thread) {{
+                const imports = {{}};
+                if (thread === undefined) {{
+                    imports.memory = new WebAssembly.Memory({{
+                        initial: {},
+                        maximum: {},
+                        shared: true,
+                    }});
+                    DATA_OFFSETS.forEach((offset, i) => imports[`data${{i}}`] = offset);
+                }} else {{
+                    imports.memory = thread.memory;
+                    DATA_OFFSETS.forEach((offset, i) => {{
+                        imports[`data${{i}}`] = thread.stack + offset - DATA_START;
+                    }});
+                }}
+                return imports;
+            }}
+
+            function startThread(thread) {{
+                if (thread === undefined) return;
+                wasm.{}(thread.stack + thread.stackSize);
+                wasm.{}(thread.state);
+                {}
+                threadState = thread.state;
+            }}
+            ",
+            offsets,
+            memory.data_start,
+            (1 << 20) + memory.data_size,
+            tls_size,
+            tls_align,
+            memory.initial,
+            memory.maximum,
+            threads::STACK_POINTER_SET,
+            threads::THREAD_PTR_SET,
+            init_tls,
+        ));
+        self.export(
+            "threadData",
+            "
+            function() {
+                const state = wasm.__wbindgen_thread_alloc(THREAD_STACK_SIZE, TLS_SIZE, TLS_ALIGN);
+                const mem = getUint32Memory();
+                return {
+                    module: init.__wbindgen_wasm_module,
+                    memory: wasm.memory,
+                    state,
+                    stack: mem[state / 4],
+                    stackSize: mem[state / 4 + 1],
+                    tls: mem[state / 4 + 2],
+                };
+            }
+            ",
+            Some(format_doc_comments(&vec![
+                " Allocates a new thread, returning what to initialize the module with".to_string(),
+                " on the web worker it's going to run on, as in `init(data.module, data)`."
+                    .to_string(),
+            ])),
+        );
+        self.export(
+            "threadExit",
+            "
+            function() {
+                if (threadState === 0) {
+                    throw new Error('only threads started with `threadData` can exit');
+                }
+                wasm.__wbindgen_thread_free(threadState);
+                threadState = 0;
+            }
+            ",
+            Some(format_doc_comments(&vec![
+                " Frees the current thread, after which it mustn't call into the module"
+                    .to_string(),
+                " again.".to_string(),
+            ])),
+        );
+    }
+
     fn expose_text_decoder(&mut self) {
         if !self.exposed_globals.insert("text_decoder") {
             return;
         }
-        if self.config.nodejs_experimental_modules {
+        if self.config.node_esm() {
             self.imports
                 .push_str("import { TextDecoder } from 'util';\n");
         } else if self.config.nodejs {
@@ -1015,7 +2071,11 @@ impl<'a> Context<'a> {
                 const TextDecoder = require('util').TextDecoder;
                 ",
             );
-        } else if !(self.config.browser || self.config.no_modules) {
+        } else if !(self.config.browser
+            || self.config.web
+            || self.config.deno
+            || self.config.no_modules)
+        {
             self.global(
                 "
                 const TextDecoder = typeof self === 'object' && self.TextDecoder
@@ -1053,13 +2113,20 @@ impl<'a> Context<'a> {
         }
         self.expose_text_decoder();
         self.expose_uint8_memory();
-        self.global(
+        // `TextDecoder` doesn't accept views of shared memory.
+        let view = if self.config.threads {
+            "slice"
+        } else {
+            "subarray"
+        };
+        self.global(&format!(
             "
-            function getStringFromWasm(ptr, len) {
-                return cachedDecoder.decode(getUint8Memory().subarray(ptr, ptr + len));
-            }
+            function getStringFromWasm(ptr, len) {{
+                return cachedDecoder.decode(getUint8Memory().{}(ptr, ptr + len));
+            }}
             ",
-        );
+            view,
+        ));
     }
 
     fn expose_get_array_js_value_from_wasm(&mut self) {
@@ -1133,6 +2200,11 @@ impl<'a> Context<'a> {
         self.arrayget("getArrayF64FromWasm", "getFloat64Memory", 8);
     }
 
+    fn expose_get_clamped_array_u8_from_wasm(&mut self) {
+        self.expose_clamped_uint8_memory();
+        self.arrayget("getClampedArrayU8FromWasm", "getUint8ClampedMemory", 1);
+    }
+
     fn arrayget(&mut self, name: &'static str, mem: &'static str, size: usize) {
         if !self.exposed_globals.insert(name) {
             return;
@@ -1157,6 +2229,10 @@ impl<'a> Context<'a> {
         self.memview("getUint8Memory", "Uint8Array");
     }
 
+    fn expose_clamped_uint8_memory(&mut self) {
+        self.memview("getUint8ClampedMemory", "Uint8ClampedArray");
+    }
+
     fn expose_int16_memory(&mut self) {
         self.memview("getInt16Memory", "Int16Array");
     }
@@ -1235,6 +2311,10 @@ impl<'a> Context<'a> {
                 self.expose_f64_memory();
                 "getFloat64Memory"
             }
+            VectorKind::ClampedU8 => {
+                self.expose_clamped_uint8_memory();
+                "getUint8ClampedMemory"
+            }
             VectorKind::Anyref => {
                 self.expose_uint32_memory();
                 "getUint32Memory"
@@ -1282,6 +2362,21 @@ impl<'a> Context<'a> {
             return;
         }
         self.expose_global_stack();
+        if self.config.anyref {
+            // Rust can only refer to values in the table, so borrowed objects
+            // are added to it as well, and dropped again when the borrow ends.
+            self.expose_add_heap_object();
+            self.global(
+                "
+                function addBorrowedObject(obj) {
+                    const idx = addHeapObject(obj);
+                    stack.push(idx);
+                    return idx;
+                }
+                ",
+            );
+            return;
+        }
         self.global(
             "
             function addBorrowedObject(obj) {
@@ -1313,35 +2408,52 @@ impl<'a> Context<'a> {
         if !self.exposed_globals.insert("add_heap_object") {
             return;
         }
+        if self.config.anyref {
+            self.expose_anyref_table();
+            self.global(
+                "
+                function addHeapObject(obj) {
+                    const slot = wasm.__wbindgen_anyref_table_alloc();
+                    anyrefTable().set(slot, obj);
+                    return slot << 1;
+                }
+                ",
+            );
+            return;
+        }
         self.expose_global_slab();
         self.expose_global_slab_next();
-        let set_slab_next = if self.config.debug {
-            String::from(
+        if self.config.debug {
+            self.global(
                 "
-                if (typeof(next) !== 'number') throw new Error('corrupt slab');
-                slab_next = next;
+                function addHeapObject(obj) {
+                    if (slab_next === slab.length) {
+                        if (slab.length === SLAB_MAX_SLOTS) {
+                            throw new Error('too many live JS objects in debug mode');
+                        }
+                        slab.push(slab.length + 1);
+                        slab_gen.push(1);
+                    }
+                    const slot = slab_next;
+                    slab_next = slab[slot];
+                    slab[slot] = obj;
+                    return heapIndex(slot);
+                }
                 ",
-            )
+            );
         } else {
-            String::from(
+            self.global(
                 "
-                slab_next = next;
+                function addHeapObject(obj) {
+                    if (slab_next === slab.length) slab.push(slab.length + 1);
+                    const idx = slab_next;
+                    slab_next = slab[idx];
+                    slab[idx] = obj;
+                    return idx << 1;
+                }
                 ",
-            )
-        };
-        self.global(&format!(
-            "
-            function addHeapObject(obj) {{
-                if (slab_next === slab.length) slab.push(slab.length + 1);
-                const idx = slab_next;
-                const next = slab[idx];
-                {}
-                slab[idx] = {{ obj, cnt: 1 }};
-                return idx << 1;
-            }}
-            ",
-            set_slab_next
-        ));
+            );
+        }
     }
 
     fn wasm_import_needed(&self, name: &str) -> bool {
@@ -1362,7 +2474,7 @@ impl<'a> Context<'a> {
                 self.expose_pass_string_to_wasm()?;
                 "passStringToWasm"
             }
-            VectorKind::I8 | VectorKind::U8 => {
+            VectorKind::I8 | VectorKind::U8 | VectorKind::ClampedU8 => {
                 self.expose_pass_array8_to_wasm()?;
                 "passArray8ToWasm"
             }
@@ -1437,6 +2549,10 @@ impl<'a> Context<'a> {
                 self.expose_get_array_f64_from_wasm();
                 "getArrayF64FromWasm"
             }
+            VectorKind::ClampedU8 => {
+                self.expose_get_clamped_array_u8_from_wasm();
+                "getClampedArrayU8FromWasm"
+            }
             VectorKind::Anyref => {
                 self.expose_get_array_js_value_from_wasm();
                 "getArrayJsValueFromWasm"
@@ -1466,14 +2582,31 @@ impl<'a> Context<'a> {
             return Ok(());
         }
         self.require_internal_export("__wbindgen_global_argument_ptr")?;
+        // The argument area grows on demand so it can move between calls, and
+        // isn't cached here.
         self.global(
             "
-            let cachedGlobalArgumentPtr = null;
             function globalArgumentPtr() {
-                if (cachedGlobalArgumentPtr === null) {
-                    cachedGlobalArgumentPtr = wasm.__wbindgen_global_argument_ptr();
+                return wasm.__wbindgen_global_argument_ptr();
+            }
+            ",
+        );
+        Ok(())
+    }
+
+    fn expose_global_ret_ptr(&mut self) -> Result<(), Error> {
+        if !self.exposed_globals.insert("global_ret_ptr") {
+            return Ok(());
+        }
+        self.require_internal_export("__wbindgen_global_ret_ptr")?;
+        self.global(
+            "
+            let cachedGlobalRetPtr = null;
+            function globalRetPtr() {
+                if (cachedGlobalRetPtr === null) {
+                    cachedGlobalRetPtr = wasm.__wbindgen_global_ret_ptr();
                 }
-                return cachedGlobalArgumentPtr;
+                return cachedGlobalRetPtr;
             }
             ",
         );
@@ -1545,8 +2678,11 @@ impl<'a> Context<'a> {
     fn gc(&mut self) -> Result<(), Error> {
         let module = mem::replace(self.module, Module::default());
         let wasm_bytes = parity_wasm::serialize(module)?;
+        // wasm-gc remaps function indices in the `name` section as it removes
+        // functions, but DWARF sections are only kept if asked for.
         let bytes = wasm_gc::Config::new()
             .demangle(self.config.demangle)
+            .keep_debug(self.config.keep_debug)
             .gc(&wasm_bytes)?;
         *self.module = deserialize_buffer(&bytes)?;
         Ok(())
@@ -1592,8 +2728,38 @@ impl<'a> Context<'a> {
         self.module.sections_mut().push(Section::Custom(section));
     }
 
+    /// Like `export`, but if source maps are enabled the exported item is
+    /// mapped back to the Rust item at `location`.
+    fn export_at(
+        &mut self,
+        location: &Option<shared::Location>,
+        name: &str,
+        contents: &str,
+        comments: Option<String>,
+    ) {
+        let marker = self.source_map_marker(location);
+        self.globals.push_str(&marker);
+        self.export(name, contents, comments);
+        if marker.len() > 0 {
+            self.globals.push_str(source_map::END_MARKER);
+        }
+    }
+
+    /// The marker to place before JS generated for the Rust item at
+    /// `location`, which must be followed by `source_map::END_MARKER` unless
+    /// it's empty.
+    fn source_map_marker(&mut self, location: &Option<shared::Location>) -> String {
+        match *location {
+            Some(ref location) if self.config.source_map => {
+                self.source_locations.push(location.clone());
+                source_map::start_marker(self.source_locations.len() - 1)
+            }
+            _ => String::new(),
+        }
+    }
+
     fn use_node_require(&self) -> bool {
-        self.config.nodejs && !self.config.nodejs_experimental_modules
+        self.config.nodejs && !self.config.node_esm()
     }
 }
 
@@ -1613,6 +2779,11 @@ impl<'a, 'b> SubContext<'a, 'b> {
         for e in self.program.enums.iter() {
             self.generate_enum(e);
         }
+        for o in self.program.objects.iter() {
+            self.generate_object(o).with_context(|_| {
+                format!("failed to generate bindings for Rust object `{}`", o.name)
+            })?;
+        }
         for s in self.program.structs.iter() {
             let mut class = self
                 .cx
@@ -1643,7 +2814,8 @@ impl<'a, 'b> SubContext<'a, 'b> {
         let (js, ts) = Js2Rust::new(&export.function.name, self.cx)
             .process(descriptor.unwrap_function())?
             .finish("function", &format!("wasm.{}", export.function.name));
-        self.cx.export(
+        self.cx.export_at(
+            &export.function.location,
             &export.function.name,
             &js,
             Some(format_doc_comments(&export.comments)),
@@ -1668,14 +2840,16 @@ impl<'a, 'b> SubContext<'a, 'b> {
         };
 
         let (js, ts) = Js2Rust::new(&export.function.name, self.cx)
-            .method(export.method, export.consumed)
+            .method(class_name, export.method, export.consumed)
             .process(descriptor.unwrap_function())?
             .finish("", &format!("wasm.{}", wasm_name));
+        let marker = self.cx.source_map_marker(&export.function.location);
         let class = self
             .cx
             .exported_classes
             .entry(class_name.to_string())
             .or_insert(ExportedClass::default());
+        class.contents.push_str(&marker);
         class
             .contents
             .push_str(&format_doc_comments(&export.comments));
@@ -1700,6 +2874,9 @@ impl<'a, 'b> SubContext<'a, 'b> {
         class.contents.push_str(&export.function.name);
         class.contents.push_str(&js);
         class.contents.push_str("\n");
+        if marker.len() > 0 {
+            class.contents.push_str(source_map::END_MARKER);
+        }
         class.typescript.push_str(&ts);
         class.typescript.push_str("\n");
         Ok(())
@@ -1749,7 +2926,7 @@ impl<'a, 'b> SubContext<'a, 'b> {
                 ),
             };
             match module[first_slash + 1..].find('/') {
-                Some(i) => &module[..i],
+                Some(i) => &module[..first_slash + 1 + i],
                 None => module,
             }
         } else {
@@ -1896,11 +3073,45 @@ impl<'a, 'b> SubContext<'a, 'b> {
             }
         };
 
-        let js = Rust2Js::new(self.cx)
-            .catch(import.catch)
-            .process(descriptor.unwrap_function())?
-            .finish(&target);
-        self.cx.export(&import.shim, &js, None);
+        let (js, anyref) = {
+            let mut shim = Rust2Js::new(self.cx);
+            shim.catch(import.catch)
+                .process(descriptor.unwrap_function())?;
+            (shim.finish(&target), shim.anyref_signature())
+        };
+        if self.cx.config.anyref {
+            self.cx.anyref.imports.insert(import.shim.clone(), anyref);
+        }
+        self.cx.export_at(&import.function.location, &import.shim, &js, None);
+        Ok(())
+    }
+
+    fn generate_object(&mut self, object: &shared::Object) -> Result<(), Error> {
+        let mut ts = String::new();
+        if object.comments.len() > 0 {
+            ts.push_str(&format_doc_comments(&object.comments));
+        }
+        ts.push_str(&format!("export interface {} {{\n", object.name));
+        for field in object.fields.iter() {
+            let describe = shared::object_field_describe(&object.name, &field.name);
+            let descriptor = match self.cx.describe(&describe) {
+                Some(d) => d,
+                None => bail!("missing descriptor for field `{}`", field.name),
+            };
+            if field.comments.len() > 0 {
+                ts.push_str(&format_doc_comments(&field.comments));
+            }
+            match descriptor {
+                Descriptor::Optional(ty) => {
+                    ts.push_str(&format!("{}?: {};\n", field.name, object_field_ts_type(&ty)));
+                }
+                ty => {
+                    ts.push_str(&format!("{}: {};\n", field.name, object_field_ts_type(&ty)));
+                }
+            }
+        }
+        ts.push_str("}\n");
+        self.cx.typescript.push_str(&ts);
         Ok(())
     }
 
@@ -1937,6 +3148,25 @@ impl<'a, 'b> SubContext<'a, 'b> {
                 );
             }
 
+            // Deno has no notion of packages, so everything is imported by
+            // URL or relative path.
+            if self.cx.config.deno && !is_relative_or_url(module) {
+                bail!(
+                    "import from `{}` module not allowed with `--target deno`; \
+                     import it by URL or relative path instead",
+                    module
+                );
+            }
+            // Deno doesn't guess extensions either. URLs are left alone as the
+            // server decides what they resolve to.
+            if self.cx.config.deno && !module.contains("://") && !has_extension(module) {
+                bail!(
+                    "import from `{}` module not allowed with `--target deno`; \
+                     relative paths must include the file extension",
+                    module
+                );
+            }
+
             let name = import.js_namespace.as_ref().map(|s| &**s).unwrap_or(item);
 
             if self.cx.imported_names.insert(name.to_string()) {
@@ -1964,6 +3194,37 @@ impl<'a, 'b> SubContext<'a, 'b> {
     }
 }
 
+fn is_relative_or_url(module: &str) -> bool {
+    module.starts_with("./")
+        || module.starts_with("../")
+        || module.starts_with("/")
+        || module.contains("://")
+}
+
+fn has_extension(module: &str) -> bool {
+    Path::new(module).extension().is_some()
+}
+
+/// The TypeScript type of a field of a `#[derive(JsObject)]` struct.
+fn object_field_ts_type(ty: &Descriptor) -> String {
+    match *ty {
+        Descriptor::Boolean => "boolean".to_string(),
+        Descriptor::Char | Descriptor::String => "string".to_string(),
+        Descriptor::Optional(ref ty) => format!("{} | undefined", object_field_ts_type(ty)),
+        Descriptor::Vector(ref ty) => match **ty {
+            Descriptor::Optional(_) => format!("({})[]", object_field_ts_type(ty)),
+            ref ty => format!("{}[]", object_field_ts_type(ty)),
+        },
+        Descriptor::Object(ref name) => name.clone(),
+        ref ty if ty.is_number() => "number".to_string(),
+        _ => "any".to_string(),
+    }
+}
+
+/// Values in the first slots of the slab, which are never freed. Must be kept
+/// in sync with the `JSIDX_*` constants in `src/lib.rs`.
+const SLAB_RESERVED: &[&str] = &["null", "undefined", "true", "false"];
+
 fn format_doc_comments(comments: &Vec<String>) -> String {
     let body: String = comments
         .iter()
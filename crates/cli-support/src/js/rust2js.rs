@@ -1,6 +1,7 @@
 use failure::Error;
 
 use super::{Context, Js2Rust};
+use anyref::{Pass, Signature};
 use descriptor::{Descriptor, Function};
 
 /// Helper struct for manufacturing a shim in JS used to translate Rust types to
@@ -36,6 +37,12 @@ pub struct Rust2Js<'a, 'b: 'a> {
 
     /// Whether or not we're catching JS exceptions
     catch: bool,
+
+    /// Shim arguments which are passed as `anyref`s with `--anyref`.
+    anyref_args: Vec<(String, Pass)>,
+
+    /// How a JS value returned as an `anyref` is passed, if any.
+    anyref_ret: Option<Pass>,
 }
 
 impl<'a, 'b> Rust2Js<'a, 'b> {
@@ -50,6 +57,8 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
             arg_idx: 0,
             ret_expr: String::new(),
             catch: false,
+            anyref_args: Vec::new(),
+            anyref_ret: None,
         }
     }
 
@@ -144,6 +153,9 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
         if let Some((f, mutable)) = arg.stack_closure() {
             let (js, _ts) = {
                 let mut builder = Js2Rust::new("", self.cx);
+                // Closures are invoked through the function table rather
+                // than an export, so they always take indices.
+                builder.anyref(false);
                 if mutable {
                     builder
                         .prelude("let a = this.a;\n")
@@ -181,6 +193,9 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
         if let Some(closure) = arg.ref_closure() {
             let (js, _ts) = {
                 let mut builder = Js2Rust::new("", self.cx);
+                // Closures are invoked through the function table rather
+                // than an export, so they always take indices.
+                builder.anyref(false);
                 if closure.mutable {
                     builder
                         .prelude("let a = this.a;\n")
@@ -229,11 +244,27 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
             return Ok(());
         }
 
+        if self.cx.config.anyref {
+            match *arg {
+                Descriptor::Anyref | Descriptor::Object(_) => {
+                    self.anyref_args.push((abi.clone(), Pass::Owned));
+                    self.js_arguments.push(abi);
+                    return Ok(());
+                }
+                _ => {}
+            }
+            if arg.is_ref_anyref() {
+                self.anyref_args.push((abi.clone(), Pass::Borrowed));
+                self.js_arguments.push(abi);
+                return Ok(());
+            }
+        }
+
         let invoc_arg = match *arg {
             ref d if d.is_number() => abi,
             Descriptor::Boolean => format!("{} !== 0", abi),
             Descriptor::Char => format!("String.fromCodePoint({})", abi),
-            Descriptor::Anyref => {
+            Descriptor::Anyref | Descriptor::Object(_) => {
                 self.cx.expose_take_object();
                 format!("takeObject({})", abi)
             }
@@ -303,6 +334,15 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
             if ty.is_by_ref() {
                 bail!("cannot invoke JS functions returning custom ref types yet")
             }
+            let mut untrack = String::new();
+            if self.cx.config.debug {
+                self.cx.expose_live_class_ptrs();
+                untrack.push_str(&format!("classPtrs('{}').delete(ret);\n", class));
+            }
+            if self.cx.config.weak_refs {
+                self.cx.expose_class_registry();
+                untrack.push_str("classRegistry.unregister(val);\n");
+            }
             // Insert an assertion to the type of the returned value as
             // otherwise this will cause memory unsafety on the Rust side of
             // things.
@@ -313,15 +353,20 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
                 }}
                 const ret = val.ptr;
                 val.ptr = 0;
+                {1}
                 return ret;\
-            ", class);
+            ", class, untrack);
             return Ok(())
         }
 
         self.ret_expr = match *ty {
             Descriptor::Boolean => "return JS ? 1 : 0;".to_string(),
             Descriptor::Char => "return JS.codePointAt(0);".to_string(),
-            Descriptor::Anyref => {
+            Descriptor::Anyref | Descriptor::Object(_) if self.cx.config.anyref => {
+                self.anyref_ret = Some(Pass::Owned);
+                "return JS;".to_string()
+            }
+            Descriptor::Anyref | Descriptor::Object(_) => {
                 self.cx.expose_add_heap_object();
                 "return addHeapObject(JS);".to_string()
             }
@@ -330,6 +375,22 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
         Ok(())
     }
 
+    /// Which arguments of the shim, and whether its return value, are passed
+    /// as `anyref`s with `--anyref`.
+    pub fn anyref_signature(&self) -> Signature {
+        Signature {
+            args: self
+                .anyref_args
+                .iter()
+                .map(|&(ref name, pass)| {
+                    let i = self.shim_arguments.iter().position(|a| a == name);
+                    (i.unwrap(), pass)
+                })
+                .collect(),
+            ret: self.anyref_ret,
+        }
+    }
+
     pub fn finish(&self, invoc: &str) -> String {
         let mut ret = String::new();
         ret.push_str("function(");
@@ -4,12 +4,11 @@ extern crate wasm_bindgen_shared as shared;
 extern crate serde_derive;
 extern crate serde_json;
 extern crate wasm_gc;
-extern crate wasmi;
 #[macro_use]
 extern crate failure;
 
-use std::collections::BTreeSet;
-use std::fmt;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -17,39 +16,85 @@ use std::path::{Path, PathBuf};
 use failure::{Error, ResultExt};
 use parity_wasm::elements::*;
 
+mod anyref;
+mod binary;
 mod descriptor;
+mod interpreter;
 mod js;
+mod source_map;
+mod threads;
 pub mod wasm2es6js;
 
+#[derive(Clone)]
 pub struct Bindgen {
-    path: Option<PathBuf>,
+    input: Input,
     nodejs: bool,
     nodejs_experimental_modules: bool,
+    nodejs_module: bool,
+    nodejs_commonjs: bool,
+    top_level_await: bool,
+    // Set while generating the CommonJS half of `nodejs_commonjs` output
+    commonjs_entry: bool,
     browser: bool,
+    web: bool,
+    deno: bool,
     no_modules: bool,
     no_modules_global: Option<String>,
     debug: bool,
     typescript: bool,
     demangle: bool,
+    package_json: bool,
+    package_name: Option<String>,
+    package_version: Option<String>,
+    source_map: bool,
+    keep_debug: bool,
+    remove_name_section: bool,
+    weak_refs: bool,
+    anyref: bool,
+    threads: bool,
+    rethrow_panics: bool,
 }
 
 impl Bindgen {
     pub fn new() -> Bindgen {
         Bindgen {
-            path: None,
+            input: Input::None,
             nodejs: false,
             nodejs_experimental_modules: false,
+            nodejs_module: false,
+            nodejs_commonjs: false,
+            top_level_await: false,
+            commonjs_entry: false,
             browser: false,
+            web: false,
+            deno: false,
             no_modules: false,
             no_modules_global: None,
             debug: false,
             typescript: false,
             demangle: true,
+            package_json: false,
+            package_name: None,
+            package_version: None,
+            source_map: false,
+            keep_debug: false,
+            remove_name_section: false,
+            weak_refs: false,
+            anyref: false,
+            threads: false,
+            rethrow_panics: false,
         }
     }
 
     pub fn input_path<P: AsRef<Path>>(&mut self, path: P) -> &mut Bindgen {
-        self.path = Some(path.as_ref().to_path_buf());
+        self.input = Input::Path(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Use an in-memory wasm module as input, where `name` is used in place
+    /// of the file stem of `input_path` to name the generated files.
+    pub fn input_bytes(&mut self, name: &str, bytes: Vec<u8>) -> &mut Bindgen {
+        self.input = Input::Bytes(bytes, name.to_string());
         self
     }
 
@@ -63,11 +108,51 @@ impl Bindgen {
         self
     }
 
+    /// Generate native ES modules for Node, with a `.js` extension and fully
+    /// specified relative imports. Node only loads `.js` files as ES modules
+    /// within a package with `"type": "module"`, so a `package.json` is always
+    /// generated along with them.
+    pub fn nodejs_module(&mut self, node: bool) -> &mut Bindgen {
+        self.nodejs_module = node;
+        self
+    }
+
+    /// Along with the ES modules of `nodejs_module`, also generate CommonJS
+    /// `.cjs` entry points so a single package can be both imported and
+    /// required.
+    pub fn nodejs_commonjs(&mut self, commonjs: bool) -> &mut Bindgen {
+        self.nodejs_commonjs = commonjs;
+        self
+    }
+
+    /// With `nodejs_module`, instantiate the wasm module asynchronously with a
+    /// top-level `await` rather than synchronously compiling it.
+    pub fn top_level_await(&mut self, top_level_await: bool) -> &mut Bindgen {
+        self.top_level_await = top_level_await;
+        self
+    }
+
     pub fn browser(&mut self, browser: bool) -> &mut Bindgen {
         self.browser = browser;
         self
     }
 
+    /// Generate a native ES module for the web which doesn't need a bundler,
+    /// whose default export is an async `init` function that fetches and
+    /// instantiates the wasm module.
+    pub fn web(&mut self, web: bool) -> &mut Bindgen {
+        self.web = web;
+        self
+    }
+
+    /// Generate an ES module for Deno, which reads the wasm module next to it
+    /// with `Deno.readFile` (or `fetch` when loaded remotely) and instantiates
+    /// it with a top-level `await`.
+    pub fn deno(&mut self, deno: bool) -> &mut Bindgen {
+        self.deno = deno;
+        self
+    }
+
     pub fn no_modules(&mut self, no_modules: bool) -> &mut Bindgen {
         self.no_modules = no_modules;
         self
@@ -93,28 +178,256 @@ impl Bindgen {
         self
     }
 
+    /// Keep DWARF `.debug_*` custom sections in the output wasm, which are
+    /// otherwise removed.
+    pub fn keep_debug(&mut self, keep_debug: bool) -> &mut Bindgen {
+        self.keep_debug = keep_debug;
+        self
+    }
+
+    /// Remove the `name` section, holding function names for debuggers and
+    /// stack traces, from the output wasm to make it smaller.
+    pub fn remove_name_section(&mut self, remove: bool) -> &mut Bindgen {
+        self.remove_name_section = remove;
+        self
+    }
+
+    /// Free the Rust half of exported class instances automatically once the
+    /// JS object is garbage collected, using a `FinalizationRegistry` where
+    /// the JS engine supports it.
+    pub fn weak_refs(&mut self, weak_refs: bool) -> &mut Bindgen {
+        self.weak_refs = weak_refs;
+        self
+    }
+
+    /// Pass JS values to and from imports and exports as native `anyref`s,
+    /// keeping those owned by Rust in a table of `anyref`s in the wasm module
+    /// rather than in a heap of objects in JS. The generated wasm requires
+    /// reference types support from the JS engine.
+    pub fn anyref(&mut self, anyref: bool) -> &mut Bindgen {
+        self.anyref = anyref;
+        self
+    }
+
+    /// Generate a module whose memory is shared between instances, along
+    /// with JS to instantiate it on web workers which then run as threads of
+    /// the same program. Only supported with `web` and `no_modules`.
+    pub fn threads(&mut self, threads: bool) -> &mut Bindgen {
+        self.threads = threads;
+        self
+    }
+
+    /// Throw panics forwarded by `wasm_bindgen::panic::set_hook` as an
+    /// `Error` with the panic's message, rather than logging them with
+    /// `console.error` and leaving it to the trap which follows.
+    pub fn rethrow_panics(&mut self, rethrow_panics: bool) -> &mut Bindgen {
+        self.rethrow_panics = rethrow_panics;
+        self
+    }
+
+    /// Also generate a source map for the JS, mapping the JS generated for
+    /// each exported and imported function back to the Rust source of its
+    /// `#[wasm_bindgen]` item.
+    pub fn source_map(&mut self, source_map: bool) -> &mut Bindgen {
+        self.source_map = source_map;
+        self
+    }
+
+    /// Also generate a `package.json` so the output can be published to npm
+    /// as is, depending on every npm package imported with a `version`.
+    pub fn package_json(&mut self, package_json: bool) -> &mut Bindgen {
+        self.package_json = package_json;
+        self
+    }
+
+    /// The `name` of the generated `package.json`, which defaults to the name
+    /// of the input.
+    pub fn package_name(&mut self, name: &str) -> &mut Bindgen {
+        self.package_name = Some(name.to_string());
+        self
+    }
+
+    /// The `version` of the generated `package.json`, which defaults to
+    /// `0.1.0`.
+    pub fn package_version(&mut self, version: &str) -> &mut Bindgen {
+        self.package_version = Some(version.to_string());
+        self
+    }
+
     pub fn generate<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
-        self._generate(path.as_ref())
+        self.generate_output()?.emit(path)
+    }
+
+    /// Generate bindings without touching the filesystem, returning
+    /// everything that `generate` would write to the output directory.
+    pub fn generate_output(&mut self) -> Result<Output, Error> {
+        let (stem, contents) = match self.input {
+            Input::Path(ref path) => {
+                let mut contents = Vec::new();
+                File::open(&path)
+                    .and_then(|mut f| f.read_to_end(&mut contents))
+                    .with_context(|_| format!("failed to read `{}`", path.display()))?;
+                let stem = path.file_stem().unwrap().to_str().unwrap();
+                (stem, Cow::Owned(contents))
+            }
+            Input::Bytes(ref bytes, ref name) => (&name[..], Cow::Borrowed(&bytes[..])),
+            Input::None => bail!("must have an input wasm file or bytes"),
+        };
+        let mut output = self._generate(stem, &contents)?;
+        if self.nodejs_module && self.nodejs_commonjs {
+            // The CommonJS entry points load the same `*_bg.wasm`, which is
+            // generated identically by both passes.
+            let mut commonjs = self.clone();
+            commonjs.nodejs = true;
+            commonjs.nodejs_module = false;
+            commonjs.commonjs_entry = true;
+            commonjs.typescript = false;
+            let commonjs = commonjs._generate(stem, &contents)?;
+            output.extra_files.push((commonjs.js_file_name(), commonjs.js));
+            output.extra_files.extend(commonjs.extra_files);
+        }
+        if self.package_json || self.nodejs_module {
+            output.package_json = Some(self.generate_package_json(stem, &output)?);
+        }
+        Ok(output)
     }
 
-    fn _generate(&mut self, out_dir: &Path) -> Result<(), Error> {
-        let input = match self.path {
-            Some(ref path) => path,
-            None => bail!("must have a path input for now"),
+    fn generate_package_json(&self, stem: &str, output: &Output) -> Result<String, Error> {
+        #[derive(Serialize)]
+        struct PackageJson<'a> {
+            name: &'a str,
+            version: &'a str,
+            #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+            ty: Option<&'static str>,
+            files: Vec<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            main: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            module: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            browser: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            exports: Option<PackageExports>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            types: Option<String>,
+            #[serde(rename = "sideEffects", skip_serializing_if = "Option::is_none")]
+            side_effects: Option<bool>,
+            dependencies: BTreeMap<&'a str, &'a str>,
+        }
+
+        #[derive(Serialize)]
+        struct PackageExports {
+            import: String,
+            require: String,
+        }
+
+        let mut dependencies = BTreeMap::new();
+        for &(ref pkg, ref version) in output.module_versions.iter() {
+            if let Some(prev) = dependencies.insert(&pkg[..], &version[..]) {
+                if prev != &version[..] {
+                    bail!(
+                        "conflicting versions of npm package `{}` are imported: \
+                         `{}` and `{}`",
+                        pkg,
+                        prev,
+                        version
+                    );
+                }
+            }
+        }
+
+        let js = output.js_file_name();
+        let mut files = vec![js.clone(), format!("{}_bg.wasm", stem)];
+        files.extend(output.extra_files.iter().map(|&(ref name, _)| name.clone()));
+        let types = output.typescript.as_ref().map(|_| format!("{}.d.ts", stem));
+        files.extend(types.clone());
+
+        let mut package = PackageJson {
+            name: self.package_name.as_ref().map(|s| &**s).unwrap_or(stem),
+            version: self
+                .package_version
+                .as_ref()
+                .map(|s| &**s)
+                .unwrap_or("0.1.0"),
+            ty: None,
+            files,
+            main: None,
+            module: None,
+            browser: None,
+            exports: None,
+            types,
+            side_effects: None,
+            dependencies,
         };
-        let stem = input.file_stem().unwrap().to_str().unwrap();
-        let mut contents = Vec::new();
-        File::open(&input)
-            .and_then(|mut f| f.read_to_end(&mut contents))
-            .with_context(|_| format!("failed to read `{}`", input.display()))?;
-        let mut module = parity_wasm::deserialize_buffer::<Module>(&contents)
+        if self.nodejs_module {
+            package.ty = Some("module");
+            if self.nodejs_commonjs {
+                package.exports = Some(PackageExports {
+                    import: format!("./{}", js),
+                    require: format!("./{}.cjs", stem),
+                });
+            }
+            package.main = Some(js);
+        } else if self.nodejs {
+            package.main = Some(js);
+        } else if self.no_modules {
+            package.browser = Some(js);
+        } else if self.deno {
+            package.module = Some(js);
+        } else {
+            // Bundlers prefer `module`, while `main` is there for tools which
+            // only know about that. Nothing is run until the bindings are used,
+            // so bundlers are free to drop them entirely when they're not.
+            package.main = Some(js.clone());
+            package.module = Some(js);
+            package.side_effects = Some(false);
+        }
+        Ok(serde_json::to_string_pretty(&package)?)
+    }
+
+    /// Whether the generated JS targets Node, whether as CommonJS or as ES
+    /// modules.
+    fn node(&self) -> bool {
+        self.nodejs || self.nodejs_module
+    }
+
+    /// Whether the generated JS for Node uses ES modules.
+    fn node_esm(&self) -> bool {
+        self.nodejs_experimental_modules || self.nodejs_module
+    }
+
+    /// The extension of generated JS files.
+    fn js_extension(&self) -> &'static str {
+        if self.nodejs_experimental_modules {
+            "mjs"
+        } else if self.commonjs_entry {
+            "cjs"
+        } else {
+            "js"
+        }
+    }
+
+    /// What to append to relative imports between generated JS files, which
+    /// need to be fully specified when Node resolves them.
+    fn import_suffix(&self) -> &'static str {
+        if self.nodejs_module {
+            ".js"
+        } else if self.commonjs_entry {
+            ".cjs"
+        } else {
+            ""
+        }
+    }
+
+    fn _generate(&self, stem: &str, contents: &[u8]) -> Result<Output, Error> {
+        let mut module = parity_wasm::deserialize_buffer::<Module>(contents)
             .with_context(|_| "failed to parse input file as wasm")?;
         let programs = extract_programs(&mut module)
             .with_context(|_| "failed to extract wasm-bindgen custom sections")?;
 
-        // Here we're actually instantiating the module we've parsed above for
-        // execution. Why, you might be asking, are we executing wasm code? A
-        // good question!
+        // Here we're actually executing parts of the module we've parsed
+        // above. Why, you might be asking, are we executing wasm code? A good
+        // question!
         //
         // Transmitting information from `#[wasm_bindgen]` here to the CLI tool
         // is pretty tricky. Specifically information about the types involved
@@ -122,16 +435,14 @@ impl Bindgen {
         // translate over. As a result, the macro emits a bunch of shims which,
         // when executed, will describe to us what the types look like.
         //
-        // This means that whenever we encounter an import or export we'll
-        // execute a shim function which informs us about its type so we can
-        // then generate the appropriate bindings.
-        let instance = wasmi::Module::from_buffer(&contents)
-            .with_context(|_| "failed to create wasmi module")?;
-        let instance = wasmi::ModuleInstance::new(&instance, &MyResolver)
-            .with_context(|_| "failed to instantiate wasm module")?;
-        let instance = instance.not_started_instance();
-
-        let (js, ts) = {
+        // This means that for every import and export there's a shim function
+        // which informs us about its type, and we run all of them up front
+        // with a small interpreter to generate the appropriate bindings. The
+        // shims are no longer exported afterwards, so they're removed from
+        // the output along with the `__wbindgen_describe` import they call.
+        let descriptors = interpreter::interpret_descriptors(&module)?;
+
+        let (js, ts, module_versions, source_locations, anyref) = {
             let mut cx = js::Context {
                 globals: String::new(),
                 imports: String::new(),
@@ -145,20 +456,12 @@ impl Bindgen {
                 module: &mut module,
                 function_table_needed: false,
                 module_versions: Default::default(),
-                run_descriptor: &|name| {
-                    let mut v = MyExternals(Vec::new());
-                    match instance.invoke_export(name, &[], &mut v) {
-                        Ok(None) => Some(v.0),
-                        Ok(Some(_)) => unreachable!(
-                            "there is only one export, and we only return None from it"
-                        ),
-                        // Allow missing exported describe functions. This can
-                        // happen when a nested dependency crate exports things
-                        // but the root crate doesn't use them.
-                        Err(wasmi::Error::Function(_)) => None,
-                        Err(e) => panic!("unexpected error running descriptor: {}", e),
-                    }
-                },
+                source_locations: Default::default(),
+                anyref: Default::default(),
+                // Missing descriptors are allowed. This can happen when a
+                // nested dependency crate exports things but the root crate
+                // doesn't use them.
+                run_descriptor: &|name| descriptors.get(name).cloned(),
             };
             for program in programs.iter() {
                 js::SubContext {
@@ -166,40 +469,57 @@ impl Bindgen {
                     cx: &mut cx,
                 }.generate()?;
             }
-            cx.finalize(stem)?
+            let (js, ts) = cx.finalize(stem)?;
+            (js, ts, cx.module_versions, cx.source_locations, cx.anyref)
         };
 
-        let extension = if self.nodejs_experimental_modules { "mjs" } else { "js" };
-        let js_path = out_dir.join(stem).with_extension(extension);
-        File::create(&js_path)
-            .and_then(|mut f| f.write_all(reset_indentation(&js).as_bytes()))
-            .with_context(|_| format!("failed to write `{}`", js_path.display()))?;
-
-        if self.typescript {
-            let ts_path = out_dir.join(stem).with_extension("d.ts");
-            File::create(&ts_path)
-                .and_then(|mut f| f.write_all(ts.as_bytes()))
-                .with_context(|_| format!("failed to write `{}`", ts_path.display()))?;
+        let extension = self.js_extension();
+        let wasm_file_name = format!("{}_bg.wasm", stem);
+        let mut extra_files = Vec::new();
+        if self.node() {
+            let shim = self.generate_node_wasm_import(&module, &wasm_file_name);
+            extra_files.push((format!("{}_bg.{}", stem, extension), shim));
+        }
+
+        if self.remove_name_section {
+            module.sections_mut().retain(|s| match *s {
+                Section::Custom(ref s) => s.name() != "name",
+                _ => true,
+            });
         }
 
-        let wasm_path = out_dir.join(format!("{}_bg", stem)).with_extension("wasm");
+        let mut js = reset_indentation(&js);
+        if self.source_map {
+            let js_file_name = format!("{}.{}", stem, extension);
+            let (stripped, map) = source_map::extract(&js, &source_locations, &js_file_name);
+            js = format!("{}\n//# sourceMappingURL={}.map\n", stripped, js_file_name);
+            extra_files.push((format!("{}.map", js_file_name), map));
+        }
 
-        if self.nodejs {
-            let js_path = wasm_path.with_extension(extension);
-            let shim = self.generate_node_wasm_import(&module, &wasm_path);
-            File::create(&js_path)
-                .and_then(|mut f| f.write_all(shim.as_bytes()))
-                .with_context(|_| format!("failed to write `{}`", js_path.display()))?;
+        // With `--anyref` and `--threads` the final module can't be
+        // represented with `parity_wasm`, so the last step happens after
+        // serializing it.
+        let mut wasm = parity_wasm::serialize(module)?;
+        if self.threads {
+            wasm = threads::finish(&wasm)?;
+        }
+        if self.anyref {
+            wasm = anyref.finish(&wasm)?;
         }
 
-        let wasm_bytes = parity_wasm::serialize(module)?;
-        File::create(&wasm_path)
-            .and_then(|mut f| f.write_all(&wasm_bytes))
-            .with_context(|_| format!("failed to write `{}`", wasm_path.display()))?;
-        Ok(())
+        Ok(Output {
+            stem: stem.to_string(),
+            js_extension: extension,
+            js,
+            typescript: if self.typescript { Some(ts) } else { None },
+            wasm,
+            extra_files,
+            package_json: None,
+            module_versions,
+        })
     }
 
-    fn generate_node_wasm_import(&self, m: &Module, path: &Path) -> String {
+    fn generate_node_wasm_import(&self, m: &Module, wasm_file_name: &str) -> String {
         let mut imports = BTreeSet::new();
         if let Some(i) = m.import_section() {
             for i in i.entries() {
@@ -209,7 +529,18 @@ impl Bindgen {
 
         let mut shim = String::new();
 
-        if self.nodejs_experimental_modules {
+        if self.nodejs_module {
+            for (i, module) in imports.iter().enumerate() {
+                shim.push_str(&format!("import * as import{} from '{}';\n",
+                                       i, self.import_path(module)));
+            }
+            shim.push_str(&format!("
+                import {{ readFileSync }} from 'fs';
+                import {{ fileURLToPath }} from 'url';
+
+                const bytes = readFileSync(fileURLToPath(new URL('./{}', import.meta.url)));
+            ", wasm_file_name));
+        } else if self.nodejs_experimental_modules {
             for (i, module) in imports.iter().enumerate() {
                 shim.push_str(&format!("import * as import{} from '{}';\n",
                                        i, module));
@@ -227,30 +558,39 @@ impl Bindgen {
                     file = file.substring(1);
                 }}
                 const bytes = fs.readFileSync(path.join(file, '{}'));
-            ", path.file_name().unwrap().to_str().unwrap()));
+            ", wasm_file_name));
         } else {
             shim.push_str(&format!("
                 const path = require('path').join(__dirname, '{}');
                 const bytes = require('fs').readFileSync(path);
-            ", path.file_name().unwrap().to_str().unwrap()));
+            ", wasm_file_name));
         }
         shim.push_str("let imports = {};\n");
         for (i, module) in imports.iter().enumerate() {
-            if self.nodejs_experimental_modules {
+            if self.node_esm() {
                 shim.push_str(&format!("imports['{}'] = import{};\n", module, i));
             } else {
-                shim.push_str(&format!("imports['{0}'] = require('{0}');\n", module));
+                shim.push_str(&format!("imports['{}'] = require('{}');\n",
+                                       module, self.import_path(module)));
             }
         }
 
-        shim.push_str(&format!(
-            "
+        if self.nodejs_module && self.top_level_await {
+            shim.push_str(
+                "
+                const wasmInstance = (await WebAssembly.instantiate(bytes, imports)).instance;
+                ",
+            );
+        } else {
+            shim.push_str(
+                "
                 const wasmModule = new WebAssembly.Module(bytes);
                 const wasmInstance = new WebAssembly.Instance(wasmModule, imports);
-            ",
-        ));
+                ",
+            );
+        }
 
-        if self.nodejs_experimental_modules {
+        if self.node_esm() {
             if let Some(e) = m.export_section() {
                 for name in e.entries().iter().map(|e| e.field()) {
                     shim.push_str("export const ");
@@ -266,6 +606,93 @@ impl Bindgen {
 
         reset_indentation(&shim)
     }
+
+    /// The path to import `module` from, fully specifying relative imports
+    /// of generated JS files.
+    fn import_path(&self, module: &str) -> String {
+        if module.starts_with("./") {
+            format!("{}{}", module, self.import_suffix())
+        } else {
+            module.to_string()
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Input {
+    None,
+    Path(PathBuf),
+    Bytes(Vec<u8>, String),
+}
+
+/// Everything generated for a wasm module by `Bindgen::generate_output`.
+pub struct Output {
+    stem: String,
+    js_extension: &'static str,
+    js: String,
+    typescript: Option<String>,
+    wasm: Vec<u8>,
+    extra_files: Vec<(String, String)>,
+    package_json: Option<String>,
+    module_versions: Vec<(String, String)>,
+}
+
+impl Output {
+    /// The generated JS bindings.
+    pub fn js(&self) -> &str {
+        &self.js
+    }
+
+    /// The name of the file the JS bindings are written to, e.g. `foo.js`.
+    pub fn js_file_name(&self) -> String {
+        format!("{}.{}", self.stem, self.js_extension)
+    }
+
+    /// The TypeScript definitions for the JS bindings, if enabled.
+    pub fn typescript(&self) -> Option<&str> {
+        self.typescript.as_ref().map(|s| &**s)
+    }
+
+    /// The wasm module imported by the JS bindings, written to
+    /// `{stem}_bg.wasm`.
+    pub fn wasm(&self) -> &[u8] {
+        &self.wasm
+    }
+
+    /// Any other JS files the bindings depend on, such as the shim which loads
+    /// the wasm module in Node, as pairs of file name and contents.
+    pub fn extra_files(&self) -> &[(String, String)] {
+        &self.extra_files
+    }
+
+    /// The generated `package.json`, if enabled.
+    pub fn package_json(&self) -> Option<&str> {
+        self.package_json.as_ref().map(|s| &**s)
+    }
+
+    /// Write all of the generated files into the directory `out_dir`.
+    pub fn emit<P: AsRef<Path>>(&self, out_dir: P) -> Result<(), Error> {
+        let out_dir = out_dir.as_ref();
+        let write = |name: &str, contents: &[u8]| -> Result<(), Error> {
+            let path = out_dir.join(name);
+            File::create(&path)
+                .and_then(|mut f| f.write_all(contents))
+                .with_context(|_| format!("failed to write `{}`", path.display()))?;
+            Ok(())
+        };
+
+        write(&self.js_file_name(), self.js.as_bytes())?;
+        if let Some(ref ts) = self.typescript {
+            write(&format!("{}.d.ts", self.stem), ts.as_bytes())?;
+        }
+        for &(ref name, ref contents) in self.extra_files.iter() {
+            write(name, contents.as_bytes())?;
+        }
+        if let Some(ref package_json) = self.package_json {
+            write("package.json", package_json.as_bytes())?;
+        }
+        write(&format!("{}_bg.wasm", self.stem), &self.wasm)
+    }
 }
 
 fn extract_programs(module: &mut Module) -> Result<Vec<shared::Program>, Error> {
@@ -337,106 +764,6 @@ to open an issue at https://github.com/alexcrichton/wasm-bindgen/issues!
     Ok(ret)
 }
 
-struct MyResolver;
-
-impl wasmi::ImportResolver for MyResolver {
-    fn resolve_func(
-        &self,
-        module_name: &str,
-        field_name: &str,
-        signature: &wasmi::Signature,
-    ) -> Result<wasmi::FuncRef, wasmi::Error> {
-        // Route our special "describe" export to 1 and everything else to 0.
-        // That way whenever the function 1 is invoked we know what to do and
-        // when 0 is invoked (by accident) we'll trap and produce an error.
-        let idx = (module_name == "__wbindgen_placeholder__" && field_name == "__wbindgen_describe")
-            as usize;
-        Ok(wasmi::FuncInstance::alloc_host(signature.clone(), idx))
-    }
-
-    fn resolve_global(
-        &self,
-        _module_name: &str,
-        _field_name: &str,
-        descriptor: &wasmi::GlobalDescriptor,
-    ) -> Result<wasmi::GlobalRef, wasmi::Error> {
-        // dummy implementation to ensure instantiation succeeds
-        let val = match descriptor.value_type() {
-            wasmi::ValueType::I32 => wasmi::RuntimeValue::I32(0),
-            wasmi::ValueType::I64 => wasmi::RuntimeValue::I64(0),
-            wasmi::ValueType::F32 => wasmi::RuntimeValue::F32(0.0.into()),
-            wasmi::ValueType::F64 => wasmi::RuntimeValue::F64(0.0.into()),
-        };
-        Ok(wasmi::GlobalInstance::alloc(val, descriptor.is_mutable()))
-    }
-
-    fn resolve_memory(
-        &self,
-        _module_name: &str,
-        _field_name: &str,
-        descriptor: &wasmi::MemoryDescriptor,
-    ) -> Result<wasmi::MemoryRef, wasmi::Error> {
-        // dummy implementation to ensure instantiation succeeds
-        use wasmi::memory_units::Pages;
-        let initial = Pages(descriptor.initial() as usize);
-        let maximum = descriptor.maximum().map(|i| Pages(i as usize));
-        wasmi::MemoryInstance::alloc(initial, maximum)
-    }
-
-    fn resolve_table(
-        &self,
-        _module_name: &str,
-        _field_name: &str,
-        descriptor: &wasmi::TableDescriptor,
-    ) -> Result<wasmi::TableRef, wasmi::Error> {
-        // dummy implementation to ensure instantiation succeeds
-        let initial = descriptor.initial();
-        let maximum = descriptor.maximum();
-        wasmi::TableInstance::alloc(initial, maximum)
-    }
-}
-
-struct MyExternals(Vec<u32>);
-
-#[derive(Debug)]
-struct MyError(String);
-
-impl wasmi::Externals for MyExternals {
-    fn invoke_index(
-        &mut self,
-        index: usize,
-        args: wasmi::RuntimeArgs,
-    ) -> Result<Option<wasmi::RuntimeValue>, wasmi::Trap> {
-        macro_rules! bail {
-            ($($t:tt)*) => ({
-                let s = MyError(format!($($t)*));
-                return Err(wasmi::Trap::new(wasmi::TrapKind::Host(Box::new(s))))
-            })
-        }
-        // We only recognize one function here which was mapped to the index 1
-        // by the resolver above.
-        if index != 1 {
-            bail!("only __wbindgen_describe can be run at this time")
-        }
-        if args.len() != 1 {
-            bail!("must have exactly one argument");
-        }
-        match args.nth_value_checked(0)? {
-            wasmi::RuntimeValue::I32(i) => self.0.push(i as u32),
-            _ => bail!("expected one argument of i32 type"),
-        }
-        Ok(None)
-    }
-}
-
-impl wasmi::HostError for MyError {}
-
-impl fmt::Display for MyError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.0.fmt(f)
-    }
-}
-
 fn reset_indentation(s: &str) -> String {
     indent_recurse(s.lines(), 0)
 }
@@ -0,0 +1,109 @@
+//! Source maps from generated JS back to the `#[wasm_bindgen]` items in Rust
+//! source that the JS was generated for.
+//!
+//! While generating JS, each shim is surrounded by marker comments referring
+//! to the location of its Rust item. Once the JS is complete the markers are
+//! stripped out again by `extract`, which maps every line between them to the
+//! Rust item.
+
+use std::collections::HashMap;
+
+use serde_json;
+use shared;
+
+const START: &str = "/*wbg-source-map:";
+pub const END_MARKER: &str = "/*wbg-source-map-end*/\n";
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The marker starting JS generated for the item at `locations[idx]`, ended by
+/// `END_MARKER`.
+pub fn start_marker(idx: usize) -> String {
+    format!("{}{}*/\n", START, idx)
+}
+
+/// Removes all markers from `js`, returning the remaining JS along with the
+/// source map for it, where `file` is the name the JS is written to.
+pub fn extract(js: &str, locations: &[shared::Location], file: &str) -> (String, String) {
+    #[derive(Serialize)]
+    struct SourceMap<'a> {
+        version: u32,
+        file: &'a str,
+        sources: Vec<&'a str>,
+        names: Vec<&'a str>,
+        mappings: String,
+    }
+
+    let mut lines = Vec::new();
+    let mut sources = Vec::new();
+    let mut source_indices = HashMap::new();
+    let mut mappings = String::new();
+    let mut current = None;
+    // Everything but the generated column is relative to the previous segment
+    let mut prev = (0, 0, 0);
+
+    for line in js.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with(START) {
+            let idx = &trimmed[START.len()..trimmed.len() - 2];
+            current = Some(&locations[idx.parse::<usize>().unwrap()]);
+            continue;
+        }
+        if trimmed == END_MARKER.trim() {
+            current = None;
+            continue;
+        }
+
+        if lines.len() > 0 {
+            mappings.push(';');
+        }
+        lines.push(line);
+
+        let location = match current {
+            Some(location) if trimmed.len() > 0 => location,
+            _ => continue,
+        };
+        let source = *source_indices
+            .entry(&location.file[..])
+            .or_insert_with(|| {
+                sources.push(&location.file[..]);
+                sources.len() - 1
+            }) as i64;
+        let line = location.line as i64 - 1;
+        let column = location.column as i64;
+        vlq(0, &mut mappings);
+        vlq(source - prev.0, &mut mappings);
+        vlq(line - prev.1, &mut mappings);
+        vlq(column - prev.2, &mut mappings);
+        prev = (source, line, column);
+    }
+
+    let map = serde_json::to_string(&SourceMap {
+        version: 3,
+        file,
+        sources,
+        names: Vec::new(),
+        mappings,
+    }).unwrap();
+    (lines.join("\n"), map)
+}
+
+/// Appends `value` to `dst` as a base64 VLQ.
+fn vlq(value: i64, dst: &mut String) {
+    let mut value = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = value & 0b11111;
+        value >>= 5;
+        if value > 0 {
+            digit |= 0b100000;
+        }
+        dst.push(BASE64[digit as usize] as char);
+        if value == 0 {
+            break;
+        }
+    }
+}
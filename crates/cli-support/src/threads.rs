@@ -0,0 +1,515 @@
+//! Support for `--threads`, instantiating the module on several web workers
+//! which share one memory.
+//!
+//! Each instance has its own globals, so the stack pointer and the pointer to
+//! the `wasm-bindgen` runtime's per-thread state are kept in globals, which
+//! the generated JS sets when starting a thread. Memory on the other hand is
+//! shared, so it's imported from JS rather than defined by the module. As
+//! instantiating the module copies its data segments into memory, which
+//! mustn't clobber what the program has since modified, the offsets of the
+//! data segments are imported as well so that other threads can copy them
+//! somewhere harmless instead.
+//!
+//! `parity_wasm` can't represent shared memories, so the import of the memory
+//! is only marked shared by `finish` in the serialized module.
+//!
+//! Thread-local storage is laid out by LLD like any other data, with the main
+//! thread's TLS block at the address `__tls_base` starts out with. Other
+//! threads get a TLS block of their own when they're allocated, which the
+//! generated JS initializes from the copy of the data segments on the new
+//! thread's stack and points `__tls_base` at through the `TLS_BASE_SET`
+//! export.
+
+use failure::Error;
+use parity_wasm::elements::*;
+
+use binary;
+
+/// Module of the imports added for `--threads`.
+pub const IMPORT_MODULE: &str = "__wbindgen_threads";
+
+/// Export setting the pointer to the per-thread state of the runtime.
+pub const THREAD_PTR_SET: &str = "__wbindgen_thread_ptr_set";
+
+/// Export setting the stack pointer.
+pub const STACK_POINTER_SET: &str = "__wbindgen_stack_pointer_set";
+
+/// Export setting the base address of the current thread's TLS block, which
+/// is only added for modules using thread-local storage.
+pub const TLS_BASE_SET: &str = "__wbindgen_tls_base_set";
+
+const THREAD_PTR: &str = "__wbindgen_thread_ptr";
+
+const TLS_BASE: &str = "__tls_base";
+const TLS_SIZE: &str = "__tls_size";
+const TLS_ALIGN: &str = "__tls_align";
+
+/// Maximum size of the shared memory in pages, if the module doesn't specify
+/// one. Unlike other memories shared memories must have a maximum size.
+const DEFAULT_MAXIMUM_PAGES: u32 = 16384;
+
+/// Implements the `__wbindgen_thread_ptr` intrinsic of the runtime in wasm.
+///
+/// With `threads` it reads a new global which can be set through the
+/// `THREAD_PTR_SET` export, and the stack pointer can be set through the
+/// `STACK_POINTER_SET` export, as can `__tls_base` through the `TLS_BASE_SET`
+/// export if the module has one. Otherwise it always returns null, which
+/// refers to the state of the main thread.
+///
+/// Calls to the import are redirected to the implementation, so the import
+/// itself is removed the next time the module is garbage collected.
+pub fn implement_thread_ptr(module: &mut Module, threads: bool) -> Result<(), Error> {
+    let get = if threads {
+        let stack_pointer = match stack_pointer(module) {
+            Some(g) => g,
+            None => {
+                bail!("failed to find the stack pointer global, which is required for `--threads`")
+            }
+        };
+        let thread_ptr = add_global(
+            module,
+            GlobalEntry::new(
+                GlobalType::new(ValueType::I32, true),
+                InitExpr::new(vec![Instruction::I32Const(0), Instruction::End]),
+            ),
+        );
+        let mut setters = vec![
+            (THREAD_PTR_SET, thread_ptr),
+            (STACK_POINTER_SET, stack_pointer),
+        ];
+        if let Some(tls_base) = exported_global(module, TLS_BASE) {
+            setters.push((TLS_BASE_SET, tls_base));
+        }
+        let set = FunctionType::new(vec![ValueType::I32], None);
+        for &(name, global) in setters.iter() {
+            let f = add_function(
+                module,
+                set.clone(),
+                vec![
+                    Instruction::GetLocal(0),
+                    Instruction::SetGlobal(global),
+                    Instruction::End,
+                ],
+            )?;
+            add_export(module, name, Internal::Function(f))?;
+        }
+        Instruction::GetGlobal(thread_ptr)
+    } else {
+        Instruction::I32Const(0)
+    };
+
+    let import = match function_import(module, THREAD_PTR) {
+        Some(i) => i,
+        None => return Ok(()),
+    };
+    let f = add_function(
+        module,
+        FunctionType::new(Vec::new(), Some(ValueType::I32)),
+        vec![get, Instruction::End],
+    )?;
+
+    if let Some(code) = module.code_section_mut() {
+        for body in code.bodies_mut() {
+            for instr in body.code_mut().elements_mut() {
+                if let Instruction::Call(ref mut i) = *instr {
+                    if *i == import {
+                        *i = f;
+                    }
+                }
+            }
+        }
+    }
+    if let Some(elements) = module.elements_section_mut() {
+        for segment in elements.entries_mut() {
+            for i in segment.members_mut() {
+                if *i == import {
+                    *i = f;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the module uses thread-local storage, in which case
+/// `implement_thread_ptr` adds the `TLS_BASE_SET` export.
+pub fn has_tls(module: &Module) -> bool {
+    exported_global(module, TLS_BASE).is_some()
+}
+
+/// The memory of the module and its data segments, which the generated JS
+/// needs to know about to create the shared memory and start threads.
+pub struct Memory {
+    /// Initial size of the memory in pages.
+    pub initial: u32,
+    /// Maximum size of the memory in pages.
+    pub maximum: u32,
+    /// Offsets of the data segments, each of which is imported as a global.
+    pub data_offsets: Vec<u32>,
+    /// Start of the area spanned by the data segments.
+    pub data_start: u32,
+    /// Size of the area spanned by the data segments, and the main thread's
+    /// TLS block.
+    pub data_size: u32,
+    /// The thread-local storage of the module, if it has any.
+    pub tls: Option<Tls>,
+}
+
+/// The layout of the TLS block of each thread.
+pub struct Tls {
+    /// Address of the main thread's TLS block, which the data segments fill
+    /// in with the initial values of thread-locals.
+    pub base: u32,
+    /// Size of a TLS block.
+    pub size: u32,
+    /// Alignment of a TLS block.
+    pub align: u32,
+}
+
+impl Memory {
+    pub fn read(module: &Module) -> Result<Memory, Error> {
+        if let Some(imports) = module.import_section() {
+            for entry in imports.entries() {
+                if let External::Memory(_) = *entry.external() {
+                    bail!("`--threads` requires a module which doesn't import its memory");
+                }
+            }
+        }
+        let tls = match exported_global(module, TLS_BASE) {
+            Some(base) => Some(Tls {
+                base: constant_global(module, base, TLS_BASE)?,
+                size: match exported_global(module, TLS_SIZE) {
+                    Some(size) => constant_global(module, size, TLS_SIZE)?,
+                    None => bail!("`--threads` requires `{}` to be exported", TLS_SIZE),
+                },
+                align: match exported_global(module, TLS_ALIGN) {
+                    Some(align) => constant_global(module, align, TLS_ALIGN)?,
+                    None => 1,
+                },
+            }),
+            None => {
+                if exported_global(module, TLS_SIZE).is_some() {
+                    bail!(
+                        "`--threads` requires `{}` to be exported to set up \
+                         thread-local storage",
+                        TLS_BASE
+                    );
+                }
+                None
+            }
+        };
+        let limits = match module.memory_section().and_then(|s| s.entries().get(0)) {
+            Some(memory) => memory.limits(),
+            None => bail!("`--threads` requires a module with a memory"),
+        };
+
+        let mut data_offsets = Vec::new();
+        let mut data_start = u32::max_value();
+        let mut data_end = 0;
+        if let Some(data) = module.data_section() {
+            for segment in data.entries() {
+                let offset = match segment.offset().code() {
+                    [Instruction::I32Const(offset), Instruction::End] => *offset as u32,
+                    _ => bail!("`--threads` requires data segments to have constant offsets"),
+                };
+                data_offsets.push(offset);
+                data_start = data_start.min(offset);
+                data_end = data_end.max(offset + segment.value().len() as u32);
+            }
+        }
+        // The TLS block of the main thread is copied from the data segments
+        // along with them, including any zeroed part which isn't in a segment.
+        if let Some(ref tls) = tls {
+            data_start = data_start.min(tls.base);
+            data_end = data_end.max(tls.base + tls.size);
+        }
+        if data_start > data_end {
+            data_start = 0;
+        }
+
+        Ok(Memory {
+            initial: limits.initial(),
+            maximum: limits.maximum().unwrap_or(DEFAULT_MAXIMUM_PAGES),
+            data_offsets,
+            data_start,
+            data_size: data_end - data_start,
+            tls,
+        })
+    }
+}
+
+/// Replaces the memory of the module with one imported from `IMPORT_MODULE`,
+/// and imports the offsets of data segments from there as well.
+pub fn share_memory(module: &mut Module, memory: &Memory) -> Result<(), Error> {
+    module.sections_mut().retain(|s| match *s {
+        Section::Memory(_) => false,
+        _ => true,
+    });
+
+    // The offsets are imported after all the other imported globals, so the
+    // indices of the globals defined in the module shift.
+    let imported_globals = imported(module, |e| match *e {
+        External::Global(_) => true,
+        _ => false,
+    });
+    let shift = memory.data_offsets.len() as u32;
+    let remap = |i: &mut u32| {
+        if *i >= imported_globals {
+            *i += shift;
+        }
+    };
+    if let Some(code) = module.code_section_mut() {
+        for body in code.bodies_mut() {
+            for instr in body.code_mut().elements_mut() {
+                match *instr {
+                    Instruction::GetGlobal(ref mut i) | Instruction::SetGlobal(ref mut i) => {
+                        remap(i)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    if let Some(exports) = module.export_section_mut() {
+        for entry in exports.entries_mut() {
+            if let Internal::Global(ref mut i) = *entry.internal_mut() {
+                remap(i);
+            }
+        }
+    }
+
+    let mut imports = vec![ImportEntry::new(
+        IMPORT_MODULE.to_string(),
+        "memory".to_string(),
+        External::Memory(MemoryType::new(memory.initial, Some(memory.maximum))),
+    )];
+    for i in 0..memory.data_offsets.len() {
+        imports.push(ImportEntry::new(
+            IMPORT_MODULE.to_string(),
+            format!("data{}", i),
+            External::Global(GlobalType::new(ValueType::I32, false)),
+        ));
+    }
+    if module.import_section().is_none() {
+        insert_section(module, Section::Import(ImportSection::default()));
+    }
+    module
+        .import_section_mut()
+        .unwrap()
+        .entries_mut()
+        .extend(imports);
+
+    if let Some(data) = module.data_section_mut() {
+        for (i, segment) in data.entries_mut().iter_mut().enumerate() {
+            let global = imported_globals + i as u32;
+            *segment.offset_mut() =
+                InitExpr::new(vec![Instruction::GetGlobal(global), Instruction::End]);
+        }
+    }
+    Ok(())
+}
+
+/// Marks the memory imported by `share_memory` as shared in the serialized
+/// `wasm`.
+pub fn finish(wasm: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut sections = binary::sections(wasm)?;
+    for &mut (id, ref mut payload) in sections.iter_mut() {
+        if id != 2 {
+            continue;
+        }
+        let mut pos = 0;
+        let count = binary::read_u32(payload, &mut pos)?;
+        for _ in 0..count {
+            let module = binary::read_name(payload, &mut pos)? == IMPORT_MODULE.as_bytes();
+            let memory = binary::read_name(payload, &mut pos)? == b"memory";
+            let kind = *binary::byte(payload, pos)?;
+            pos += 1;
+            match kind {
+                0 => {
+                    binary::read_u32(payload, &mut pos)?;
+                }
+                1 => {
+                    pos += 1;
+                    binary::read_limits(payload, &mut pos)?;
+                }
+                2 => {
+                    if module && memory {
+                        // Shared memories must have a maximum, which is
+                        // always there as `share_memory` specifies one.
+                        payload[pos] |= 0x02;
+                    }
+                    binary::read_limits(payload, &mut pos)?;
+                }
+                3 => pos += 2,
+                _ => bail!("unknown import kind {}", kind),
+            }
+        }
+    }
+    Ok(binary::module(wasm, sections))
+}
+
+/// The function index of the import of `name` from the runtime.
+fn function_import(module: &Module, name: &str) -> Option<u32> {
+    let mut f = 0;
+    for entry in module.import_section()?.entries() {
+        if let External::Function(_) = *entry.external() {
+            if entry.module() == "__wbindgen_placeholder__" && entry.field() == name {
+                return Some(f);
+            }
+            f += 1;
+        }
+    }
+    None
+}
+
+/// The stack pointer global, which is the first mutable `i32` global the
+/// module defines in modules produced by LLD.
+fn stack_pointer(module: &Module) -> Option<u32> {
+    let imported_globals = imported(module, |e| match *e {
+        External::Global(_) => true,
+        _ => false,
+    });
+    module
+        .global_section()?
+        .entries()
+        .iter()
+        .position(|g| {
+            let ty = g.global_type();
+            ty.is_mutable() && ty.content_type() == ValueType::I32
+        })
+        .map(|i| imported_globals + i as u32)
+}
+
+/// The index of the global exported as `name`, if any.
+fn exported_global(module: &Module, name: &str) -> Option<u32> {
+    module
+        .export_section()?
+        .entries()
+        .iter()
+        .filter(|e| e.field() == name)
+        .filter_map(|e| match *e.internal() {
+            Internal::Global(i) => Some(i),
+            _ => None,
+        })
+        .next()
+}
+
+/// The constant value the global `index` defined by the module is initialized
+/// with.
+fn constant_global(module: &Module, index: u32, name: &str) -> Result<u32, Error> {
+    let imported_globals = imported(module, |e| match *e {
+        External::Global(_) => true,
+        _ => false,
+    });
+    let global = index
+        .checked_sub(imported_globals)
+        .and_then(|i| module.global_section()?.entries().get(i as usize));
+    match global.map(|g| g.init_expr().code()) {
+        Some([Instruction::I32Const(value), Instruction::End]) => Ok(*value as u32),
+        _ => bail!(
+            "`--threads` requires `{}` to be a constant defined by the module",
+            name
+        ),
+    }
+}
+
+fn imported(module: &Module, kind: fn(&External) -> bool) -> u32 {
+    module
+        .import_section()
+        .map(|s| s.entries().iter().filter(|e| kind(e.external())).count() as u32)
+        .unwrap_or(0)
+}
+
+fn add_global(module: &mut Module, global: GlobalEntry) -> u32 {
+    let imported_globals = imported(module, |e| match *e {
+        External::Global(_) => true,
+        _ => false,
+    });
+    if module.global_section().is_none() {
+        insert_section(module, Section::Global(GlobalSection::default()));
+    }
+    let globals = module.global_section_mut().unwrap().entries_mut();
+    globals.push(global);
+    imported_globals + globals.len() as u32 - 1
+}
+
+fn add_function(
+    module: &mut Module,
+    ty: FunctionType,
+    code: Vec<Instruction>,
+) -> Result<u32, Error> {
+    let imported_functions = imported(module, |e| match *e {
+        External::Function(_) => true,
+        _ => false,
+    });
+    let ty = {
+        let types = match module.type_section_mut() {
+            Some(s) => s.types_mut(),
+            None => bail!("wasm module has no type section"),
+        };
+        let existing = types.iter().position(|t| match *t {
+            Type::Function(ref f) => *f == ty,
+        });
+        match existing {
+            Some(i) => i as u32,
+            None => {
+                types.push(Type::Function(ty));
+                types.len() as u32 - 1
+            }
+        }
+    };
+    let index = match module.function_section_mut() {
+        Some(functions) => {
+            functions.entries_mut().push(Func::new(ty));
+            imported_functions + functions.entries().len() as u32 - 1
+        }
+        None => bail!("wasm module has no function section"),
+    };
+    match module.code_section_mut() {
+        Some(bodies) => bodies
+            .bodies_mut()
+            .push(FuncBody::new(Vec::new(), Instructions::new(code))),
+        None => bail!("wasm module has no code section"),
+    }
+    Ok(index)
+}
+
+fn add_export(module: &mut Module, name: &str, internal: Internal) -> Result<(), Error> {
+    match module.export_section_mut() {
+        Some(exports) => exports
+            .entries_mut()
+            .push(ExportEntry::new(name.to_string(), internal)),
+        None => bail!("wasm module has no export section"),
+    }
+    Ok(())
+}
+
+/// Inserts `section`, which the module doesn't have yet, in the right place.
+fn insert_section(module: &mut Module, section: Section) {
+    fn id(section: &Section) -> Option<u8> {
+        match *section {
+            Section::Type(_) => Some(1),
+            Section::Import(_) => Some(2),
+            Section::Function(_) => Some(3),
+            Section::Table(_) => Some(4),
+            Section::Memory(_) => Some(5),
+            Section::Global(_) => Some(6),
+            Section::Export(_) => Some(7),
+            Section::Start(_) => Some(8),
+            Section::Element(_) => Some(9),
+            Section::Code(_) => Some(10),
+            Section::Data(_) => Some(11),
+            _ => None,
+        }
+    }
+    let new = id(&section);
+    let sections = module.sections_mut();
+    let at = sections
+        .iter()
+        .position(|s| match (id(s), new) {
+            (Some(a), Some(b)) => a > b,
+            _ => false,
+        })
+        .unwrap_or(sections.len());
+    sections.insert(at, section);
+}
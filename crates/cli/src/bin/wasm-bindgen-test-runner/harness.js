@@ -0,0 +1,56 @@
+// Support for running `#[wasm_bindgen_test]` functions in Node.js, written out
+// next to the generated bindings by `wasm-bindgen-test-runner`.
+
+let currentPanic = null;
+
+// Called from the panic hook installed by `wasm-bindgen-test`
+exports.testPanicked = function(message) {
+    currentPanic = message;
+};
+
+// Test exports are named `__wbgt_{name}_{n}`
+function testName(exportName) {
+    return exportName.replace(/^__wbgt_/, '').replace(/_\d+$/, '');
+}
+
+exports.run = function(wasm, tests, filters) {
+    tests = tests.filter(t => {
+        const name = testName(t);
+        return filters.length === 0 || filters.some(f => name.includes(f));
+    });
+
+    console.log(`running ${tests.length} test${tests.length === 1 ? '' : 's'}`);
+
+    const failures = [];
+    for (const test of tests) {
+        const name = testName(test);
+        currentPanic = null;
+        try {
+            wasm[test]();
+            console.log(`test ${name} ... ok`);
+        } catch (e) {
+            console.log(`test ${name} ... FAILED`);
+            failures.push({ name, message: currentPanic || (e && e.stack) || String(e) });
+        }
+    }
+
+    if (failures.length > 0) {
+        console.log('\nfailures:\n');
+        for (const failure of failures) {
+            console.log(`---- ${failure.name} output ----`);
+            console.log(`    ${failure.message}\n`);
+        }
+        console.log('failures:');
+        for (const failure of failures) {
+            console.log(`    ${failure.name}`);
+        }
+    }
+
+    const passed = tests.length - failures.length;
+    const result = failures.length > 0 ? 'FAILED' : 'ok';
+    console.log(`\ntest result: ${result}. ${passed} passed; ${failures.length} failed\n`);
+
+    if (failures.length > 0) {
+        process.exit(1);
+    }
+};
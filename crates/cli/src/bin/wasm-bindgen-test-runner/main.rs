@@ -0,0 +1,149 @@
+//! A "wrapper binary" used to execute wasm files as tests
+//!
+//! This binary is intended to be used as a "test runner" for wasm binaries,
+//! being compatible with `cargo test` for the wasm target. It will
+//! automatically execute `wasm-bindgen` (or the equivalent thereof) and then
+//! execute the tests it finds in Node.js.
+
+#[macro_use]
+extern crate failure;
+extern crate parity_wasm;
+extern crate wasm_bindgen_cli_support;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{self, Command};
+
+use failure::{Error, ResultExt};
+use parity_wasm::elements::{Module, Section};
+use wasm_bindgen_cli_support::Bindgen;
+
+fn main() {
+    let err = match rmain() {
+        Ok(()) => return,
+        Err(e) => e,
+    };
+    eprintln!("error: {}", err);
+    for cause in err.causes().skip(1) {
+        eprintln!("\tcaused by: {}", cause);
+    }
+    process::exit(1);
+}
+
+fn rmain() -> Result<(), Error> {
+    let mut args = env::args_os().skip(1);
+
+    // Currently no flags are supported, and assume there's only one argument
+    // which is the wasm file to test. This'll want to improve over time!
+    let wasm_file_to_test = match args.next() {
+        Some(file) => PathBuf::from(file),
+        None => bail!("must have a file to test as first argument"),
+    };
+
+    // Everything else that isn't a flag is a filter on test names, like the
+    // default test harness.
+    let filters = args
+        .filter_map(|arg| arg.into_string().ok())
+        .filter(|arg| !arg.starts_with("-"))
+        .collect::<Vec<_>>();
+
+    let mut contents = Vec::new();
+    File::open(&wasm_file_to_test)
+        .and_then(|mut f| f.read_to_end(&mut contents))
+        .with_context(|_| format!("failed to read `{}`", wasm_file_to_test.display()))?;
+    let module = parity_wasm::deserialize_buffer::<Module>(&contents)
+        .with_context(|_| "failed to parse input file as wasm")?;
+    let tests = find_tests(&module)?;
+
+    // Without any tests the `wasm-bindgen-test` runtime support, including the
+    // harness import, may not even be linked in, so bail out early.
+    if tests.is_empty() {
+        println!("no tests to run!");
+        return Ok(());
+    }
+
+    let stem = match wasm_file_to_test.file_stem() {
+        Some(stem) => stem.to_str().unwrap().to_string(),
+        None => bail!("invalid file to test: {}", wasm_file_to_test.display()),
+    };
+    let tmpdir = wasm_file_to_test.with_extension("wbgtest");
+    drop(fs::remove_dir_all(&tmpdir));
+    fs::create_dir_all(&tmpdir)
+        .with_context(|_| format!("failed to create `{}`", tmpdir.display()))?;
+
+    Bindgen::new()
+        .input_bytes(&stem, contents)
+        .nodejs(true)
+        .debug(true)
+        .generate_output()
+        .and_then(|output| output.emit(&tmpdir))
+        .context("executing `wasm-bindgen` over the wasm file")?;
+
+    let harness = tmpdir.join("__wasm_bindgen_test_harness.js");
+    File::create(&harness)
+        .and_then(|mut f| f.write_all(include_str!("harness.js").as_bytes()))
+        .with_context(|_| format!("failed to write `{}`", harness.display()))?;
+
+    let quote = |s: &str| format!("'{}'", s.replace("\\", "\\\\").replace("'", "\\'"));
+    let tests = tests.iter().map(|t| quote(t)).collect::<Vec<_>>();
+    let filters = filters.iter().map(|t| quote(t)).collect::<Vec<_>>();
+    let run = format!(
+        "
+            const harness = require('./__wasm_bindgen_test_harness');
+            require('./{stem}');
+            const wasm = require('./{stem}_bg');
+            harness.run(wasm, [{tests}], [{filters}]);
+        ",
+        stem = stem,
+        tests = tests.join(", "),
+        filters = filters.join(", "),
+    );
+    let run_path = tmpdir.join("run.js");
+    File::create(&run_path)
+        .and_then(|mut f| f.write_all(run.as_bytes()))
+        .with_context(|_| format!("failed to write `{}`", run_path.display()))?;
+
+    let status = Command::new("node")
+        .arg(&run_path)
+        .status()
+        .context("failed to execute `node`, is it installed?")?;
+    if !status.success() {
+        process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+/// Finds the names of the test exports recorded by `#[wasm_bindgen_test]`.
+fn find_tests(module: &Module) -> Result<Vec<String>, Error> {
+    let mut tests = Vec::new();
+    for section in module.sections() {
+        let custom = match *section {
+            Section::Custom(ref s) => s,
+            _ => continue,
+        };
+        if custom.name() != "__wasm_bindgen_test_unstable" {
+            continue;
+        }
+
+        let mut payload = custom.payload();
+        while payload.len() > 0 {
+            if payload.len() < 4 {
+                bail!("malformed `__wasm_bindgen_test_unstable` section");
+            }
+            let len = ((payload[0] as usize) << 0)
+                | ((payload[1] as usize) << 8)
+                | ((payload[2] as usize) << 16)
+                | ((payload[3] as usize) << 24);
+            if payload.len() < 4 + len {
+                bail!("malformed `__wasm_bindgen_test_unstable` section");
+            }
+            let (a, b) = payload[4..].split_at(len);
+            payload = b;
+            tests.push(String::from_utf8(a.to_vec())?);
+        }
+    }
+    tests.sort();
+    Ok(tests)
+}
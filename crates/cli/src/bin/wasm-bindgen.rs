@@ -24,13 +24,34 @@ Usage:
 Options:
     -h --help                Show this screen.
     --out-dir DIR            Output directory
+    --target TARGET          What type of output to generate, valid values are
+                             [bundler, browser, nodejs, nodejs-module,
+                             no-modules, web, deno]
+    --commonjs               With `--target nodejs-module` also emit CommonJS
+                             `*.cjs` entry points
+    --top-level-await        With `--target nodejs-module` instantiate the wasm
+                             with a top-level `await`
     --nodejs                 Generate output that only works in node.js
     --browser                Generate output that only works in a browser
     --no-modules             Generate output that only works in a browser (without modules)
     --no-modules-global VAR  Name of the global variable to initialize
     --typescript             Output a TypeScript definition file (on by default)
     --no-typescript          Don't emit a *.d.ts file
+    --package-json           Also emit a package.json for publishing to npm
+    --package-name NAME      The name in the generated package.json
+    --package-version VER    The version in the generated package.json
+    --source-map             Emit a source map from the JS to Rust source
     --debug                  Include otherwise-extraneous debug checks in output
+    --keep-debug             Keep DWARF debug sections in the output wasm
+    --remove-name-section    Remove the function name section from the output wasm
+    --weak-refs              Free exported class instances when they're garbage
+                             collected, where `FinalizationRegistry` exists
+    --anyref                 Pass JS values to wasm as `anyref`s, requiring
+                             reference types support
+    --threads                Share memory between instances on web workers,
+                             with `--target web` or `--target no-modules`
+    --rethrow-panics         Throw panics forwarded by `wasm_bindgen::panic`
+                             as an `Error` rather than logging them
     --no-demangle            Don't demangle Rust symbol names
     -V --version             Print the version number of wasm-bindgen
 ";
@@ -47,6 +68,19 @@ struct Args {
     flag_version: bool,
     flag_no_demangle: bool,
     flag_no_modules_global: Option<String>,
+    flag_target: Option<String>,
+    flag_commonjs: bool,
+    flag_package_json: bool,
+    flag_source_map: bool,
+    flag_keep_debug: bool,
+    flag_remove_name_section: bool,
+    flag_weak_refs: bool,
+    flag_anyref: bool,
+    flag_threads: bool,
+    flag_rethrow_panics: bool,
+    flag_package_name: Option<String>,
+    flag_package_version: Option<String>,
+    flag_top_level_await: bool,
     arg_input: Option<PathBuf>,
 }
 
@@ -83,12 +117,52 @@ fn rmain(args: &Args) -> Result<(), Error> {
         .nodejs(args.flag_nodejs)
         .browser(args.flag_browser)
         .no_modules(args.flag_no_modules)
+        .nodejs_commonjs(args.flag_commonjs)
+        .top_level_await(args.flag_top_level_await)
+        .package_json(args.flag_package_json)
+        .source_map(args.flag_source_map)
+        .keep_debug(args.flag_keep_debug)
+        .remove_name_section(args.flag_remove_name_section)
+        .weak_refs(args.flag_weak_refs)
+        .anyref(args.flag_anyref)
+        .threads(args.flag_threads)
+        .rethrow_panics(args.flag_rethrow_panics)
         .debug(args.flag_debug)
         .demangle(!args.flag_no_demangle)
         .typescript(typescript);
     if let Some(ref name) = args.flag_no_modules_global {
         b.no_modules_global(name);
     }
+    if let Some(ref name) = args.flag_package_name {
+        b.package_name(name);
+    }
+    if let Some(ref version) = args.flag_package_version {
+        b.package_version(version);
+    }
+    if let Some(ref target) = args.flag_target {
+        match target.as_str() {
+            "bundler" => {}
+            "browser" => {
+                b.browser(true);
+            }
+            "nodejs" => {
+                b.nodejs(true);
+            }
+            "nodejs-module" => {
+                b.nodejs_module(true);
+            }
+            "no-modules" => {
+                b.no_modules(true);
+            }
+            "web" => {
+                b.web(true);
+            }
+            "deno" => {
+                b.deno(true);
+            }
+            s => bail!("invalid target: `{}`", s),
+        }
+    }
 
     let out_dir = match args.flag_out_dir {
         Some(ref p) => p,
@@ -0,0 +1,86 @@
+#[macro_use]
+extern crate serde_derive;
+extern crate docopt;
+extern crate failure;
+extern crate serde_json;
+extern crate wasm_bindgen_webidl;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use docopt::Docopt;
+use failure::{Error, ResultExt};
+use wasm_bindgen_webidl::Report;
+
+const USAGE: &'static str = "
+Reports which parts of some WebIDL files wasm-bindgen can generate bindings for
+
+Usage:
+    webidl-report [options] <input>
+    webidl-report -h | --help
+
+Options:
+    -h --help               Show this screen.
+    --json                  Print the report as JSON instead of text
+
+The input is either a single `.webidl` file or a directory, in which case every
+`.webidl` file in it is included in the report.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    flag_json: bool,
+    arg_input: PathBuf,
+}
+
+fn main() {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+    let err = match rmain(&args) {
+        Ok(()) => return,
+        Err(e) => e,
+    };
+    eprintln!("error: {}", err);
+    for cause in err.causes().skip(1) {
+        eprintln!("\tcaused by: {}", cause);
+    }
+    process::exit(1);
+}
+
+fn rmain(args: &Args) -> Result<(), Error> {
+    let mut files = Vec::new();
+    if args.arg_input.is_dir() {
+        for entry in fs::read_dir(&args.arg_input)
+            .with_context(|_| format!("failed to read `{}`", args.arg_input.display()))?
+        {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("webidl") {
+                files.push(path);
+            }
+        }
+        files.sort();
+    } else {
+        files.push(args.arg_input.clone());
+    }
+
+    let mut report = Report::default();
+    for file in files.iter() {
+        report.extend(report_file(file)?);
+    }
+
+    if args.flag_json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{}", report);
+    }
+
+    Ok(())
+}
+
+fn report_file(path: &Path) -> Result<Report, Error> {
+    let (_, report) = wasm_bindgen_webidl::compile_file_with_report(path)
+        .with_context(|_| format!("failed to compile `{}`", path.display()))?;
+    Ok(report)
+}
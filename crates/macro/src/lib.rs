@@ -32,3 +32,20 @@ pub fn wasm_bindgen(attr: TokenStream, input: TokenStream) -> TokenStream {
 
     ret.into()
 }
+
+#[proc_macro_derive(JsObject)]
+pub fn js_object(input: TokenStream) -> TokenStream {
+    let item = syn::parse::<syn::ItemStruct>(input)
+        .expect("#[derive(JsObject)] can only be used on structs");
+
+    let mut ret = proc_macro2::TokenStream::new();
+    let mut program = backend::ast::Program::default();
+    program.objects.push(parser::object(&item));
+    program.to_tokens(&mut ret);
+
+    if cfg!(feature = "xxx_debug_only_print_generated_code") {
+        println!("{}", ret);
+    }
+
+    ret.into()
+}
@@ -325,6 +325,40 @@ impl<'a> ConvertToAst<()> for &'a mut syn::ItemStruct {
     }
 }
 
+/// Converts a struct with `#[derive(JsObject)]` to its AST.
+pub fn object(s: &syn::ItemStruct) -> ast::Object {
+    if s.generics.params.len() > 0 {
+        panic!(
+            "structs with #[derive(JsObject)] cannot have lifetime or \
+             type parameters currently"
+        );
+    }
+    let names = match &s.fields {
+        syn::Fields::Named(names) => names,
+        _ => panic!("#[derive(JsObject)] can only be used on structs with named fields"),
+    };
+    let object_name = s.ident.to_string();
+    let fields = names
+        .named
+        .iter()
+        .map(|field| {
+            let name = field.ident.clone().unwrap();
+            let describe = shared::object_field_describe(&object_name, &name.to_string());
+            ast::ObjectField {
+                name,
+                ty: field.ty.clone(),
+                describe: Ident::new(&describe, Span::call_site()),
+                comments: extract_doc_comments(&field.attrs),
+            }
+        })
+        .collect();
+    ast::Object {
+        name: s.ident.clone(),
+        fields,
+        comments: extract_doc_comments(&s.attrs),
+    }
+}
+
 impl ConvertToAst<BindgenAttrs> for syn::ForeignItemFn {
     type Target = ast::ImportKind;
 
@@ -547,11 +581,30 @@ fn function_from_decl(
             ret,
             rust_vis: vis,
             rust_attrs: attrs,
+            location: location(name.span()),
         },
         method_self,
     )
 }
 
+/// The location in Rust source of `span`, which is only available with the
+/// nightly span APIs.
+#[cfg(feature = "spans")]
+fn location(span: Span) -> Option<ast::Location> {
+    let span = span.unstable();
+    let start = span.start();
+    Some(ast::Location {
+        file: span.source_file().path().display().to_string(),
+        line: start.line as u32,
+        column: start.column as u32,
+    })
+}
+
+#[cfg(not(feature = "spans"))]
+fn location(_span: Span) -> Option<ast::Location> {
+    None
+}
+
 pub(crate) trait MacroParse<Ctx> {
     fn macro_parse(self, program: &mut ast::Program, context: Ctx);
 }
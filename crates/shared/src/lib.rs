@@ -1,7 +1,7 @@
 #[macro_use]
 extern crate serde_derive;
 
-pub const SCHEMA_VERSION: &str = "6";
+pub const SCHEMA_VERSION: &str = "8";
 
 #[derive(Deserialize)]
 pub struct ProgramOnlySchema {
@@ -15,6 +15,7 @@ pub struct Program {
     pub enums: Vec<Enum>,
     pub imports: Vec<Import>,
     pub structs: Vec<Struct>,
+    pub objects: Vec<Object>,
     pub version: String,
     pub schema_version: String,
 }
@@ -104,6 +105,17 @@ pub struct EnumVariant {
 #[derive(Deserialize, Serialize)]
 pub struct Function {
     pub name: String,
+    pub location: Option<Location>,
+}
+
+/// Where a `#[wasm_bindgen]` item was defined in Rust source.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Location {
+    pub file: String,
+    /// 1-based
+    pub line: u32,
+    /// 0-based
+    pub column: u32,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -120,6 +132,21 @@ pub struct StructField {
     pub comments: Vec<String>,
 }
 
+/// A struct with `#[derive(JsObject)]`, passed to and from JS as a plain
+/// object.
+#[derive(Deserialize, Serialize)]
+pub struct Object {
+    pub name: String,
+    pub fields: Vec<ObjectField>,
+    pub comments: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ObjectField {
+    pub name: String,
+    pub comments: Vec<String>,
+}
+
 pub fn new_function(struct_name: &str) -> String {
     let mut name = format!("__wbg_");
     name.extend(struct_name.chars().flat_map(|s| s.to_lowercase()));
@@ -164,6 +191,14 @@ pub fn struct_field_set(struct_: &str, f: &str) -> String {
     return name;
 }
 
+pub fn object_field_describe(object: &str, f: &str) -> String {
+    let mut name = String::from("__wbg_object_");
+    name.extend(object.chars().flat_map(|s| s.to_lowercase()));
+    name.push_str("_");
+    name.push_str(f);
+    return name;
+}
+
 pub fn version() -> String {
     let mut v = env!("CARGO_PKG_VERSION").to_string();
     if let Some(s) = option_env!("WBG_VERSION") {
@@ -0,0 +1,60 @@
+//! See the README for `wasm-bindgen-test` for a bit more info about what's
+//! going on here.
+
+#![feature(proc_macro)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use std::sync::atomic::*;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
+
+static CNT: AtomicUsize = ATOMIC_USIZE_INIT;
+
+#[proc_macro_attribute]
+pub fn wasm_bindgen_test(attr: TokenStream, body: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        panic!("#[wasm_bindgen_test] does not take any arguments");
+    }
+    let item = syn::parse::<syn::ItemFn>(body).expect("#[wasm_bindgen_test] must be on a function");
+    if !item.decl.inputs.is_empty() {
+        panic!("#[wasm_bindgen_test] functions must not take any arguments");
+    }
+
+    let ident = &item.ident;
+    let cnt = CNT.fetch_add(1, Ordering::SeqCst);
+    let export = format!("__wbgt_{}_{}", ident, cnt);
+    let export_ident = Ident::new(&export, Span::call_site());
+    let section_ident = Ident::new(&format!("__WBGT_SECTION_{}", cnt), Span::call_site());
+
+    // The runner finds tests through a custom section, using the same framing
+    // as `__wasm_bindgen_unstable`: a little-endian 32-bit length followed by
+    // that many bytes, here the name of the test's export.
+    let mut bytes = Vec::new();
+    bytes.push((export.len() >> 0) as u8);
+    bytes.push((export.len() >> 8) as u8);
+    bytes.push((export.len() >> 16) as u8);
+    bytes.push((export.len() >> 24) as u8);
+    bytes.extend_from_slice(export.as_bytes());
+    let len = bytes.len();
+    let bytes = syn::LitByteStr::new(&bytes, Span::call_site());
+
+    (quote! {
+        #item
+
+        #[no_mangle]
+        #[doc(hidden)]
+        pub extern fn #export_ident() {
+            ::wasm_bindgen_test::__rt::run_test(#ident);
+        }
+
+        #[allow(non_upper_case_globals)]
+        #[wasm_custom_section = "__wasm_bindgen_test_unstable"]
+        const #section_ident: [u8; #len] = *#bytes;
+    }).into()
+}
@@ -21,13 +21,27 @@ pub struct Project {
     debug: bool,
     node: bool,
     nodejs_experimental_modules: bool,
+    nodejs_module: bool,
+    top_level_await: bool,
     no_std: bool,
     serde: bool,
     rlib: bool,
     webpack: bool,
+    web: bool,
+    deno: bool,
+    package_json: bool,
+    source_map: bool,
+    remove_name_section: bool,
+    keep_debug: bool,
+    weak_refs: bool,
+    anyref: bool,
+    threads: bool,
+    rethrow_panics: bool,
     node_args: Vec<String>,
+    rustflags: Vec<String>,
     deps: Vec<String>,
     headless: bool,
+    timeout: u32,
 }
 
 pub fn project() -> Project {
@@ -42,12 +56,26 @@ pub fn project() -> Project {
         no_std: false,
         node: true,
         nodejs_experimental_modules: true,
+        nodejs_module: false,
+        top_level_await: false,
         webpack: false,
+        web: false,
+        deno: false,
+        package_json: false,
+        source_map: false,
+        remove_name_section: false,
+        keep_debug: false,
+        weak_refs: false,
+        anyref: false,
+        threads: false,
+        rethrow_panics: false,
         serde: false,
         rlib: false,
         headless: false,
+        timeout: 10_000,
         deps: Vec::new(),
         node_args: Vec::new(),
+        rustflags: Vec::new(),
         files: vec![
             ("Cargo.lock".to_string(), lockfile),
         ],
@@ -143,12 +171,99 @@ impl Project {
         self
     }
 
+    /// Enables or disables native Node ES module output, along with CommonJS
+    /// entry points
+    pub fn nodejs_module(&mut self, node: bool) -> &mut Project {
+        self.nodejs_module = node;
+        self
+    }
+
+    /// Enables or disables instantiating the wasm with a top-level `await` in
+    /// native Node ES module output
+    pub fn top_level_await(&mut self, top_level_await: bool) -> &mut Project {
+        self.top_level_await = top_level_await;
+        self
+    }
+
     /// Enables or disables the usage of webpack for this project
     pub fn webpack(&mut self, webpack: bool) -> &mut Project {
         self.webpack = webpack;
         self
     }
 
+    /// Enables or disables generating output for `--target web`
+    pub fn web(&mut self, web: bool) -> &mut Project {
+        self.web = web;
+        self
+    }
+
+    /// Enables or disables generating output for `--target deno`
+    pub fn deno(&mut self, deno: bool) -> &mut Project {
+        self.deno = deno;
+        self
+    }
+
+    /// Enables or disables generating a `package.json` alongside the bindings
+    pub fn package_json(&mut self, package_json: bool) -> &mut Project {
+        self.package_json = package_json;
+        self
+    }
+
+    /// Enables or disables generating a source map for the bindings
+    pub fn source_map(&mut self, source_map: bool) -> &mut Project {
+        self.source_map = source_map;
+        self
+    }
+
+    /// Enables or disables removing the `name` section from the output wasm
+    pub fn remove_name_section(&mut self, remove: bool) -> &mut Project {
+        self.remove_name_section = remove;
+        self
+    }
+
+    /// Enables or disables keeping DWARF `.debug_*` sections in the output wasm
+    pub fn keep_debug(&mut self, keep_debug: bool) -> &mut Project {
+        self.keep_debug = keep_debug;
+        self
+    }
+
+    /// Enables or disables freeing exported class instances when they're
+    /// garbage collected
+    pub fn weak_refs(&mut self, weak_refs: bool) -> &mut Project {
+        self.weak_refs = weak_refs;
+        self
+    }
+
+    /// Enables or disables passing JS values to wasm as `anyref`s
+    pub fn anyref(&mut self, anyref: bool) -> &mut Project {
+        self.anyref = anyref;
+        self
+    }
+
+    /// Enables or disables sharing memory between instances on web workers
+    pub fn threads(&mut self, threads: bool) -> &mut Project {
+        self.threads = threads;
+        self
+    }
+
+    /// Enables or disables throwing forwarded panics as an `Error`
+    pub fn rethrow_panics(&mut self, rethrow_panics: bool) -> &mut Project {
+        self.rethrow_panics = rethrow_panics;
+        self
+    }
+
+    /// Pass an extra argument to `node` when running this test
+    pub fn node_arg(&mut self, arg: &str) -> &mut Project {
+        self.node_args.push(arg.to_string());
+        self
+    }
+
+    /// Pass an extra flag to `rustc` when compiling this test
+    pub fn rustflag(&mut self, flag: &str) -> &mut Project {
+        self.rustflags.push(flag.to_string());
+        self
+    }
+
     /// Add a path dependency to the generated project
     pub fn add_local_dependency(&mut self, name: &str, path: &str) -> &mut Project {
         self.deps
@@ -176,13 +291,24 @@ impl Project {
         self
     }
 
+    /// How long, in milliseconds, to wait for a test that returns a `Promise`
+    /// to settle before failing it. Defaults to 10 seconds.
+    ///
+    /// Only the JS `test` function can be asynchronous, by returning a promise
+    /// or any other thenable. There's no support for Rust futures, so Rust
+    /// code being tested has to hand JS a `Promise` to wait on.
+    pub fn timeout(&mut self, timeout: u32) -> &mut Project {
+        self.timeout = timeout;
+        self
+    }
+
     /// Write this project to the filesystem, ensuring all files are ready to
     /// go.
     pub fn build(&mut self) -> (PathBuf, PathBuf) {
         if self.headless {
             self.webpack = true;
         }
-        if self.webpack {
+        if self.webpack || self.web || self.deno || self.nodejs_module {
             self.node = false;
             self.nodejs_experimental_modules = false;
         }
@@ -476,29 +602,59 @@ impl Project {
             runjs.push_str("const process = require('process');\n");
         }
 
-        runjs.push_str("
-            function run(test, wasm) {
-                test.test();
+        // Tests may return a promise (or any other thenable), in which case
+        // it's driven to completion by the JS event loop before checking for
+        // leaks, failing the test if it rejects or doesn't settle in time.
+        runjs.push_str(&format!("
+            function withTimeout(promise, ms) {{
+                let timer;
+                const timeout = new Promise((resolve, reject) => {{
+                    timer = setTimeout(
+                        () => reject(new Error(`test timed out after ${{ms}}ms`)),
+                        ms
+                    );
+                }});
+                return Promise.race([promise, timeout])
+                    .then(
+                        value => {{ clearTimeout(timer); return value; }},
+                        error => {{ clearTimeout(timer); throw error; }}
+                    );
+            }}
+
+            function run(test, wasm) {{
+                function finish() {{
+                    if (wasm.assertStackEmpty)
+                        wasm.assertStackEmpty();
+                    if (wasm.assertSlabEmpty)
+                        wasm.assertSlabEmpty();
+                }}
 
-                if (wasm.assertStackEmpty)
-                    wasm.assertStackEmpty();
-                if (wasm.assertSlabEmpty)
-                    wasm.assertSlabEmpty();
-            }
-        ");
+                const result = test.test();
+                if (result && typeof result.then === 'function')
+                    return withTimeout(Promise.resolve(result), {}).then(finish);
+
+                finish();
+                return Promise.resolve();
+            }}
+
+            function describeError(error) {{
+                if (error instanceof Error)
+                    return `exception: ${{error.message}}\\nstack: ${{error.stack}}`;
+                return `rejected with: ${{error}}`;
+            }}
+        ", self.timeout));
 
         if self.headless {
             runjs.push_str("
                 function onerror(error) {
                     const errors = document.getElementById('error');
-                    let content = `exception: ${e.message}\\nstack: ${e.stack}`;
-                    errors.innerHTML = `<pre>${content}</pre>`;
+                    errors.innerHTML = `<pre>${describeError(error)}</pre>`;
                 }
             ");
         } else {
             runjs.push_str("
                 function onerror(error) {
-                    console.error(error);
+                    console.error(describeError(error));
                     process.exit(1);
                 }
             ");
@@ -544,11 +700,9 @@ impl Project {
             assert!(modules.is_empty());
             runjs.push_str("
                 const test = require('./test');
-                try {
-                    run(test, {});
-                } catch (e) {
-                    onerror(e);
-                }
+                Promise.resolve()
+                    .then(() => run(test, {}))
+                    .catch(onerror);
             ");
         }
         self.files.push(("run.js".to_string(), runjs));
@@ -565,7 +719,7 @@ impl Project {
             .current_dir(&root)
             .env("CARGO_TARGET_DIR", &target_dir)
             // Catch any warnings in generated code because we don't want any
-            .env("RUSTFLAGS", "-Dwarnings");
+            .env("RUSTFLAGS", format!("-Dwarnings {}", self.rustflags.join(" ")));
         run(&mut cmd, "cargo");
         (root, target_dir)
     }
@@ -578,23 +732,43 @@ impl Project {
         let idx = IDX.with(|x| *x);
         let out = target_dir.join(&format!("wasm32-unknown-unknown/debug/test{}.wasm", idx));
 
-        let as_a_module = root.join("out.wasm");
-        fs::hard_link(&out, &as_a_module).unwrap();
+        let mut wasm = Vec::new();
+        File::open(&out)
+            .unwrap()
+            .read_to_end(&mut wasm)
+            .unwrap();
 
         let _x = wrap_step("running wasm-bindgen");
         let res = cli::Bindgen::new()
-            .input_path(&as_a_module)
+            .input_bytes("out", wasm)
             .typescript(self.webpack)
             .debug(self.debug)
             .nodejs(self.node)
             .nodejs_experimental_modules(self.nodejs_experimental_modules)
-            .generate(&root);
+            .nodejs_module(self.nodejs_module)
+            .nodejs_commonjs(self.nodejs_module)
+            .top_level_await(self.top_level_await)
+            .web(self.web)
+            .deno(self.deno)
+            .package_json(self.package_json)
+            .source_map(self.source_map)
+            .remove_name_section(self.remove_name_section)
+            .keep_debug(self.keep_debug)
+            .weak_refs(self.weak_refs)
+            .anyref(self.anyref)
+            .threads(self.threads)
+            .rethrow_panics(self.rethrow_panics)
+            .generate_output()
+            .and_then(|output| output.emit(&root));
 
         if let Err(e) = res {
-            for e in e.causes() {
-                println!("- {}", e);
+            // The causes are part of the message so that tests can check for
+            // them with `#[should_panic(expected = "...")]`.
+            let causes = e.causes().map(|e| e.to_string()).collect::<Vec<_>>();
+            for cause in causes.iter() {
+                println!("- {}", cause);
             }
-            panic!("failed");
+            panic!("failed: {}", causes.join(": "));
         }
 
         (root, target_dir)
@@ -761,10 +935,15 @@ pub fn run(cmd: &mut Command, program: &str) {
     if output.stdout.len() > 0 {
         println!("stdout ---\n{}", String::from_utf8_lossy(&output.stdout));
     }
-    if output.stderr.len() > 0 {
-        println!("stderr ---\n{}", String::from_utf8_lossy(&output.stderr));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.len() > 0 {
+        println!("stderr ---\n{}", stderr);
+    }
+    // stderr is part of the message so that tests expected to fail can check
+    // why with `#[should_panic(expected = "...")]`.
+    if !output.status.success() {
+        panic!("`{}` failed with {}:\n{}", program, output.status, stderr);
     }
-    assert!(output.status.success());
 }
 
 struct BackgroundChild {
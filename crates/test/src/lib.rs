@@ -0,0 +1,15 @@
+//! Runtime support for the `#[wasm_bindgen_test]` attribute
+//!
+//! More documentation can be found in the README for this crate!
+
+#![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+#![deny(missing_docs)]
+
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test_macro;
+
+pub use wasm_bindgen_test_macro::wasm_bindgen_test;
+
+#[path = "rt.rs"]
+#[doc(hidden)]
+pub mod __rt;
@@ -0,0 +1,24 @@
+//! Internal implementation details of the `#[wasm_bindgen_test]` attribute,
+//! called from the code it generates.
+
+use std::panic;
+use std::sync::{Once, ONCE_INIT};
+
+use wasm_bindgen::prelude::*;
+
+// This module is written out by `wasm-bindgen-test-runner` next to the
+// generated bindings.
+#[wasm_bindgen(module = "./__wasm_bindgen_test_harness")]
+extern {
+    #[wasm_bindgen(js_name = testPanicked)]
+    fn test_panicked(message: &str);
+}
+
+/// Runs a single test, forwarding the message of any panic to the runner.
+pub fn run_test(test: fn()) {
+    static SET_HOOK: Once = ONCE_INIT;
+    SET_HOOK.call_once(|| {
+        panic::set_hook(Box::new(|info| test_panicked(&info.to_string())));
+    });
+    test();
+}
@@ -0,0 +1,18 @@
+#![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn pass() {
+    assert_eq!(1 + 1, 2);
+}
+
+#[wasm_bindgen_test]
+fn js_value() {
+    assert_eq!(JsValue::from(1.0).as_f64(), Some(1.0));
+    assert!(JsValue::null().is_null());
+}
@@ -1,6 +1,6 @@
 use std::process::Command;
 
-pub(crate) fn run() {
+pub fn run() {
     let output = Command::new("api-extractor")
         .arg("run")
         .output()
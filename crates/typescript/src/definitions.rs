@@ -1,45 +1,69 @@
-use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
 
 // Public API types for a TypeScript project based on
 // https://github.com/Microsoft/web-build-tools/blob/master/apps/api-extractor/src/api/api-json.schema.json
 //
 // There are some attributes that are omitted because they are not relevant to
 // us.
-#[derive(Serialize, Deserialize, Debug)]
-pub(crate) struct TsPackage {
-    kind: String,
-    name: String,
-    pub(crate) exports: HashMap<String, TsExport>,
+//
+// api-extractor emits named items (exports, members, parameters) as JSON
+// objects in declaration order. Those are deserialized into `Vec`s of pairs
+// rather than maps so that the order, and thus the order of parameters in the
+// generated bindings, is preserved.
+#[derive(Deserialize, Debug)]
+pub struct TsPackage {
+    pub name: String,
+    #[serde(deserialize_with = "ordered_map", default)]
+    pub exports: Vec<(String, TsExport)>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Deserialize, Debug)]
 #[serde(tag = "kind")]
-pub(crate) enum TsExport {
+pub enum TsExport {
     #[serde(rename = "class")]
     TsClass {
-        members: HashMap<String, TsClassMembers>,
+        #[serde(deserialize_with = "ordered_map", default)]
+        members: Vec<(String, TsClassMember)>,
+    },
+
+    #[serde(rename = "interface")]
+    TsInterface {
+        #[serde(deserialize_with = "ordered_map", default)]
+        members: Vec<(String, TsClassMember)>,
     },
 
     #[serde(rename = "function")]
     TsFunction {
-        parameters: HashMap<String, TsMethodProperty>,
+        #[serde(deserialize_with = "ordered_map", default)]
+        parameters: Vec<(String, TsMethodProperty)>,
         #[serde(rename = "returnValue")]
         return_value: TsReturnValue,
     },
-    //TODO: implement ...
-    //{ "$ref": "#/definitions/interfaceApiItem" },
-    //{ "$ref": "#/definitions/namespaceApiItem" },
-    //{ "$ref": "#/definitions/enumApiItem" },
+
+    #[serde(rename = "enum")]
+    TsEnum {
+        #[serde(deserialize_with = "ordered_map", default)]
+        values: Vec<(String, TsEnumValue)>,
+    },
+
+    #[serde(rename = "namespace")]
+    TsNamespace {
+        #[serde(deserialize_with = "ordered_map", default)]
+        exports: Vec<(String, TsExport)>,
+    },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Deserialize, Debug)]
 #[serde(tag = "kind")]
-pub(crate) enum TsClassMembers {
+pub enum TsClassMember {
     #[serde(rename = "property")]
     TsProperty {
-        #[serde(rename = "isStatic")]
+        #[serde(rename = "isStatic", default)]
         is_static: bool,
-        #[serde(rename = "isReadOnly")]
+        #[serde(rename = "isReadOnly", default)]
         is_read_only: bool,
         #[serde(rename = "type")]
         property_type: String,
@@ -47,34 +71,67 @@ pub(crate) enum TsClassMembers {
 
     #[serde(rename = "constructor")]
     TsConstructor {
-        parameters: HashMap<String, TsMethodProperty>,
+        #[serde(deserialize_with = "ordered_map", default)]
+        parameters: Vec<(String, TsMethodProperty)>,
     },
 
     #[serde(rename = "method")]
     TsMethod {
-        #[serde(rename = "accessModifier")]
+        #[serde(rename = "accessModifier", default)]
         access_modifier: String,
-        #[serde(rename = "isStatic")]
+        #[serde(rename = "isStatic", default)]
         is_static: bool,
-        parameters: HashMap<String, TsMethodProperty>,
+        #[serde(deserialize_with = "ordered_map", default)]
+        parameters: Vec<(String, TsMethodProperty)>,
         #[serde(rename = "returnValue")]
         return_value: TsReturnValue,
     },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub(crate) struct TsMethodProperty {
-    name: String,
+#[derive(Deserialize, Debug)]
+pub struct TsMethodProperty {
+    pub name: String,
     #[serde(rename = "type")]
-    pub(crate) property_type: String,
-    #[serde(rename = "isSpread")]
-    is_spread: bool,
-    #[serde(rename = "isOptional")]
-    is_optional: bool,
+    pub property_type: String,
+    #[serde(rename = "isSpread", default)]
+    pub is_spread: bool,
+    #[serde(rename = "isOptional", default)]
+    pub is_optional: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub(crate) struct TsReturnValue {
+#[derive(Deserialize, Debug)]
+pub struct TsReturnValue {
     #[serde(rename = "type")]
-    pub(crate) property_type: String,
+    pub property_type: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TsEnumValue {
+    pub value: String,
+}
+
+fn ordered_map<'de, D, T>(deserializer: D) -> Result<Vec<(String, T)>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    struct OrderedMap<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for OrderedMap<T> {
+        type Value = Vec<(String, T)>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a map")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut res = Vec::new();
+            while let Some(entry) = map.next_entry()? {
+                res.push(entry);
+            }
+            Ok(res)
+        }
+    }
+
+    deserializer.deserialize_map(OrderedMap(PhantomData))
 }
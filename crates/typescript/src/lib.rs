@@ -1,11 +1,19 @@
+//! Generates `#[wasm_bindgen] extern` blocks importing the API of a TypeScript
+//! project, as described by the JSON output of `api-extractor`.
+
+extern crate failure;
+extern crate heck;
 extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
-extern crate syn;
 extern crate wasm_bindgen_backend as backend;
 
 pub mod api_extractor;
 pub mod definitions;
 pub mod parser;
+
+pub use parser::{ts_file_to_rust, ts_to_rust};
@@ -1,16 +1,19 @@
-extern crate proc_macro2;
-extern crate quote;
 extern crate wasm_bindgen_typescript;
 
-use wasm_bindgen_typescript::parser;
-
-use proc_macro2::TokenStream;
-use quote::ToTokens;
+use std::path::Path;
+use std::process;
 
 fn main() {
-    let program = parser::ts_to_program("dist/wasm.api.json");
+    wasm_bindgen_typescript::api_extractor::run();
 
-    let mut tokens = TokenStream::new();
-    program.to_tokens(&mut tokens);
-    println!("{:#?}", tokens);
+    match wasm_bindgen_typescript::ts_file_to_rust(Path::new("dist/wasm.api.json")) {
+        Ok(rust) => println!("{}", rust),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            for cause in err.causes().skip(1) {
+                eprintln!("\tcaused by: {}", cause);
+            }
+            process::exit(1);
+        }
+    }
 }
@@ -1,134 +1,372 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
 use std::io::Read;
+use std::path::Path;
 
-use backend;
-use backend::ast::{BindgenAttrs, Export, Function};
-use proc_macro2;
+use backend::util::rust_ident;
+use failure::{Error, ResultExt};
+use heck::{ShoutySnakeCase, SnakeCase};
+use proc_macro2::{Ident, Span, TokenStream};
 use serde_json;
-use syn;
 
-use api_extractor;
 use definitions::*;
 
-pub fn ts_to_program(file_name: &str) -> backend::ast::Program {
-    api_extractor::run();
-
-    let ts_package = parse_json(file_name);
-
-    let mut program = backend::ast::Program::default();
-
-    for (name, export) in ts_package.exports {
-        match export {
-            TsExport::TsClass { members } => {
-                for (member_name, member) in members {
-                    match member {
-                        TsClassMembers::TsProperty { .. } => {}
-                        TsClassMembers::TsConstructor { .. } => {}
-                        TsClassMembers::TsMethod {
-                            parameters,
-                            return_value,
-                            ..
-                        } => {
-                            let function = build_function(member_name, parameters, return_value);
-
-                            program.exports.push(Export {
-                                class: Some(syn::Ident::new(&name, proc_macro2::Span::call_site())),
-                                method: true,
-                                mutable: false,
-                                constructor: None,
-                                function,
-                            });
+/// Generate the Rust source of a `#[wasm_bindgen] extern` block importing
+/// everything described by the api-extractor JSON file at `path`.
+pub fn ts_file_to_rust(path: &Path) -> Result<String, Error> {
+    let mut data = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut data))
+        .with_context(|_| format!("failed to read `{}`", path.display()))?;
+    ts_to_rust(&data)
+}
+
+/// Generate the Rust source of a `#[wasm_bindgen] extern` block importing
+/// everything described by the given api-extractor JSON.
+pub fn ts_to_rust(json: &str) -> Result<String, Error> {
+    let package: TsPackage =
+        serde_json::from_str(json).context("failed to parse api-extractor JSON")?;
+    Ok(Generator::new(&package).generate(&package).to_string())
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TypePosition {
+    Argument,
+    Return,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum EnumKind {
+    Number,
+    String,
+}
+
+struct Generator {
+    /// Classes and interfaces, which are imported as types.
+    types: BTreeSet<String>,
+    /// Enums, which are passed as their underlying number or string.
+    enums: BTreeMap<String, EnumKind>,
+}
+
+impl Generator {
+    fn new(package: &TsPackage) -> Generator {
+        let mut types = BTreeSet::new();
+        let mut enums = BTreeMap::new();
+        for &(ref name, ref export) in package.exports.iter() {
+            match *export {
+                TsExport::TsClass { .. } | TsExport::TsInterface { .. } => {
+                    types.insert(name.clone());
+                }
+                TsExport::TsEnum { ref values } => {
+                    let numeric = values.iter().all(|&(_, ref v)| v.value.parse::<f64>().is_ok());
+                    let kind = if numeric {
+                        EnumKind::Number
+                    } else {
+                        EnumKind::String
+                    };
+                    enums.insert(name.clone(), kind);
+                }
+                TsExport::TsFunction { .. } | TsExport::TsNamespace { .. } => {}
+            }
+        }
+        Generator { types, enums }
+    }
+
+    fn generate(&self, package: &TsPackage) -> TokenStream {
+        let mut imports = Vec::new();
+        let mut modules = Vec::new();
+
+        for &(ref name, ref export) in package.exports.iter() {
+            match *export {
+                TsExport::TsClass { ref members } => {
+                    self.class(name, members, false, &mut imports);
+                }
+                TsExport::TsInterface { ref members } => {
+                    self.class(name, members, true, &mut imports);
+                }
+                TsExport::TsFunction {
+                    ref parameters,
+                    ref return_value,
+                } => {
+                    imports.extend(self.function(name, None, parameters, return_value));
+                }
+                TsExport::TsEnum { ref values } => {
+                    modules.push(self.enum_constants(name, values));
+                }
+                TsExport::TsNamespace { ref exports } => {
+                    // Only plain functions can be imported from a namespace
+                    for &(ref fn_name, ref export) in exports.iter() {
+                        if let TsExport::TsFunction {
+                            ref parameters,
+                            ref return_value,
+                        } = *export
+                        {
+                            imports.extend(self.function(
+                                fn_name,
+                                Some(name),
+                                parameters,
+                                return_value,
+                            ));
                         }
                     }
                 }
             }
+        }
 
-            TsExport::TsFunction {
-                parameters,
-                return_value,
-            } => {
-                let function = build_function(name, parameters, return_value);
-
-                program.exports.push(Export {
-                    class: None,
-                    method: false,
-                    mutable: false,
-                    constructor: None,
-                    function,
-                });
+        let module = &package.name;
+        quote! {
+            #[wasm_bindgen(module = #module)]
+            extern {
+                #(#imports)*
             }
+
+            #(#modules)*
         }
     }
 
-    program
-}
+    fn class(
+        &self,
+        name: &str,
+        members: &[(String, TsClassMember)],
+        structural: bool,
+        imports: &mut Vec<TokenStream>,
+    ) {
+        let class = ident(name);
+        imports.push(quote! { pub type #class; });
 
-fn parse_json(file_name: &str) -> TsPackage {
-    let mut file = File::open(file_name).unwrap();
-    let mut data = String::new();
-    file.read_to_string(&mut data).unwrap();
+        let structural = if structural {
+            quote!(structural,)
+        } else {
+            quote!()
+        };
 
-    serde_json::from_str(&data).unwrap()
-}
+        for &(ref member_name, ref member) in members.iter() {
+            match *member {
+                TsClassMember::TsConstructor { ref parameters } => {
+                    let args = match self.arguments(parameters) {
+                        Some(args) => args,
+                        None => continue,
+                    };
+                    imports.push(quote! {
+                        #[wasm_bindgen(constructor)]
+                        pub fn new(#(#args),*) -> #class;
+                    });
+                }
+                TsClassMember::TsMethod {
+                    ref access_modifier,
+                    is_static,
+                    ref parameters,
+                    ref return_value,
+                } => {
+                    if access_modifier == "private" || access_modifier == "protected" {
+                        continue;
+                    }
+                    let mut args = match self.arguments(parameters) {
+                        Some(args) => args,
+                        None => continue,
+                    };
+                    let ret = match self.ret(&return_value.property_type) {
+                        Some(ret) => ret,
+                        None => continue,
+                    };
+                    let rust_name = rust_ident(&member_name.to_snake_case());
+                    let js_name = ident(member_name);
+                    let kind = if is_static {
+                        quote!(static_method_of = #class,)
+                    } else {
+                        args.insert(0, quote!(this: &#class));
+                        quote!(method, #structural)
+                    };
+                    imports.push(quote! {
+                        #[wasm_bindgen(#kind js_name = #js_name)]
+                        pub fn #rust_name(#(#args),*) #ret;
+                    });
+                }
+                TsClassMember::TsProperty {
+                    is_static,
+                    is_read_only,
+                    ref property_type,
+                } => {
+                    let getter_ty = match self.ty(property_type, TypePosition::Return) {
+                        Some(ty) => ty,
+                        None => continue,
+                    };
+                    let setter_ty = match self.ty(property_type, TypePosition::Argument) {
+                        Some(ty) => ty,
+                        None => continue,
+                    };
+                    let (kind, this) = if is_static {
+                        (quote!(static_method_of = #class,), None)
+                    } else {
+                        (quote!(method, #structural), Some(quote!(this: &#class,)))
+                    };
+                    let js_name = ident(member_name);
+                    let getter = rust_ident(&member_name.to_snake_case());
+                    imports.push(quote! {
+                        #[wasm_bindgen(#kind getter = #js_name)]
+                        pub fn #getter(#this) -> #getter_ty;
+                    });
+                    if !is_read_only {
+                        let setter = ident(&format!("set_{}", member_name.to_snake_case()));
+                        imports.push(quote! {
+                            #[wasm_bindgen(#kind setter = #js_name)]
+                            pub fn #setter(#this value: #setter_ty);
+                        });
+                    }
+                }
+            }
+        }
+    }
 
-fn build_function(
-    name: String,
-    parameters: HashMap<String, TsMethodProperty>,
-    return_value: TsReturnValue,
-) -> Function {
-    let arguments = parameters
-        .iter()
-        .map(|(_name, property)| {
-            let mut segments = syn::punctuated::Punctuated::new();
-            segments.push(syn::PathSegment {
-                ident: syn::Ident::new(&property.property_type, proc_macro2::Span::call_site()),
-                arguments: syn::PathArguments::None,
-            });
+    fn function(
+        &self,
+        name: &str,
+        namespace: Option<&str>,
+        parameters: &[(String, TsMethodProperty)],
+        return_value: &TsReturnValue,
+    ) -> Option<TokenStream> {
+        let args = self.arguments(parameters)?;
+        let ret = self.ret(&return_value.property_type)?;
+        let js_name = ident(name);
+        let (rust_name, namespace) = match namespace {
+            Some(ns) => {
+                let rust_name = format!("{}_{}", ns.to_snake_case(), name.to_snake_case());
+                let ns = ident(ns);
+                (rust_ident(&rust_name), quote!(js_namespace = #ns,))
+            }
+            None => (rust_ident(&name.to_snake_case()), quote!()),
+        };
+        Some(quote! {
+            #[wasm_bindgen(#namespace js_name = #js_name)]
+            pub fn #rust_name(#(#args),*) #ret;
+        })
+    }
 
-            syn::Type::Path(syn::TypePath {
-                qself: None,
-                path: syn::Path {
-                    leading_colon: None,
-                    segments,
-                },
+    fn enum_constants(&self, name: &str, values: &[(String, TsEnumValue)]) -> TokenStream {
+        let module = rust_ident(&name.to_snake_case());
+        let kind = self.enums[name];
+        let constants = values.iter().map(|&(ref value_name, ref value)| {
+            let value_name = ident(&value_name.to_shouty_snake_case());
+            match kind {
+                EnumKind::Number => {
+                    let value: f64 = value.value.parse().unwrap();
+                    quote!(pub const #value_name: f64 = #value;)
+                }
+                EnumKind::String => {
+                    let value = value.value.trim_matches(|c| c == '"' || c == '\'');
+                    quote!(pub const #value_name: &'static str = #value;)
+                }
+            }
+        });
+        quote! {
+            pub mod #module {
+                #(#constants)*
+            }
+        }
+    }
+
+    /// Arguments in declaration order, or `None` if any of them can't be
+    /// imported.
+    ///
+    /// Optional parameters are passed as a `JsValue`, which can be
+    /// `JsValue::undefined()` to leave them out.
+    fn arguments(&self, parameters: &[(String, TsMethodProperty)]) -> Option<Vec<TokenStream>> {
+        parameters
+            .iter()
+            .map(|&(_, ref param)| {
+                // Rest parameters would need to be spread out on the JS side.
+                if param.is_spread {
+                    return None;
+                }
+                let name = rust_ident(&param.name.to_snake_case());
+                if param.is_optional {
+                    return Some(quote!(#name: JsValue));
+                }
+                let ty = self.ty(&param.property_type, TypePosition::Argument)?;
+                Some(quote!(#name: #ty))
             })
+            .collect()
+    }
+
+    /// The return type of a function, or `None` if it can't be imported.
+    fn ret(&self, ts: &str) -> Option<TokenStream> {
+        if ts.trim() == "void" {
+            return Some(quote!());
+        }
+        let ty = self.ty(ts, TypePosition::Return)?;
+        Some(quote!(-> #ty))
+    }
+
+    /// Map a TypeScript type to the Rust type it is imported as.
+    ///
+    /// Anything without a more precise mapping, such as unions (including
+    /// `T | undefined`) and generics, is passed as a `JsValue`.
+    fn ty(&self, ts: &str, pos: TypePosition) -> Option<TokenStream> {
+        let ts = ts.trim();
+        if ts == "void" {
+            return None;
+        }
+        if ts.starts_with('(') && ts.ends_with(')') {
+            return self.ty(&ts[1..ts.len() - 1], pos);
+        }
+        if is_union(ts) {
+            return Some(quote!(JsValue));
+        }
+
+        let elem = if ts.ends_with("[]") {
+            Some(&ts[..ts.len() - 2])
+        } else if ts.starts_with("Array<") && ts.ends_with('>') {
+            Some(&ts[6..ts.len() - 1])
+        } else {
+            None
+        };
+        if let Some(elem) = elem {
+            return Some(match (elem.trim(), pos) {
+                ("number", TypePosition::Argument) => quote!(&[f64]),
+                ("number", TypePosition::Return) => quote!(Vec<f64>),
+                _ => quote!(JsValue),
+            });
+        }
+
+        let kind = match ts {
+            "number" => return Some(quote!(f64)),
+            "boolean" => return Some(quote!(bool)),
+            "string" => EnumKind::String,
+            _ if ts.starts_with('"') || ts.starts_with('\'') => EnumKind::String,
+            _ => match self.enums.get(ts) {
+                Some(kind) => *kind,
+                None if self.types.contains(ts) => {
+                    let ty = ident(ts);
+                    return Some(match pos {
+                        TypePosition::Argument => quote!(&#ty),
+                        TypePosition::Return => quote!(#ty),
+                    });
+                }
+                None => return Some(quote!(JsValue)),
+            },
+        };
+        Some(match (kind, pos) {
+            (EnumKind::Number, _) => quote!(f64),
+            (EnumKind::String, TypePosition::Argument) => quote!(&str),
+            (EnumKind::String, TypePosition::Return) => quote!(String),
         })
-        .collect::<Vec<_>>();
-
-    let mut ret_segments = syn::punctuated::Punctuated::new();
-    ret_segments.push(syn::PathSegment {
-        ident: syn::Ident::new(&return_value.property_type, proc_macro2::Span::call_site()),
-        arguments: syn::PathArguments::None,
-    });
-
-    let ret = syn::Type::Path(syn::TypePath {
-        qself: None,
-        path: syn::Path {
-            leading_colon: None,
-            segments: ret_segments,
-        },
-    });
-
-    let rust_decl = Box::new(syn::FnDecl {
-        fn_token: Default::default(),
-        generics: Default::default(),
-        paren_token: Default::default(),
-        //TODO investigate if inputs should be taken from arguments
-        inputs: Default::default(),
-        variadic: None,
-        output: syn::ReturnType::Type(Default::default(), Box::new(ret.clone())),
-    });
-
-    Function {
-        name: syn::Ident::new(&name, proc_macro2::Span::call_site()),
-        arguments,
-        ret: Some(ret),
-        opts: BindgenAttrs::default(),
-        rust_attrs: Vec::new(),
-        rust_decl,
-        rust_vis: syn::Visibility::Public(syn::VisPublic {
-            pub_token: Default::default(),
-        }),
     }
 }
+
+fn ident(name: &str) -> Ident {
+    Ident::new(name, Span::call_site())
+}
+
+/// Whether `ts` has a `|` outside of any brackets.
+fn is_union(ts: &str) -> bool {
+    let mut depth = 0;
+    for c in ts.chars() {
+        match c {
+            '(' | '<' | '[' | '{' => depth += 1,
+            ')' | '>' | ']' | '}' => depth -= 1,
+            '|' if depth == 0 => return true,
+            _ => {}
+        }
+    }
+    false
+}
@@ -0,0 +1,54 @@
+extern crate proc_macro2;
+extern crate wasm_bindgen_typescript;
+
+use std::str::FromStr;
+
+use proc_macro2::{TokenStream, TokenTree};
+
+/// The individual tokens of `s`, ignoring whitespace and how punctuation is
+/// spaced, so that formatting doesn't matter when comparing them.
+fn tokens(s: &str) -> Vec<String> {
+    fn flatten(stream: TokenStream, out: &mut Vec<String>) {
+        for tree in stream {
+            match tree {
+                TokenTree::Group(g) => {
+                    let delim = format!("{:?}", g.delimiter());
+                    out.push(format!("{} open", delim));
+                    flatten(g.stream(), out);
+                    out.push(format!("{} close", delim));
+                }
+                TokenTree::Punct(p) => out.push(p.as_char().to_string()),
+                other => out.push(other.to_string()),
+            }
+        }
+    }
+    let mut out = Vec::new();
+    flatten(TokenStream::from_str(s).unwrap(), &mut out);
+    out
+}
+
+macro_rules! assert_generate {
+    ($test_name:ident) => {
+        #[test]
+        fn $test_name() {
+            let json = include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/",
+                stringify!($test_name),
+                ".api.json"
+            ));
+            let expected = include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/expected/",
+                stringify!($test_name),
+                ".rs"
+            ));
+            let actual = wasm_bindgen_typescript::ts_to_rust(json).unwrap();
+
+            assert_eq!(tokens(expected), tokens(&actual));
+        }
+    };
+}
+
+assert_generate!(example);
+assert_generate!(optional);
@@ -0,0 +1,35 @@
+#[wasm_bindgen(module = "example")]
+extern {
+    pub type Point;
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: f64, y: f64) -> Point;
+    #[wasm_bindgen(method, getter = x)]
+    pub fn x(this: &Point,) -> f64;
+    #[wasm_bindgen(method, getter = label)]
+    pub fn label(this: &Point,) -> String;
+    #[wasm_bindgen(method, setter = label)]
+    pub fn set_label(this: &Point, value: &str);
+    #[wasm_bindgen(method, js_name = distanceTo)]
+    pub fn distance_to(this: &Point, other: &Point, unit: f64) -> f64;
+    #[wasm_bindgen(static_method_of = Point, js_name = origin)]
+    pub fn origin() -> Point;
+
+    pub type Options;
+    #[wasm_bindgen(method, structural, getter = verbose)]
+    pub fn verbose(this: &Options,) -> bool;
+    #[wasm_bindgen(method, structural, setter = verbose)]
+    pub fn set_verbose(this: &Options, value: bool);
+    #[wasm_bindgen(method, structural, getter = extra)]
+    pub fn extra(this: &Options,) -> JsValue;
+
+    #[wasm_bindgen(js_name = makePoints)]
+    pub fn make_points(xs: &[f64], options: &Options, name: JsValue) -> Vec<f64>;
+
+    #[wasm_bindgen(js_namespace = geometry, js_name = area)]
+    pub fn geometry_area(width: f64, height: f64) -> f64;
+}
+
+pub mod unit {
+    pub const METERS: f64 = 0f64;
+    pub const FEET: f64 = 1f64;
+}
@@ -0,0 +1,11 @@
+#[wasm_bindgen(module = "optional")]
+extern {
+    pub type Logger;
+    #[wasm_bindgen(constructor)]
+    pub fn new(prefix: JsValue) -> Logger;
+    #[wasm_bindgen(method, js_name = log)]
+    pub fn log(this: &Logger, message: &str, level: JsValue);
+
+    #[wasm_bindgen(js_name = connect)]
+    pub fn connect(url: &str, retries: JsValue, logger: JsValue) -> Logger;
+}
@@ -0,0 +1,147 @@
+extern crate env_logger;
+extern crate failure;
+extern crate proc_macro2;
+extern crate quote;
+extern crate wasm_bindgen_backend as backend;
+extern crate wasm_bindgen_webidl;
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process;
+
+use backend::ast;
+use failure::ResultExt;
+use proc_macro2::TokenTree;
+use quote::ToTokens;
+
+/// Names that can show up in generated signatures without belonging to any
+/// WebIDL interface.
+const BUILTIN_NAMES: &[&str] = &[
+    "wasm_bindgen", "js", "JsValue", "Array", "ArrayBuffer", "DataView", "Object", "Promise",
+    "Result", "Vec", "str", "bool", "i8", "u8", "i16", "u16", "i32", "u32", "i64", "u64", "f32",
+    "f64",
+];
+
+fn main() {
+    env_logger::init();
+
+    if let Err(e) = try_main() {
+        eprintln!("error: {}", e);
+        for cause in e.causes().skip(1) {
+            eprintln!("\tcaused by: {}", cause);
+        }
+        process::exit(1);
+    }
+}
+
+fn try_main() -> Result<(), failure::Error> {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=webidls/enabled");
+
+    let mut source = String::new();
+    let mut entries = fs::read_dir("webidls/enabled")
+        .context("reading webidls/enabled directory")?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for path in entries {
+        if path.extension().and_then(|s| s.to_str()) != Some("webidl") {
+            continue;
+        }
+        println!("cargo:rerun-if-changed={}", path.display());
+        File::open(&path)
+            .and_then(|mut f| f.read_to_string(&mut source))
+            .with_context(|_| format!("reading contents of file \"{}\"", path.display()))?;
+        source.push('\n');
+    }
+
+    let mut program =
+        wasm_bindgen_webidl::parse(&source).context("compiling WebIDL into wasm-bindgen AST")?;
+    retain_enabled(&mut program);
+    let bindings = wasm_bindgen_webidl::compile_ast(&program);
+
+    let out_dir = env::var("OUT_DIR").context("reading OUT_DIR environment variable")?;
+    let out_file_path = Path::new(&out_dir).join("bindings.rs");
+    let mut out_file = File::create(&out_file_path).context("creating output bindings file")?;
+    out_file
+        .write_all(bindings.as_bytes())
+        .context("writing bindings to output file")?;
+
+    Ok(())
+}
+
+/// Whether the cargo feature for the interface `name` is enabled.
+fn feature_enabled(name: &str) -> bool {
+    let var = format!("CARGO_FEATURE_{}", name.to_uppercase().replace("-", "_"));
+    env::var_os(var).is_some()
+}
+
+/// Removes everything from `program` that isn't part of an enabled interface,
+/// or that refers to a type which won't be generated.
+fn retain_enabled(program: &mut ast::Program) {
+    let mut known: BTreeSet<String> = BUILTIN_NAMES.iter().map(|s| s.to_string()).collect();
+
+    program.imports.retain(|import| match import.kind {
+        ast::ImportKind::Type(ref ty) => feature_enabled(&ty.name.to_string()),
+        _ => true,
+    });
+    for import in program.imports.iter() {
+        if let ast::ImportKind::Type(ref ty) = import.kind {
+            known.insert(ty.name.to_string());
+        }
+    }
+
+    // Typedefs may refer to each other, so keep adding the ones whose source
+    // type is known until nothing changes.
+    let mut aliases = Vec::new();
+    loop {
+        let (ok, rest): (Vec<_>, Vec<_>) = program
+            .type_aliases
+            .drain(..)
+            .partition(|alias| only_known_names(&alias.src, &known));
+        program.type_aliases = rest;
+        if ok.is_empty() {
+            break;
+        }
+        for alias in ok {
+            known.insert(alias.dest.to_string());
+            aliases.push(alias);
+        }
+    }
+    program.type_aliases = aliases;
+
+    program.imports.retain(|import| match import.kind {
+        ast::ImportKind::Type(_) => true,
+        ast::ImportKind::Static(ref s) => only_known_names(&s.ty, &known),
+        ast::ImportKind::Function(ref f) => {
+            if let ast::ImportFunctionKind::Method { ref class, .. } = f.kind {
+                if !known.contains(class) {
+                    return false;
+                }
+            }
+            let function = &f.function;
+            function
+                .arguments
+                .iter()
+                .all(|arg| only_known_names(&arg.ty, &known))
+                && function
+                    .ret
+                    .as_ref()
+                    .map_or(true, |ret| only_known_names(ret, &known))
+        }
+    });
+}
+
+fn only_known_names<T: ToTokens>(t: &T, known: &BTreeSet<String>) -> bool {
+    fn check(tokens: proc_macro2::TokenStream, known: &BTreeSet<String>) -> bool {
+        tokens.into_iter().all(|tt| match tt {
+            TokenTree::Ident(ref i) => known.contains(&i.to_string()),
+            TokenTree::Group(ref g) => check(g.stream(), known),
+            _ => true,
+        })
+    }
+    check(t.into_token_stream(), known)
+}
@@ -0,0 +1,15 @@
+//! Raw API bindings for Web APIs
+//!
+//! This is a procedurally generated crate from browser WebIDL which provides a
+//! binding to all APIs that browser provide on the web.
+//!
+//! This crate by default contains very little when compiled as almost all of
+//! its exposed APIs are gated by Cargo features. Each interface is a feature
+//! named after it, for example enable the `Document` feature to get bindings
+//! to the `Document` type and its methods.
+
+#![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+extern crate wasm_bindgen;
+
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
@@ -14,12 +14,18 @@ extern crate heck;
 extern crate log;
 extern crate proc_macro2;
 extern crate quote;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
 extern crate syn;
 extern crate wasm_bindgen_backend as backend;
 extern crate webidl;
 
+mod report;
 mod util;
 
+pub use report::{Definition, Member, Report, Status};
+
 use std::fs;
 use std::io::{self, Read};
 use std::path::Path;
@@ -29,31 +35,45 @@ use failure::ResultExt;
 use quote::ToTokens;
 
 use util::{
-    create_basic_method, create_function, create_getter, create_setter, webidl_ty_to_syn_ty,
-    TypePosition,
+    create_basic_method, create_function, create_getter, create_setter, getter_throws, is_overload,
+    setter_throws, throws, webidl_ty_to_syn_ty, TypePosition,
 };
 
+/// Why the second and later overloads of an operation are skipped.
+const OVERLOAD: &str = "overloaded operations are not supported yet, only the first is translated";
+
 /// Either `Ok(t)` or `Err(failure::Error)`.
 pub type Result<T> = ::std::result::Result<T, failure::Error>;
 
-/// Parse the WebIDL at the given path into a wasm-bindgen AST.
-pub fn parse_file(webidl_path: &Path) -> Result<backend::ast::Program> {
+fn read_file(webidl_path: &Path) -> Result<String> {
     let file = fs::File::open(webidl_path).context("opening WebIDL file")?;
     let mut file = io::BufReader::new(file);
     let mut source = String::new();
     file.read_to_string(&mut source)
         .context("reading WebIDL file")?;
-    parse(&source)
+    Ok(source)
+}
+
+/// Parse the WebIDL at the given path into a wasm-bindgen AST.
+pub fn parse_file(webidl_path: &Path) -> Result<backend::ast::Program> {
+    parse(&read_file(webidl_path)?)
 }
 
 /// Parse a string of WebIDL source text into a wasm-bindgen AST.
 pub fn parse(webidl_source: &str) -> Result<backend::ast::Program> {
+    Ok(parse_with_report(webidl_source)?.0)
+}
+
+/// Parse a string of WebIDL source text into a wasm-bindgen AST, along with a
+/// report of which definitions and members were translated or skipped.
+pub fn parse_with_report(webidl_source: &str) -> Result<(backend::ast::Program, Report)> {
     let definitions = webidl::parse_string(webidl_source).context("parsing WebIDL source text")?;
 
     let mut program = backend::ast::Program::default();
-    definitions.webidl_parse(&mut program, ())?;
+    let mut report = Report::default();
+    definitions.webidl_parse(&mut program, &mut report, ())?;
 
-    Ok(program)
+    Ok((program, report))
 }
 
 /// Compile the given WebIDL file into Rust source text containing
@@ -70,56 +90,120 @@ pub fn compile(webidl_source: &str) -> Result<String> {
     Ok(compile_ast(&ast))
 }
 
-fn compile_ast(ast: &backend::ast::Program) -> String {
+/// Compile the given WebIDL file like `compile_file`, and also return a report
+/// of which definitions and members were translated or skipped.
+pub fn compile_file_with_report(webidl_path: &Path) -> Result<(String, Report)> {
+    compile_with_report(&read_file(webidl_path)?)
+}
+
+/// Compile the given WebIDL source text like `compile`, and also return a
+/// report of which definitions and members were translated or skipped.
+pub fn compile_with_report(webidl_source: &str) -> Result<(String, Report)> {
+    let (ast, report) = parse_with_report(webidl_source)?;
+    Ok((compile_ast(&ast), report))
+}
+
+/// Compile an already parsed (and possibly filtered) wasm-bindgen AST into
+/// Rust source text.
+pub fn compile_ast(ast: &backend::ast::Program) -> String {
     let mut tokens = proc_macro2::TokenStream::new();
     ast.to_tokens(&mut tokens);
     tokens.to_string()
 }
 
+
 trait WebidlParse<Ctx> {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, context: Ctx) -> Result<()>;
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        context: Ctx,
+    ) -> Result<()>;
 }
 
 impl WebidlParse<()> for Vec<webidl::ast::Definition> {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, _: ()) -> Result<()> {
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        _: (),
+    ) -> Result<()> {
         for def in self {
-            def.webidl_parse(program, ())?;
+            def.webidl_parse(program, report, ())?;
         }
         Ok(())
     }
 }
 
 impl WebidlParse<()> for webidl::ast::Definition {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, _: ()) -> Result<()> {
-        match *self {
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        _: (),
+    ) -> Result<()> {
+        let (kind, name) = match *self {
             webidl::ast::Definition::Interface(ref interface) => {
-                interface.webidl_parse(program, ())
+                return interface.webidl_parse(program, report, ())
+            }
+            webidl::ast::Definition::Typedef(ref typedef) => {
+                return typedef.webidl_parse(program, report, ())
             }
-            webidl::ast::Definition::Typedef(ref typedef) => typedef.webidl_parse(program, ()),
             // TODO
-            webidl::ast::Definition::Callback(..)
-            | webidl::ast::Definition::Dictionary(..)
-            | webidl::ast::Definition::Enum(..)
-            | webidl::ast::Definition::Implements(..)
-            | webidl::ast::Definition::Includes(..)
-            | webidl::ast::Definition::Mixin(..)
-            | webidl::ast::Definition::Namespace(..) => {
-                warn!("Unsupported WebIDL definition: {:?}", self);
-                Ok(())
+            webidl::ast::Definition::Callback(ref callback) => ("callback", &callback.name),
+            webidl::ast::Definition::Dictionary(webidl::ast::Dictionary::NonPartial(ref d)) => {
+                ("dictionary", &d.name)
             }
-        }
+            webidl::ast::Definition::Dictionary(webidl::ast::Dictionary::Partial(ref d)) => {
+                ("partial dictionary", &d.name)
+            }
+            webidl::ast::Definition::Enum(ref e) => ("enum", &e.name),
+            webidl::ast::Definition::Implements(ref i) => ("implements", &i.implementer),
+            webidl::ast::Definition::Includes(ref i) => ("includes", &i.includer),
+            webidl::ast::Definition::Mixin(webidl::ast::Mixin::NonPartial(ref m)) => {
+                ("mixin", &m.name)
+            }
+            webidl::ast::Definition::Mixin(webidl::ast::Mixin::Partial(ref m)) => {
+                ("partial mixin", &m.name)
+            }
+            webidl::ast::Definition::Namespace(webidl::ast::Namespace::NonPartial(ref n)) => {
+                ("namespace", &n.name)
+            }
+            webidl::ast::Definition::Namespace(webidl::ast::Namespace::Partial(ref n)) => {
+                ("partial namespace", &n.name)
+            }
+        };
+        report.skipped_definition(kind, name, "unsupported WebIDL definition".to_string());
+        Ok(())
     }
 }
 
 impl WebidlParse<()> for webidl::ast::Interface {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, _: ()) -> Result<()> {
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        _: (),
+    ) -> Result<()> {
         match *self {
             webidl::ast::Interface::NonPartial(ref interface) => {
-                interface.webidl_parse(program, ())
+                interface.webidl_parse(program, report, ())
             }
             // TODO
-            webidl::ast::Interface::Callback(..) | webidl::ast::Interface::Partial(..) => {
-                warn!("Unsupported WebIDL interface: {:?}", self);
+            webidl::ast::Interface::Callback(ref interface) => {
+                report.skipped_definition(
+                    "callback interface",
+                    &interface.name,
+                    "unsupported WebIDL interface".to_string(),
+                );
+                Ok(())
+            }
+            webidl::ast::Interface::Partial(ref interface) => {
+                report.skipped_definition(
+                    "partial interface",
+                    &interface.name,
+                    "unsupported WebIDL interface".to_string(),
+                );
                 Ok(())
             }
         }
@@ -127,8 +211,14 @@ impl WebidlParse<()> for webidl::ast::Interface {
 }
 
 impl WebidlParse<()> for webidl::ast::Typedef {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, _: ()) -> Result<()> {
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        _: (),
+    ) -> Result<()> {
         if util::is_chrome_only(&self.extended_attributes) {
+            report.skipped_definition("typedef", &self.name, "ChromeOnly".to_string());
             return Ok(());
         }
 
@@ -136,9 +226,10 @@ impl WebidlParse<()> for webidl::ast::Typedef {
         let src = match webidl_ty_to_syn_ty(&self.type_, TypePosition::Return) {
             Some(src) => src,
             None => {
-                warn!(
-                    "typedef's source type is not yet supported: {:?}. Skipping typedef {:?}",
-                    *self.type_, self
+                report.skipped_definition(
+                    "typedef",
+                    &self.name,
+                    format!("source type is not yet supported: {:?}", *self.type_),
                 );
                 return Ok(());
             }
@@ -151,14 +242,21 @@ impl WebidlParse<()> for webidl::ast::Typedef {
             dest,
             src,
         });
+        report.definition("typedef", &self.name);
 
         Ok(())
     }
 }
 
 impl WebidlParse<()> for webidl::ast::NonPartialInterface {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, _: ()) -> Result<()> {
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        _: (),
+    ) -> Result<()> {
         if util::is_chrome_only(&self.extended_attributes) {
+            report.skipped_definition("interface", &self.name, "ChromeOnly".to_string());
             return Ok(());
         }
 
@@ -174,13 +272,14 @@ impl WebidlParse<()> for webidl::ast::NonPartialInterface {
                 attrs: Vec::new(),
             }),
         });
+        report.definition("interface", &self.name);
 
         for extended_attribute in &self.extended_attributes {
-            extended_attribute.webidl_parse(program, self)?;
+            extended_attribute.webidl_parse(program, report, self)?;
         }
 
         for member in &self.members {
-            member.webidl_parse(program, &self.name)?;
+            member.webidl_parse(program, report, &self.name)?;
         }
 
         Ok(())
@@ -191,6 +290,7 @@ impl<'a> WebidlParse<&'a webidl::ast::NonPartialInterface> for webidl::ast::Exte
     fn webidl_parse(
         &self,
         program: &mut backend::ast::Program,
+        report: &mut Report,
         interface: &'a webidl::ast::NonPartialInterface,
     ) -> Result<()> {
         let mut add_constructor = |arguments: &[webidl::ast::Argument], class: &str| {
@@ -200,21 +300,30 @@ impl<'a> WebidlParse<&'a webidl::ast::NonPartialInterface> for webidl::ast::Exte
                 ty: self_ty.clone(),
                 kind: backend::ast::MethodKind::Constructor,
             };
-            create_function(
+            let function = create_function(
                 "new",
                 arguments
                     .iter()
                     .map(|arg| (&*arg.name, &*arg.type_, arg.variadic)),
                 Some(self_ty),
                 kind,
-            ).map(|function| {
-                program.imports.push(backend::ast::Import {
-                    module: None,
-                    version: None,
-                    js_namespace: None,
-                    kind: backend::ast::ImportKind::Function(function),
-                })
-            })
+                throws(&interface.extended_attributes),
+            );
+            match function {
+                Ok(ref function) if is_overload(program, function) => {
+                    report.skipped_member("constructor", class, OVERLOAD.to_string())
+                }
+                Ok(function) => {
+                    program.imports.push(backend::ast::Import {
+                        module: None,
+                        version: None,
+                        js_namespace: None,
+                        kind: backend::ast::ImportKind::Function(function),
+                    });
+                    report.translated_member("constructor", class);
+                }
+                Err(reason) => report.skipped_member("constructor", class, reason),
+            }
         };
 
         match self {
@@ -253,32 +362,47 @@ impl<'a> WebidlParse<&'a webidl::ast::NonPartialInterface> for webidl::ast::Exte
 }
 
 impl<'a> WebidlParse<&'a str> for webidl::ast::InterfaceMember {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, self_name: &'a str) -> Result<()> {
-        match *self {
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        self_name: &'a str,
+    ) -> Result<()> {
+        let (kind, name) = match *self {
             webidl::ast::InterfaceMember::Attribute(ref attr) => {
-                attr.webidl_parse(program, self_name)
+                return attr.webidl_parse(program, report, self_name)
             }
-            webidl::ast::InterfaceMember::Operation(ref op) => op.webidl_parse(program, self_name),
-            // TODO
-            webidl::ast::InterfaceMember::Const(_)
-            | webidl::ast::InterfaceMember::Iterable(_)
-            | webidl::ast::InterfaceMember::Maplike(_)
-            | webidl::ast::InterfaceMember::Setlike(_) => {
-                warn!("Unsupported WebIDL interface member: {:?}", self);
-                Ok(())
+            webidl::ast::InterfaceMember::Operation(ref op) => {
+                return op.webidl_parse(program, report, self_name)
             }
-        }
+            // TODO
+            webidl::ast::InterfaceMember::Const(ref c) => ("const", &*c.name),
+            webidl::ast::InterfaceMember::Iterable(_) => ("iterable", ""),
+            webidl::ast::InterfaceMember::Maplike(_) => ("maplike", ""),
+            webidl::ast::InterfaceMember::Setlike(_) => ("setlike", ""),
+        };
+        report.skipped_member(kind, name, "unsupported WebIDL interface member".to_string());
+        Ok(())
     }
 }
 
 impl<'a> WebidlParse<&'a str> for webidl::ast::Attribute {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, self_name: &'a str) -> Result<()> {
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        self_name: &'a str,
+    ) -> Result<()> {
         match self {
-            webidl::ast::Attribute::Regular(attr) => attr.webidl_parse(program, self_name),
-            webidl::ast::Attribute::Static(attr) => attr.webidl_parse(program, self_name),
+            webidl::ast::Attribute::Regular(attr) => attr.webidl_parse(program, report, self_name),
+            webidl::ast::Attribute::Static(attr) => attr.webidl_parse(program, report, self_name),
             // TODO
-            webidl::ast::Attribute::Stringifier(_) => {
-                warn!("Unsupported WebIDL attribute: {:?}", self);
+            webidl::ast::Attribute::Stringifier(attr) => {
+                report.skipped_member(
+                    "stringifier attribute",
+                    &attr.name,
+                    "unsupported WebIDL attribute".to_string(),
+                );
                 Ok(())
             }
         }
@@ -286,93 +410,210 @@ impl<'a> WebidlParse<&'a str> for webidl::ast::Attribute {
 }
 
 impl<'a> WebidlParse<&'a str> for webidl::ast::Operation {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, self_name: &'a str) -> Result<()> {
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        self_name: &'a str,
+    ) -> Result<()> {
         match self {
-            webidl::ast::Operation::Regular(op) => op.webidl_parse(program, self_name),
-            webidl::ast::Operation::Static(op) => op.webidl_parse(program, self_name),
+            webidl::ast::Operation::Regular(op) => op.webidl_parse(program, report, self_name),
+            webidl::ast::Operation::Static(op) => op.webidl_parse(program, report, self_name),
             // TODO
-            webidl::ast::Operation::Special(_) | webidl::ast::Operation::Stringifier(_) => {
-                warn!("Unsupported WebIDL operation: {:?}", self);
+            webidl::ast::Operation::Special(op) => {
+                report.skipped_member(
+                    "special operation",
+                    op.name.as_ref().map_or("", |s| &**s),
+                    "unsupported WebIDL operation".to_string(),
+                );
+                Ok(())
+            }
+            webidl::ast::Operation::Stringifier(_) => {
+                report.skipped_member(
+                    "stringifier",
+                    "",
+                    "unsupported WebIDL operation".to_string(),
+                );
                 Ok(())
             }
         }
     }
 }
 
-impl<'a> WebidlParse<&'a str> for webidl::ast::RegularAttribute {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, self_name: &'a str) -> Result<()> {
-        if util::is_chrome_only(&self.extended_attributes) {
-            return Ok(());
+/// Add the accessors for an attribute to `program`, recording the outcome of
+/// each in `report`.
+fn add_attribute(
+    program: &mut backend::ast::Program,
+    report: &mut Report,
+    self_name: &str,
+    name: &str,
+    ty: &webidl::ast::Type,
+    read_only: bool,
+    is_static: bool,
+    extended_attributes: &[Box<webidl::ast::ExtendedAttribute>],
+) {
+    if util::is_chrome_only(extended_attributes) {
+        report.skipped_member("attribute", name, "ChromeOnly".to_string());
+        return;
+    }
+
+    let getter = create_getter(
+        name,
+        ty,
+        self_name,
+        is_static,
+        getter_throws(extended_attributes),
+    );
+    match getter {
+        Ok(ref function) if is_overload(program, function) => {
+            report.skipped_member("getter", name, OVERLOAD.to_string())
+        }
+        Ok(function) => {
+            program.imports.push(wrap_import_function(function));
+            report.translated_member("getter", name);
+        }
+        Err(reason) => report.skipped_member("getter", name, reason),
+    }
+
+    if !read_only {
+        let setter = create_setter(
+            name,
+            ty,
+            self_name,
+            is_static,
+            setter_throws(extended_attributes),
+        );
+        match setter {
+            Ok(ref function) if is_overload(program, function) => {
+                report.skipped_member("setter", name, OVERLOAD.to_string())
+            }
+            Ok(function) => {
+                program.imports.push(wrap_import_function(function));
+                report.translated_member("setter", name);
+            }
+            Err(reason) => report.skipped_member("setter", name, reason),
         }
+    }
+}
 
-        create_getter(&self.name, &self.type_, self_name, false)
-            .map(wrap_import_function)
-            .map(|import| program.imports.push(import));
+/// Add an operation to `program`, recording the outcome in `report`.
+fn add_operation(
+    program: &mut backend::ast::Program,
+    report: &mut Report,
+    self_name: &str,
+    name: Option<&String>,
+    arguments: &[webidl::ast::Argument],
+    return_type: &webidl::ast::ReturnType,
+    is_static: bool,
+    extended_attributes: &[Box<webidl::ast::ExtendedAttribute>],
+) {
+    let report_name = name.map_or("", |s| &**s);
+
+    if util::is_chrome_only(extended_attributes) {
+        report.skipped_member("operation", report_name, "ChromeOnly".to_string());
+        return;
+    }
 
-        if !self.read_only {
-            create_setter(&self.name, &self.type_, self_name, false)
-                .map(wrap_import_function)
-                .map(|import| program.imports.push(import));
+    let function = create_basic_method(
+        arguments,
+        name,
+        return_type,
+        self_name,
+        is_static,
+        throws(extended_attributes),
+    );
+    match function {
+        Ok(ref function) if is_overload(program, function) => {
+            report.skipped_member("operation", report_name, OVERLOAD.to_string())
+        }
+        Ok(function) => {
+            program.imports.push(wrap_import_function(function));
+            report.translated_member("operation", report_name);
         }
+        Err(reason) => report.skipped_member("operation", report_name, reason),
+    }
+}
 
+impl<'a> WebidlParse<&'a str> for webidl::ast::RegularAttribute {
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        self_name: &'a str,
+    ) -> Result<()> {
+        add_attribute(
+            program,
+            report,
+            self_name,
+            &self.name,
+            &self.type_,
+            self.read_only,
+            false,
+            &self.extended_attributes,
+        );
         Ok(())
     }
 }
 
 impl<'a> WebidlParse<&'a str> for webidl::ast::StaticAttribute {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, self_name: &'a str) -> Result<()> {
-        if util::is_chrome_only(&self.extended_attributes) {
-            return Ok(());
-        }
-
-        create_getter(&self.name, &self.type_, self_name, true)
-            .map(wrap_import_function)
-            .map(|import| program.imports.push(import));
-
-        if !self.read_only {
-            create_setter(&self.name, &self.type_, self_name, true)
-                .map(wrap_import_function)
-                .map(|import| program.imports.push(import));
-        }
-
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        self_name: &'a str,
+    ) -> Result<()> {
+        add_attribute(
+            program,
+            report,
+            self_name,
+            &self.name,
+            &self.type_,
+            self.read_only,
+            true,
+            &self.extended_attributes,
+        );
         Ok(())
     }
 }
 
 impl<'a> WebidlParse<&'a str> for webidl::ast::RegularOperation {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, self_name: &'a str) -> Result<()> {
-        if util::is_chrome_only(&self.extended_attributes) {
-            return Ok(());
-        }
-
-        create_basic_method(
-            &self.arguments,
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        self_name: &'a str,
+    ) -> Result<()> {
+        add_operation(
+            program,
+            report,
+            self_name,
             self.name.as_ref(),
+            &self.arguments,
             &self.return_type,
-            self_name,
             false,
-        ).map(wrap_import_function)
-            .map(|import| program.imports.push(import));
-
+            &self.extended_attributes,
+        );
         Ok(())
     }
 }
 
 impl<'a> WebidlParse<&'a str> for webidl::ast::StaticOperation {
-    fn webidl_parse(&self, program: &mut backend::ast::Program, self_name: &'a str) -> Result<()> {
-        if util::is_chrome_only(&self.extended_attributes) {
-            return Ok(());
-        }
-
-        create_basic_method(
-            &self.arguments,
+    fn webidl_parse(
+        &self,
+        program: &mut backend::ast::Program,
+        report: &mut Report,
+        self_name: &'a str,
+    ) -> Result<()> {
+        add_operation(
+            program,
+            report,
+            self_name,
             self.name.as_ref(),
+            &self.arguments,
             &self.return_type,
-            self_name,
             true,
-        ).map(wrap_import_function)
-            .map(|import| program.imports.push(import));
-
+            &self.extended_attributes,
+        );
         Ok(())
     }
 }
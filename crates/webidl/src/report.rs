@@ -0,0 +1,157 @@
+//! A record of what was, and wasn't, translated from WebIDL into bindings.
+
+use std::fmt;
+
+/// What happened to every definition, and every member of those definitions,
+/// while translating some WebIDL.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    /// The top level definitions, in the order they were encountered.
+    pub definitions: Vec<Definition>,
+}
+
+/// A top level WebIDL definition, such as an interface or typedef.
+#[derive(Debug, Serialize)]
+pub struct Definition {
+    /// What sort of definition this is, e.g. `"interface"`.
+    pub kind: &'static str,
+    /// The name the definition was given in the WebIDL.
+    pub name: String,
+    /// Whether bindings were generated for this definition.
+    pub status: Status,
+    /// The members of this definition, e.g. the attributes and operations of
+    /// an interface.
+    pub members: Vec<Member>,
+}
+
+/// A member of a top level definition, such as an operation of an interface.
+#[derive(Debug, Serialize)]
+pub struct Member {
+    /// What sort of member this is, e.g. `"operation"`.
+    pub kind: &'static str,
+    /// The name the member was given in the WebIDL.
+    pub name: String,
+    /// Whether bindings were generated for this member.
+    pub status: Status,
+}
+
+/// The outcome of translating a definition or member.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "lowercase")]
+pub enum Status {
+    /// Bindings were generated for everything.
+    Translated,
+    /// Bindings were generated, but some members were skipped.
+    Partial,
+    /// No bindings were generated, for the given reason.
+    Skipped(String),
+}
+
+impl Report {
+    /// Record a new top level definition, whose members will be recorded with
+    /// `translated_member` and `skipped_member` until the next definition.
+    pub(crate) fn definition(&mut self, kind: &'static str, name: &str) {
+        self.definitions.push(Definition {
+            kind,
+            name: name.to_string(),
+            status: Status::Translated,
+            members: Vec::new(),
+        });
+    }
+
+    /// Record a top level definition that no bindings were generated for.
+    pub(crate) fn skipped_definition(&mut self, kind: &'static str, name: &str, reason: String) {
+        warn!("Skipping WebIDL {} {}: {}", kind, name, reason);
+        self.definitions.push(Definition {
+            kind,
+            name: name.to_string(),
+            status: Status::Skipped(reason),
+            members: Vec::new(),
+        });
+    }
+
+    /// Record a member of the current definition that bindings were
+    /// generated for.
+    pub(crate) fn translated_member(&mut self, kind: &'static str, name: &str) {
+        self.push_member(Member {
+            kind,
+            name: name.to_string(),
+            status: Status::Translated,
+        });
+    }
+
+    /// Record a member of the current definition that was skipped.
+    pub(crate) fn skipped_member(&mut self, kind: &'static str, name: &str, reason: String) {
+        warn!("Skipping WebIDL {} {}: {}", kind, name, reason);
+        if let Some(def) = self.definitions.last_mut() {
+            if def.status == Status::Translated {
+                def.status = Status::Partial;
+            }
+        }
+        self.push_member(Member {
+            kind,
+            name: name.to_string(),
+            status: Status::Skipped(reason),
+        });
+    }
+
+    fn push_member(&mut self, member: Member) {
+        self.definitions
+            .last_mut()
+            .expect("members are only recorded within a definition")
+            .members
+            .push(member);
+    }
+
+    /// The number of definitions with the given status, ignoring any reason
+    /// for skipped definitions.
+    pub fn count(&self, status: &Status) -> usize {
+        self.definitions
+            .iter()
+            .filter(|def| same_status(&def.status, status))
+            .count()
+    }
+
+    /// Append all of the definitions in `other` to this report.
+    pub fn extend(&mut self, other: Report) {
+        self.definitions.extend(other.definitions);
+    }
+}
+
+fn same_status(a: &Status, b: &Status) -> bool {
+    match (a, b) {
+        (Status::Translated, Status::Translated)
+        | (Status::Partial, Status::Partial)
+        | (Status::Skipped(_), Status::Skipped(_)) => true,
+        _ => false,
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Status::Translated => f.write_str("translated"),
+            Status::Partial => f.write_str("partially translated"),
+            Status::Skipped(ref reason) => write!(f, "skipped: {}", reason),
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for def in &self.definitions {
+            writeln!(f, "{} {}: {}", def.kind, def.name, def.status)?;
+            for member in &def.members {
+                writeln!(f, "    {} {}: {}", member.kind, member.name, member.status)?;
+            }
+        }
+        write!(
+            f,
+            "{} definitions: {} translated, {} partially translated, {} skipped",
+            self.definitions.len(),
+            self.count(&Status::Translated),
+            self.count(&Status::Partial),
+            self.count(&Status::Skipped(String::new())),
+        )
+    }
+}
@@ -1,4 +1,4 @@
-use std::iter;
+use std::iter::{self, FromIterator};
 
 use backend;
 use backend::util::{ident_ty, raw_ident, rust_ident, simple_path_ty};
@@ -17,12 +17,102 @@ fn shared_ref(ty: syn::Type) -> syn::Type {
     }.into()
 }
 
+fn slice_ty(t: syn::Type) -> syn::Type {
+    syn::TypeSlice {
+        bracket_token: Default::default(),
+        elem: Box::new(t),
+    }.into()
+}
+
+fn generic_ty(name: &str, args: Vec<syn::Type>) -> syn::Type {
+    let arguments = syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments {
+        colon2_token: None,
+        lt_token: Default::default(),
+        args: FromIterator::from_iter(args.into_iter().map(syn::GenericArgument::Type)),
+        gt_token: Default::default(),
+    });
+
+    // The arguments go on the last segment of a path like `a::B`.
+    let mut segments = name
+        .split("::")
+        .map(|s| syn::PathSegment {
+            ident: raw_ident(s),
+            arguments: syn::PathArguments::None,
+        })
+        .collect::<Vec<_>>();
+    segments.last_mut().unwrap().arguments = arguments;
+    let path = syn::Path {
+        leading_colon: None,
+        segments: FromIterator::from_iter(segments),
+    };
+    let ty = syn::TypePath { qself: None, path };
+    ty.into()
+}
+
+fn vec_ty(t: syn::Type) -> syn::Type {
+    generic_ty("Vec", vec![t])
+}
+
+/// Wrap the return type of an import in `Result<_, JsValue>`, as expected by
+/// `catch` imports.
+fn result_ty(t: Option<syn::Type>) -> syn::Type {
+    let t = t.unwrap_or_else(|| {
+        syn::TypeTuple {
+            paren_token: Default::default(),
+            elems: syn::punctuated::Punctuated::new(),
+        }.into()
+    });
+    let js_value = simple_path_ty(vec![rust_ident("wasm_bindgen"), rust_ident("JsValue")]);
+    generic_ty("Result", vec![t, js_value])
+}
+
+/// Create a path type for the `wasm_bindgen::js` binding of the given JS
+/// global, for example `js_global_ty("Object")` is `wasm_bindgen::js::Object`.
+fn js_global_ty(name: &str) -> syn::Type {
+    simple_path_ty(vec![
+        rust_ident("wasm_bindgen"),
+        rust_ident("js"),
+        rust_ident(name),
+    ])
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TypePosition {
     Argument,
     Return,
 }
 
+/// The Rust scalar that a non-nullable numeric WebIDL type is represented
+/// with, if any.
+fn webidl_scalar_to_rust(kind: &webidl::ast::TypeKind) -> Option<&'static str> {
+    Some(match *kind {
+        webidl::ast::TypeKind::Byte => "i8",
+        webidl::ast::TypeKind::Octet => "u8",
+        webidl::ast::TypeKind::SignedShort => "i16",
+        webidl::ast::TypeKind::UnsignedShort => "u16",
+        webidl::ast::TypeKind::SignedLong => "i32",
+        webidl::ast::TypeKind::UnsignedLong => "u32",
+        webidl::ast::TypeKind::SignedLongLong => "i64",
+        webidl::ast::TypeKind::UnsignedLongLong => "u64",
+        webidl::ast::TypeKind::RestrictedFloat | webidl::ast::TypeKind::UnrestrictedFloat => "f32",
+        webidl::ast::TypeKind::RestrictedDouble | webidl::ast::TypeKind::UnrestrictedDouble => {
+            "f64"
+        }
+        _ => return None,
+    })
+}
+
+/// Contiguous numeric data (typed arrays and sequences of numbers) is copied
+/// across the boundary: borrowed as `&[T]` when passed to JS, and returned
+/// from JS as an owned `Vec<T>`.
+fn buffer_ty(elem: &str, pos: TypePosition) -> syn::Type {
+    let elem = ident_ty(raw_ident(elem));
+    match pos {
+        TypePosition::Argument => shared_ref(slice_ty(elem)),
+        TypePosition::Return => vec_ty(elem),
+    }
+}
+
 pub fn webidl_ty_to_syn_ty(ty: &webidl::ast::Type, pos: TypePosition) -> Option<syn::Type> {
     // nullable types are not yet supported (see issue #14)
     if ty.nullable {
@@ -42,18 +132,18 @@ pub fn webidl_ty_to_syn_ty(ty: &webidl::ast::Type, pos: TypePosition) -> Option<
         webidl::ast::TypeKind::Boolean => ident_ty(raw_ident("bool")),
         webidl::ast::TypeKind::Byte => ident_ty(raw_ident("i8")),
         webidl::ast::TypeKind::Octet => ident_ty(raw_ident("u8")),
-        webidl::ast::TypeKind::RestrictedDouble | webidl::ast::TypeKind::UnrestrictedDouble => {
-            ident_ty(raw_ident("f64"))
-        }
-        webidl::ast::TypeKind::RestrictedFloat | webidl::ast::TypeKind::UnrestrictedFloat => {
-            ident_ty(raw_ident("f32"))
-        }
-        webidl::ast::TypeKind::SignedLong => ident_ty(raw_ident("i32")),
-        webidl::ast::TypeKind::SignedLongLong => ident_ty(raw_ident("i64")),
         webidl::ast::TypeKind::SignedShort => ident_ty(raw_ident("i16")),
+        webidl::ast::TypeKind::UnsignedShort => ident_ty(raw_ident("u16")),
+        webidl::ast::TypeKind::SignedLong => ident_ty(raw_ident("i32")),
         webidl::ast::TypeKind::UnsignedLong => ident_ty(raw_ident("u32")),
+        webidl::ast::TypeKind::SignedLongLong => ident_ty(raw_ident("i64")),
         webidl::ast::TypeKind::UnsignedLongLong => ident_ty(raw_ident("u64")),
-        webidl::ast::TypeKind::UnsignedShort => ident_ty(raw_ident("u16")),
+        webidl::ast::TypeKind::RestrictedFloat | webidl::ast::TypeKind::UnrestrictedFloat => {
+            ident_ty(raw_ident("f32"))
+        }
+        webidl::ast::TypeKind::RestrictedDouble | webidl::ast::TypeKind::UnrestrictedDouble => {
+            ident_ty(raw_ident("f64"))
+        }
 
         // `DOMString -> `&str` for arguments
         webidl::ast::TypeKind::DOMString if pos == TypePosition::Argument => {
@@ -62,29 +152,53 @@ pub fn webidl_ty_to_syn_ty(ty: &webidl::ast::Type, pos: TypePosition) -> Option<
         // `DOMString` is not supported yet in other positions.
         webidl::ast::TypeKind::DOMString => return None,
 
+        // Buffer sources. Typed arrays are views of numbers and are passed as
+        // slices, the rest are handles to the JS objects themselves.
+        webidl::ast::TypeKind::Int8Array => buffer_ty("i8", pos),
+        webidl::ast::TypeKind::Uint8Array => buffer_ty("u8", pos),
+        webidl::ast::TypeKind::Int16Array => buffer_ty("i16", pos),
+        webidl::ast::TypeKind::Uint16Array => buffer_ty("u16", pos),
+        webidl::ast::TypeKind::Int32Array => buffer_ty("i32", pos),
+        webidl::ast::TypeKind::Uint32Array => buffer_ty("u32", pos),
+        webidl::ast::TypeKind::Float32Array => buffer_ty("f32", pos),
+        webidl::ast::TypeKind::Float64Array => buffer_ty("f64", pos),
+        webidl::ast::TypeKind::Uint8ClampedArray => {
+            generic_ty("wasm_bindgen::Clamped", vec![buffer_ty("u8", pos)])
+        }
+        webidl::ast::TypeKind::ArrayBuffer => js_global_ty("ArrayBuffer"),
+        webidl::ast::TypeKind::DataView => js_global_ty("DataView"),
+
+        // Sequences of numbers are copied like typed arrays, anything else is
+        // left as a JS array.
+        webidl::ast::TypeKind::Sequence(ref inner)
+        | webidl::ast::TypeKind::FrozenArray(ref inner) => {
+            match (inner.nullable, webidl_scalar_to_rust(&inner.kind)) {
+                (false, Some(elem)) => buffer_ty(elem, pos),
+                _ => js_global_ty("Array"),
+            }
+        }
+
+        // Objects and records are plain JS objects.
+        webidl::ast::TypeKind::Object | webidl::ast::TypeKind::Record(..) => {
+            js_global_ty("Object")
+        }
+
+        // The type that a promise resolves with is not reflected in the
+        // bindings.
+        webidl::ast::TypeKind::Promise(_) => js_global_ty("Promise"),
+
+        // Unions can hold any one of their member types, so they become
+        // `JsValue` and are left to be inspected at runtime.
+        webidl::ast::TypeKind::Union(_) => {
+            simple_path_ty(vec![rust_ident("wasm_bindgen"), rust_ident("JsValue")])
+        }
+
         // Support for these types is not yet implemented, so skip
         // generating any bindings for this function.
-        webidl::ast::TypeKind::ArrayBuffer
-        | webidl::ast::TypeKind::ByteString
-        | webidl::ast::TypeKind::DataView
+        webidl::ast::TypeKind::ByteString
         | webidl::ast::TypeKind::Error
-        | webidl::ast::TypeKind::Float32Array
-        | webidl::ast::TypeKind::Float64Array
-        | webidl::ast::TypeKind::FrozenArray(_)
-        | webidl::ast::TypeKind::Int16Array
-        | webidl::ast::TypeKind::Int32Array
-        | webidl::ast::TypeKind::Int8Array
-        | webidl::ast::TypeKind::Object
-        | webidl::ast::TypeKind::Promise(_)
-        | webidl::ast::TypeKind::Record(..)
-        | webidl::ast::TypeKind::Sequence(_)
         | webidl::ast::TypeKind::Symbol
-        | webidl::ast::TypeKind::USVString
-        | webidl::ast::TypeKind::Uint16Array
-        | webidl::ast::TypeKind::Uint32Array
-        | webidl::ast::TypeKind::Uint8Array
-        | webidl::ast::TypeKind::Uint8ClampedArray
-        | webidl::ast::TypeKind::Union(_) => {
+        | webidl::ast::TypeKind::USVString => {
             return None;
         }
     })
@@ -106,7 +220,7 @@ fn simple_fn_arg(ident: Ident, ty: syn::Type) -> syn::ArgCaptured {
 fn webidl_arguments_to_syn_arg_captured<'a, I>(
     arguments: I,
     kind: &backend::ast::ImportFunctionKind,
-) -> Option<Vec<syn::ArgCaptured>>
+) -> Result<Vec<syn::ArgCaptured>, String>
 where
     I: Iterator<Item = (&'a str, &'a webidl::ast::Type, bool)>,
 {
@@ -130,20 +244,18 @@ where
 
     for (name, ty, variadic) in arguments {
         if variadic {
-            warn!("Variadic arguments are not supported yet",);
-            return None;
+            return Err("variadic arguments are not supported yet".to_string());
         }
 
         match webidl_ty_to_syn_ty(ty, TypePosition::Argument) {
             None => {
-                warn!("Argument's type is not yet supported: {:?}", ty);
-                return None;
+                return Err(format!("argument's type is not yet supported: {:?}", ty));
             }
             Some(ty) => res.push(simple_fn_arg(rust_ident(&name.to_snake_case()), ty)),
         }
     }
 
-    Some(res)
+    Ok(res)
 }
 
 pub fn create_function<'a, I>(
@@ -151,7 +263,8 @@ pub fn create_function<'a, I>(
     arguments: I,
     ret: Option<syn::Type>,
     kind: backend::ast::ImportFunctionKind,
-) -> Option<backend::ast::ImportFunction>
+    catch: bool,
+) -> Result<backend::ast::ImportFunction, String>
 where
     I: Iterator<Item = (&'a str, &'a webidl::ast::Type, bool)>,
 {
@@ -162,6 +275,8 @@ where
 
     let js_ret = ret.clone();
 
+    let ret = if catch { Some(result_ty(ret)) } else { ret };
+
     let shim = {
         let ns = match kind {
             backend::ast::ImportFunctionKind::Normal => "",
@@ -171,7 +286,7 @@ where
         raw_ident(&format!("__widl_f_{}_{}", rust_name, ns))
     };
 
-    Some(backend::ast::ImportFunction {
+    Ok(backend::ast::ImportFunction {
         function: backend::ast::Function {
             name,
             arguments,
@@ -180,10 +295,11 @@ where
             rust_vis: syn::Visibility::Public(syn::VisPublic {
                 pub_token: Default::default(),
             }),
+            location: None,
         },
         rust_name,
         js_ret,
-        catch: false,
+        catch,
         structural: false,
         kind,
         shim,
@@ -196,11 +312,11 @@ pub fn create_basic_method(
     return_type: &webidl::ast::ReturnType,
     self_name: &str,
     is_static: bool,
-) -> Option<backend::ast::ImportFunction> {
+    catch: bool,
+) -> Result<backend::ast::ImportFunction, String> {
     let name = match name {
         None => {
-            warn!("Operations without a name are unsupported");
-            return None;
+            return Err("operations without a name are unsupported".to_string());
         }
         Some(ref name) => name,
     };
@@ -219,8 +335,7 @@ pub fn create_basic_method(
         webidl::ast::ReturnType::NonVoid(ty) => match webidl_ty_to_syn_ty(ty, TypePosition::Return)
         {
             None => {
-                warn!("Operation's return type is not yet supported: {:?}", ty);
-                return None;
+                return Err(format!("operation's return type is not yet supported: {:?}", ty));
             }
             Some(ty) => Some(ty),
         },
@@ -233,6 +348,7 @@ pub fn create_basic_method(
             .map(|arg| (&*arg.name, &*arg.type_, arg.variadic)),
         ret,
         kind,
+        catch,
     )
 }
 
@@ -241,11 +357,11 @@ pub fn create_getter(
     ty: &webidl::ast::Type,
     self_name: &str,
     is_static: bool,
-) -> Option<backend::ast::ImportFunction> {
+    catch: bool,
+) -> Result<backend::ast::ImportFunction, String> {
     let ret = match webidl_ty_to_syn_ty(ty, TypePosition::Return) {
         None => {
-            warn!("Attribute's type does not yet support reading: {:?}", ty);
-            return None;
+            return Err(format!("attribute's type does not yet support reading: {:?}", ty));
         }
         Some(ty) => Some(ty),
     };
@@ -259,7 +375,7 @@ pub fn create_getter(
         }),
     };
 
-    create_function(name, iter::empty(), ret, kind)
+    create_function(name, iter::empty(), ret, kind, catch)
 }
 
 pub fn create_setter(
@@ -267,7 +383,8 @@ pub fn create_setter(
     ty: &webidl::ast::Type,
     self_name: &str,
     is_static: bool,
-) -> Option<backend::ast::ImportFunction> {
+    catch: bool,
+) -> Result<backend::ast::ImportFunction, String> {
     let kind = backend::ast::ImportFunctionKind::Method {
         class: self_name.to_string(),
         ty: ident_ty(rust_ident(self_name)),
@@ -282,9 +399,55 @@ pub fn create_setter(
         iter::once((name, ty, false)),
         None,
         kind,
+        catch,
     )
 }
 
+/// Whether `program` already imports a function with the same Rust name on
+/// the same type as `function`, which is the case for the second and later
+/// overloads of an operation or constructor.
+pub fn is_overload(
+    program: &backend::ast::Program,
+    function: &backend::ast::ImportFunction,
+) -> bool {
+    fn class(function: &backend::ast::ImportFunction) -> Option<&str> {
+        match function.kind {
+            backend::ast::ImportFunctionKind::Method { ref class, .. } => Some(&class[..]),
+            backend::ast::ImportFunctionKind::Normal => None,
+        }
+    }
+    program.imports.iter().any(|import| match import.kind {
+        backend::ast::ImportKind::Function(ref f) => {
+            f.rust_name == function.rust_name && class(f) == class(function)
+        }
+        _ => false,
+    })
+}
+
+/// Whether `attribute` is present, without any arguments, in `ext_attrs`.
+pub fn has_named_attribute(ext_attrs: &[Box<ExtendedAttribute>], attribute: &str) -> bool {
+    ext_attrs.iter().any(|attr| match &**attr {
+        ExtendedAttribute::NoArguments(webidl::ast::Other::Identifier(name)) => name == attribute,
+        _ => false,
+    })
+}
+
+/// `[Throws]` means the function, or both accessors of an attribute, can throw
+/// an exception that should be caught and returned as a `JsValue`.
+pub fn throws(ext_attrs: &[Box<ExtendedAttribute>]) -> bool {
+    has_named_attribute(ext_attrs, "Throws")
+}
+
+/// Whether reading an attribute can throw, see `throws`.
+pub fn getter_throws(ext_attrs: &[Box<ExtendedAttribute>]) -> bool {
+    throws(ext_attrs) || has_named_attribute(ext_attrs, "GetterThrows")
+}
+
+/// Whether writing an attribute can throw, see `throws`.
+pub fn setter_throws(ext_attrs: &[Box<ExtendedAttribute>]) -> bool {
+    throws(ext_attrs) || has_named_attribute(ext_attrs, "SetterThrows")
+}
+
 /// ChromeOnly is for things that are only exposed to priveleged code in Firefox.
 pub fn is_chrome_only(ext_attrs: &[Box<ExtendedAttribute>]) -> bool {
     ext_attrs.iter().any(|external_attribute| {
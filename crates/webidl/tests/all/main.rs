@@ -10,3 +10,76 @@ mod util;
 use util::*;
 
 assert_compile!(Event);
+
+#[test]
+fn report() {
+    let webidl_source = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/Event.webidl"
+    ));
+    let (_, report) = wb_webidl::compile_with_report(webidl_source).unwrap();
+
+    let event = report
+        .definitions
+        .iter()
+        .find(|def| def.kind == "interface" && def.name == "Event")
+        .unwrap();
+    assert_eq!(event.status, wb_webidl::Status::Partial);
+
+    let member = |name: &str| {
+        event
+            .members
+            .iter()
+            .find(|member| member.name == name)
+            .unwrap()
+    };
+    assert_eq!(member("Event").kind, "constructor");
+    assert_eq!(member("stopPropagation").status, wb_webidl::Status::Translated);
+    match member("NONE").status {
+        wb_webidl::Status::Skipped(_) => {}
+        ref status => panic!("constant should have been skipped: {:?}", status),
+    }
+
+    let dictionary = report
+        .definitions
+        .iter()
+        .find(|def| def.kind == "dictionary")
+        .unwrap();
+    assert_eq!(dictionary.name, "EventInit");
+}
+
+#[test]
+fn overloads() {
+    let webidl_source = r#"
+        [Constructor(unsigned long width),
+         Constructor(DOMString data, unsigned long width)]
+        interface ImageData {
+          void put(double x, double y);
+          void put(double x, double y, double w, double h);
+        };
+    "#;
+    let (program, report) = wb_webidl::parse_with_report(webidl_source).unwrap();
+
+    // Only the first of each overload is bound, as they'd all end up with the
+    // same name in Rust.
+    let functions = program
+        .imports
+        .iter()
+        .filter_map(|import| match import.kind {
+            backend::ast::ImportKind::Function(ref f) => Some(f),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(functions.len(), 2);
+    assert_eq!(functions[0].function.arguments.len(), 1);
+    assert_eq!(functions[1].function.arguments.len(), 3);
+
+    let members = &report.definitions[0].members;
+    assert_eq!(members.len(), 4);
+    for member in &[&members[1], &members[3]] {
+        match member.status {
+            wb_webidl::Status::Skipped(ref reason) => assert!(reason.contains("overload")),
+            ref status => panic!("overload should have been skipped: {:?}", status),
+        }
+    }
+}
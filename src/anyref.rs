@@ -0,0 +1,80 @@
+//! Slots in the table of JS values used in place of the JS heap when
+//! `wasm-bindgen` is run with `--anyref`.
+//!
+//! In that mode JS values are passed to and from imports and exports as native
+//! `anyref`s, and a `JsValue` holds the index of a slot in a wasm table of
+//! `anyref`s (shifted left one bit, just like an index into the JS heap).
+//! Generated wasm and JS move values in and out of the table while the slots
+//! are handed out here. The table itself is grown by JS, which also fills in
+//! the reserved slots.
+
+use std::prelude::v1::*;
+
+use super::__wbindgen_anyref_table_grow;
+
+/// Slots holding `null`, `undefined`, `true` and `false`, matching the
+/// `JSIDX_*` constants.
+const RESERVED: usize = 4;
+
+static mut FREE: Option<Vec<usize>> = None;
+static mut NEXT: usize = 0;
+static mut LEN: usize = 0;
+
+unsafe fn free_slots() -> &'static mut Vec<usize> {
+    FREE.get_or_insert_with(Vec::new)
+}
+
+unsafe fn grow() {
+    let delta = if LEN == 0 { RESERVED + 16 } else { LEN };
+    let prev = __wbindgen_anyref_table_grow(delta);
+    debug_assert_eq!(prev, LEN);
+    if LEN == 0 {
+        NEXT = RESERVED;
+    }
+    LEN += delta;
+}
+
+/// Creates the table along with its reserved slots if that hasn't happened
+/// yet. This must be called before anything is read from the table.
+#[doc(hidden)]
+#[no_mangle]
+pub unsafe extern "C" fn __wbindgen_anyref_table_init() {
+    if LEN == 0 {
+        grow();
+    }
+}
+
+#[doc(hidden)]
+#[no_mangle]
+pub unsafe extern "C" fn __wbindgen_anyref_table_alloc() -> usize {
+    if let Some(slot) = free_slots().pop() {
+        return slot;
+    }
+    if NEXT == LEN {
+        grow();
+    }
+    let slot = NEXT;
+    NEXT += 1;
+    slot
+}
+
+/// Returns `slot` to the free list, the caller is responsible for clearing it
+/// out in the table so its value can be collected.
+#[doc(hidden)]
+#[no_mangle]
+pub unsafe extern "C" fn __wbindgen_anyref_table_dealloc(slot: usize) {
+    if slot < RESERVED {
+        return;
+    }
+    free_slots().push(slot);
+}
+
+/// Number of slots currently in use, for the leak checks of debug mode.
+#[doc(hidden)]
+#[no_mangle]
+pub unsafe extern "C" fn __wbindgen_anyref_heap_live_count() -> usize {
+    if LEN == 0 {
+        return 0;
+    }
+    NEXT - RESERVED - free_slots().len()
+}
@@ -8,7 +8,8 @@ use core::slice;
 use core::str;
 
 use describe::*;
-use {throw, JsValue};
+use threads;
+use {throw, Clamped, JsValue};
 
 #[cfg(feature = "std")]
 use std::prelude::v1::*;
@@ -328,6 +329,38 @@ if_std! {
             String::from_utf8_unchecked(<Vec<u8>>::from_abi(js, extra))
         }
     }
+
+    impl IntoWasmAbi for Clamped<Vec<u8>> {
+        type Abi = <Vec<u8> as IntoWasmAbi>::Abi;
+
+        fn into_abi(self, extra: &mut Stack) -> Self::Abi {
+            self.0.into_abi(extra)
+        }
+    }
+
+    impl FromWasmAbi for Clamped<Vec<u8>> {
+        type Abi = <Vec<u8> as FromWasmAbi>::Abi;
+
+        unsafe fn from_abi(js: Self::Abi, extra: &mut Stack) -> Self {
+            Clamped(<Vec<u8>>::from_abi(js, extra))
+        }
+    }
+}
+
+impl<'a> IntoWasmAbi for Clamped<&'a [u8]> {
+    type Abi = <&'a [u8] as IntoWasmAbi>::Abi;
+
+    fn into_abi(self, extra: &mut Stack) -> Self::Abi {
+        self.0.into_abi(extra)
+    }
+}
+
+impl<'a> IntoWasmAbi for Clamped<&'a mut [u8]> {
+    type Abi = <&'a mut [u8] as IntoWasmAbi>::Abi;
+
+    fn into_abi(self, extra: &mut Stack) -> Self::Abi {
+        self.0.into_abi(extra)
+    }
 }
 
 impl<'a> IntoWasmAbi for &'a str {
@@ -407,12 +440,283 @@ if_std! {
     }
 }
 
+/// A trait for types which can be a field of a `#[derive(JsObject)]` struct,
+/// converted to and from a property of a plain JS object.
+pub trait ObjectField: Sized {
+    /// Describes the type of the field, used to generate TypeScript
+    /// definitions.
+    fn describe_field();
+
+    /// Converts `self` to the JS value of the property.
+    fn into_js(self) -> JsValue;
+
+    /// Recovers a `Self` from the JS value of the property, returning `None`
+    /// if it's not of the right type.
+    fn from_js(js: JsValue) -> Option<Self>;
+}
+
+macro_rules! object_field_numbers {
+    ($($t:ident: $n:ident => $from:expr;)*) => ($(
+        impl ObjectField for $t {
+            fn describe_field() {
+                <$t as WasmDescribe>::describe();
+            }
+
+            fn into_js(self) -> JsValue {
+                JsValue::from_f64(self as f64)
+            }
+
+            fn from_js(js: JsValue) -> Option<$t> {
+                js.as_f64().and_then(|$n| $from)
+            }
+        }
+    )*)
+}
+
+// Casting a float which is out of range to an integer is undefined behavior, so
+// the value from JS must be checked first. Fractions and NaN are rejected too,
+// which the round trip through the integer catches.
+macro_rules! object_field_ints {
+    ($($t:ident)*) => ($(
+        object_field_numbers! {
+            $t: n => {
+                if n >= $t::min_value() as f64 &&
+                    n <= $t::max_value() as f64 &&
+                    (n as $t) as f64 == n
+                {
+                    Some(n as $t)
+                } else {
+                    None
+                }
+            };
+        }
+    )*)
+}
+
+object_field_ints! { i8 u8 i16 u16 i32 u32 }
+
+object_field_numbers! {
+    // Finite values too large for an `f32` are rejected for the same reason as
+    // with integers, while infinities and NaN carry over.
+    f32: n => {
+        let max = ::core::f32::MAX as f64;
+        let inf = ::core::f64::INFINITY;
+        if (n > max && n != inf) || (n < -max && n != -inf) {
+            None
+        } else {
+            Some(n as f32)
+        }
+    };
+    f64: n => Some(n);
+}
+
+impl ObjectField for bool {
+    fn describe_field() {
+        inform(BOOLEAN);
+    }
+
+    fn into_js(self) -> JsValue {
+        JsValue::from_bool(self)
+    }
+
+    fn from_js(js: JsValue) -> Option<bool> {
+        js.as_bool()
+    }
+}
+
+impl ObjectField for JsValue {
+    fn describe_field() {
+        inform(ANYREF);
+    }
+
+    fn into_js(self) -> JsValue {
+        self
+    }
+
+    fn from_js(js: JsValue) -> Option<JsValue> {
+        Some(js)
+    }
+}
+
+impl<T: ObjectField> ObjectField for Option<T> {
+    fn describe_field() {
+        inform(OPTIONAL);
+        T::describe_field();
+    }
+
+    fn into_js(self) -> JsValue {
+        match self {
+            Some(t) => t.into_js(),
+            None => JsValue::undefined(),
+        }
+    }
+
+    fn from_js(js: JsValue) -> Option<Option<T>> {
+        if js.is_null() || js.is_undefined() {
+            Some(None)
+        } else {
+            T::from_js(js).map(Some)
+        }
+    }
+}
+
+if_std! {
+    impl ObjectField for char {
+        fn describe_field() {
+            inform(CHAR);
+        }
+
+        fn into_js(self) -> JsValue {
+            let mut buf = [0; 4];
+            JsValue::from_str(self.encode_utf8(&mut buf))
+        }
+
+        fn from_js(js: JsValue) -> Option<char> {
+            let s = js.as_string()?;
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        }
+    }
+
+    impl ObjectField for String {
+        fn describe_field() {
+            inform(STRING);
+        }
+
+        fn into_js(self) -> JsValue {
+            JsValue::from_str(&self)
+        }
+
+        fn from_js(js: JsValue) -> Option<String> {
+            js.as_string()
+        }
+    }
+
+    impl<T: ObjectField> ObjectField for Vec<T> {
+        fn describe_field() {
+            inform(VECTOR);
+            T::describe_field();
+        }
+
+        fn into_js(self) -> JsValue {
+            unsafe {
+                let array = JsValue {
+                    idx: super::__wbindgen_array_new(),
+                };
+                for t in self {
+                    let t = t.into_js();
+                    super::__wbindgen_array_push(array.idx, t.idx);
+                }
+                array
+            }
+        }
+
+        fn from_js(js: JsValue) -> Option<Vec<T>> {
+            if !js.is_array() {
+                return None;
+            }
+            unsafe {
+                let len = super::__wbindgen_array_length(js.idx);
+                let mut ret = Vec::with_capacity(len as usize);
+                for i in 0..len {
+                    let t = JsValue {
+                        idx: super::__wbindgen_array_get(js.idx, i),
+                    };
+                    ret.push(T::from_js(t)?);
+                }
+                Some(ret)
+            }
+        }
+    }
+}
+
+/// A stack of `u32` values passed alongside the arguments of a call between
+/// JS and Rust, for values which don't fit into the arguments themselves (like
+/// the two halves of a closure).
+///
+/// The values live in a buffer which starts out as a small array and is moved
+/// to the heap and grown as needed, so the number of values per call is only
+/// limited by available memory. As the buffer may move JS must look up its
+/// address with `__wbindgen_global_argument_ptr` after values have been
+/// pushed, rather than caching it. Each thread has its own buffer.
+///
+/// Whoever writes values records how many there are, and reading them back
+/// with `pop` throws rather than reading past them.
 pub struct GlobalStack {
     next: usize,
 }
 
-const GLOBAL_STACK_CAP: usize = 16;
-static mut GLOBAL_STACK: [u32; GLOBAL_STACK_CAP] = [0; GLOBAL_STACK_CAP];
+const GLOBAL_STACK_INLINE_CAP: usize = 16;
+
+/// The buffers of one thread used to pass values between JS and Rust, see
+/// `threads::Local`.
+#[doc(hidden)]
+pub struct GlobalBuffers {
+    inline: [u32; GLOBAL_STACK_INLINE_CAP],
+    heap: *mut u32,
+    cap: usize,
+    /// The number of values last written to the stack.
+    len: usize,
+    /// Return values of exports which don't fit in a wasm value, such as
+    /// slices and 64-bit integers, are written here for JS to read back. This
+    /// is kept separate from the global stack as the stack can move while the
+    /// export runs.
+    ret: [u64; 1],
+}
+
+impl GlobalBuffers {
+    pub const INIT: GlobalBuffers = GlobalBuffers {
+        inline: [0; GLOBAL_STACK_INLINE_CAP],
+        heap: 0 as *mut u32,
+        cap: GLOBAL_STACK_INLINE_CAP,
+        len: 0,
+        ret: [0],
+    };
+
+    fn stack(&mut self) -> *mut u32 {
+        if self.heap.is_null() {
+            self.inline.as_mut_ptr()
+        } else {
+            self.heap
+        }
+    }
+
+    #[cfg(feature = "std")]
+    unsafe fn grow(&mut self) {
+        let old_cap = self.cap;
+        let mut new = Vec::with_capacity(old_cap * 2);
+        new.extend_from_slice(slice::from_raw_parts(self.stack(), old_cap));
+        new.resize(old_cap * 2, 0);
+        self.free();
+        self.heap = new.as_mut_ptr();
+        self.cap = new.len();
+        mem::forget(new);
+    }
+
+    #[cfg(not(feature = "std"))]
+    unsafe fn grow(&mut self) {
+        throw(
+            "too many values passed between JS and Rust in one call, the `std` \
+             feature of the `wasm-bindgen` crate is required to pass more",
+        );
+    }
+
+    /// Frees the stack if it's been moved to the heap.
+    #[cfg(feature = "std")]
+    pub(crate) unsafe fn free(&mut self) {
+        if !self.heap.is_null() {
+            drop(Vec::from_raw_parts(self.heap, self.cap, self.cap));
+            self.heap = 0 as *mut u32;
+        }
+    }
+}
+
+unsafe fn global_buffers() -> &'static mut GlobalBuffers {
+    &mut threads::local().global_buffers
+}
 
 impl GlobalStack {
     pub unsafe fn new() -> GlobalStack {
@@ -423,16 +727,23 @@ impl GlobalStack {
 impl Stack for GlobalStack {
     fn push(&mut self, val: u32) {
         unsafe {
-            assert!(self.next < GLOBAL_STACK_CAP);
-            GLOBAL_STACK[self.next] = val;
+            let buffers = global_buffers();
+            if self.next == buffers.cap {
+                buffers.grow();
+            }
+            *buffers.stack().offset(self.next as isize) = val;
             self.next += 1;
+            buffers.len = self.next;
         }
     }
 
     fn pop(&mut self) -> u32 {
         unsafe {
-            assert!(self.next < GLOBAL_STACK_CAP);
-            let ret = GLOBAL_STACK[self.next];
+            let buffers = global_buffers();
+            if self.next >= buffers.len {
+                throw("popped more values than were pushed onto the global stack");
+            }
+            let ret = *buffers.stack().offset(self.next as isize);
             self.next += 1;
             ret
         }
@@ -442,7 +753,13 @@ impl Stack for GlobalStack {
 #[doc(hidden)]
 #[no_mangle]
 pub unsafe extern "C" fn __wbindgen_global_argument_ptr() -> *mut u32 {
-    GLOBAL_STACK.as_mut_ptr()
+    global_buffers().stack()
+}
+
+#[doc(hidden)]
+#[no_mangle]
+pub unsafe extern "C" fn __wbindgen_global_ret_ptr() -> *mut u64 {
+    global_buffers().ret.as_mut_ptr()
 }
 
 macro_rules! stack_closures {
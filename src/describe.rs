@@ -3,7 +3,7 @@
 
 #![doc(hidden)]
 
-use JsValue;
+use {Clamped, JsValue};
 
 macro_rules! tys {
     ($($a:ident)*) => (tys! { @ ($($a)*) 0 });
@@ -38,6 +38,9 @@ tys! {
     ENUM
     RUST_STRUCT
     CHAR
+    OPTIONAL
+    OBJECT
+    CLAMPED
 }
 
 pub fn inform(a: u32) {
@@ -108,6 +111,13 @@ impl<'a, T: WasmDescribe + ?Sized> WasmDescribe for &'a mut T {
     }
 }
 
+impl<T: WasmDescribe> WasmDescribe for Clamped<T> {
+    fn describe() {
+        inform(CLAMPED);
+        T::describe();
+    }
+}
+
 if_std! {
     use std::prelude::v1::*;
 
@@ -20,6 +20,7 @@ use wasm_bindgen_macro::*;
 use JsValue;
 if_std! {
     use std::prelude::v1::*;
+    use closure::Closure;
 }
 
 // When adding new imports:
@@ -338,6 +339,55 @@ extern "C" {
     pub fn value_of(this: &Boolean) -> bool;
 }
 
+// DataView
+#[wasm_bindgen]
+extern "C" {
+    pub type DataView;
+
+    /// The `DataView` view provides a low-level interface for reading and
+    /// writing multiple number types in an `ArrayBuffer` irrespective of the
+    /// platform's endianness.
+    ///
+    /// https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/DataView
+    #[wasm_bindgen(constructor)]
+    pub fn new(buffer: &ArrayBuffer, byte_offset: u32, byte_length: u32) -> DataView;
+
+    /// The `buffer` accessor property represents the `ArrayBuffer` referenced
+    /// by the `DataView` at construction time.
+    ///
+    /// https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/DataView/buffer
+    #[wasm_bindgen(method, getter, structural)]
+    pub fn buffer(this: &DataView) -> ArrayBuffer;
+
+    /// The `byteLength` accessor property represents the length (in bytes) of
+    /// this view from the start of its `ArrayBuffer`.
+    ///
+    /// https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/DataView/byteLength
+    #[wasm_bindgen(method, getter, structural, js_name = byteLength)]
+    pub fn byte_length(this: &DataView) -> u32;
+
+    /// The `byteOffset` accessor property represents the offset (in bytes) of
+    /// this view from the start of its `ArrayBuffer`.
+    ///
+    /// https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/DataView/byteOffset
+    #[wasm_bindgen(method, getter, structural, js_name = byteOffset)]
+    pub fn byte_offset(this: &DataView) -> u32;
+
+    /// The `getUint8()` method gets an unsigned 8-bit integer (byte) at the
+    /// specified byte offset from the start of the DataView.
+    ///
+    /// https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/DataView/getUint8
+    #[wasm_bindgen(method, js_name = getUint8)]
+    pub fn get_uint8(this: &DataView, byte_offset: u32) -> u8;
+
+    /// The `setUint8()` method stores an unsigned 8-bit integer (byte) value
+    /// at the specified byte offset from the start of the DataView.
+    ///
+    /// https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/DataView/setUint8
+    #[wasm_bindgen(method, js_name = setUint8)]
+    pub fn set_uint8(this: &DataView, byte_offset: u32, value: u8);
+}
+
 // Error
 #[wasm_bindgen]
 extern "C" {
@@ -1099,6 +1149,45 @@ extern "C" {
     pub fn values(object: &Object) -> Array;
 }
 
+// Promise
+#[wasm_bindgen]
+extern "C" {
+    pub type Promise;
+
+    /// The `Promise.resolve(value)` method returns a `Promise` object that is
+    /// resolved with the given value.
+    ///
+    /// https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Promise/resolve
+    #[wasm_bindgen(static_method_of = Promise)]
+    pub fn resolve(value: &JsValue) -> Promise;
+
+    /// The `Promise.reject(reason)` method returns a `Promise` object that is
+    /// rejected with the given reason.
+    ///
+    /// https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Promise/reject
+    #[wasm_bindgen(static_method_of = Promise)]
+    pub fn reject(reason: &JsValue) -> Promise;
+}
+
+if_std! {
+    #[wasm_bindgen]
+    extern "C" {
+        /// The `then()` method returns a `Promise`. It takes a callback
+        /// function for the success case of the `Promise`.
+        ///
+        /// https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Promise/then
+        #[wasm_bindgen(method)]
+        pub fn then(this: &Promise, on_fulfilled: &Closure<FnMut(JsValue)>) -> Promise;
+
+        /// The `catch()` method returns a `Promise` and deals with rejected
+        /// cases only.
+        ///
+        /// https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Promise/catch
+        #[wasm_bindgen(method)]
+        pub fn catch(this: &Promise, on_rejected: &Closure<FnMut(JsValue)>) -> Promise;
+    }
+}
+
 // Proxy
 #[wasm_bindgen]
 extern {
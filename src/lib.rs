@@ -10,6 +10,7 @@
 #![no_std]
 
 #[cfg(feature = "serde-serialize")]
+#[macro_use]
 extern crate serde;
 #[cfg(feature = "serde-serialize")]
 extern crate serde_json;
@@ -18,7 +19,7 @@ extern crate wasm_bindgen_macro;
 
 use core::cell::UnsafeCell;
 use core::fmt;
-use core::ops::Deref;
+use core::ops::{self, Deref, DerefMut};
 use core::ptr;
 
 use convert::FromWasmAbi;
@@ -35,7 +36,7 @@ macro_rules! if_std {
 /// use wasm_bindgen::prelude::*;
 /// ```
 pub mod prelude {
-    pub use wasm_bindgen_macro::wasm_bindgen;
+    pub use wasm_bindgen_macro::{wasm_bindgen, JsObject};
     pub use JsValue;
 
     if_std! {
@@ -47,6 +48,9 @@ pub mod convert;
 pub mod describe;
 #[cfg(feature = "js_globals")]
 pub mod js;
+#[cfg(feature = "serde-serialize")]
+pub mod serde_js;
+pub mod threads;
 
 #[cfg(feature = "js_globals")]
 mod wasm_bindgen {
@@ -57,6 +61,8 @@ if_std! {
     extern crate std;
     use std::prelude::v1::*;
     pub mod closure;
+    pub mod panic;
+    mod anyref;
 }
 
 /// Representation of an object owned by JS.
@@ -69,6 +75,8 @@ pub struct JsValue {
     idx: u32,
 }
 
+// NB: these must be kept in sync with `SLAB_RESERVED` in
+// `crates/cli-support/src/js/mod.rs`
 const JSIDX_NULL: u32 = 0;
 const JSIDX_UNDEFINED: u32 = 2;
 const JSIDX_TRUE: u32 = 4;
@@ -157,7 +165,8 @@ impl JsValue {
     /// send the JSON string to JS, parse it into a JS object, and then return
     /// a handle to the JS object. This is unlikely to be super speedy so it's
     /// not recommended for large payloads, but it's a nice to have in some
-    /// situations!
+    /// situations! For those the `serde_js` module converts values directly
+    /// without going through JSON.
     ///
     /// Usage of this API requires activating the `serde-serialize` feature of
     /// the `wasm-bindgen` crate.
@@ -183,7 +192,8 @@ impl JsValue {
     ///
     /// This function will first call `JSON.stringify` on the `JsValue` itself.
     /// The resulting string is then passed into Rust which then parses it as
-    /// JSON into the resulting value.
+    /// JSON into the resulting value. Like `from_serde`, the `serde_js` module
+    /// is a faster alternative which also supports more kinds of JS values.
     ///
     /// Usage of this API requires activating the `serde-serialize` feature of
     /// the `wasm-bindgen` crate.
@@ -270,6 +280,182 @@ impl JsValue {
     pub fn is_symbol(&self) -> bool {
         unsafe { __wbindgen_is_symbol(self.idx) == 1 }
     }
+
+    /// Tests whether the type of this JS value is `object`, excluding `null`
+    pub fn is_object(&self) -> bool {
+        unsafe { __wbindgen_is_object(self.idx) == 1 }
+    }
+
+    /// Tests whether the type of this JS value is `function`
+    pub fn is_function(&self) -> bool {
+        unsafe { __wbindgen_is_function(self.idx) == 1 }
+    }
+
+    /// Tests whether the type of this JS value is `string`
+    pub fn is_string(&self) -> bool {
+        unsafe { __wbindgen_is_string(self.idx) == 1 }
+    }
+
+    /// Tests whether this JS value is an array, as `Array.isArray` does
+    pub fn is_array(&self) -> bool {
+        unsafe { __wbindgen_is_array(self.idx) == 1 }
+    }
+
+    /// Returns the result of the JS `typeof` operator on this value, for
+    /// example `"number"` or `"object"`.
+    pub fn js_typeof(&self) -> JsValue {
+        unsafe {
+            JsValue {
+                idx: __wbindgen_typeof(self.idx),
+            }
+        }
+    }
+
+    /// Tests whether `typeof` this JS value is `ty`.
+    ///
+    /// This is equivalent to comparing the result of `js_typeof` against
+    /// `ty`, but doesn't need to create a string in JS.
+    pub fn has_type(&self, ty: &str) -> bool {
+        unsafe { __wbindgen_has_type(self.idx, ty.as_ptr(), ty.len()) == 1 }
+    }
+
+    /// Tests whether this JS value is an instance of `constructor`, as the JS
+    /// `instanceof` operator does.
+    #[cfg(feature = "js_globals")]
+    pub fn instance_of(&self, constructor: &js::Function) -> bool {
+        unsafe { __wbindgen_instanceof(self.idx, constructor.as_ref().idx) == 1 }
+    }
+
+    /// Compares this JS value with `other` using the JS `==` operator.
+    ///
+    /// Unlike `PartialEq`, which uses `===`, this performs type coercions, so
+    /// for example `1` is loosely equal to `"1"` and `null` to `undefined`.
+    pub fn loose_eq(&self, other: &JsValue) -> bool {
+        unsafe { __wbindgen_jsval_loose_eq(self.idx, other.idx) != 0 }
+    }
+
+    /// Compares this JS value with `other` using the JS `<` operator.
+    pub fn lt(&self, other: &JsValue) -> bool {
+        unsafe { __wbindgen_lt(self.idx, other.idx) == 1 }
+    }
+
+    /// Compares this JS value with `other` using the JS `<=` operator.
+    pub fn le(&self, other: &JsValue) -> bool {
+        unsafe { __wbindgen_le(self.idx, other.idx) == 1 }
+    }
+
+    /// Compares this JS value with `other` using the JS `>` operator.
+    pub fn gt(&self, other: &JsValue) -> bool {
+        unsafe { __wbindgen_gt(self.idx, other.idx) == 1 }
+    }
+
+    /// Compares this JS value with `other` using the JS `>=` operator.
+    pub fn ge(&self, other: &JsValue) -> bool {
+        unsafe { __wbindgen_ge(self.idx, other.idx) == 1 }
+    }
+
+    /// Applies the JS binary operator `op` to this value and `other`.
+    ///
+    /// JS operators can throw, for example when mixing a `BigInt` with a
+    /// number, in which case the exception is returned as the error.
+    fn binary_op(
+        &self,
+        other: &JsValue,
+        op: unsafe extern fn(u32, u32, *mut u32) -> u32,
+    ) -> Result<JsValue, JsValue> {
+        unsafe {
+            let mut threw = 0;
+            let idx = op(self.idx, other.idx, &mut threw);
+            if threw == 0 {
+                Ok(JsValue { idx })
+            } else {
+                Err(JsValue { idx })
+            }
+        }
+    }
+
+    /// Computes `self + other` in JS, returning the exception if one is
+    /// thrown.
+    pub fn checked_add(&self, other: &JsValue) -> Result<JsValue, JsValue> {
+        self.binary_op(other, __wbindgen_add)
+    }
+
+    /// Computes `self - other` in JS, returning the exception if one is
+    /// thrown.
+    pub fn checked_sub(&self, other: &JsValue) -> Result<JsValue, JsValue> {
+        self.binary_op(other, __wbindgen_sub)
+    }
+
+    /// Computes `self * other` in JS, returning the exception if one is
+    /// thrown.
+    pub fn checked_mul(&self, other: &JsValue) -> Result<JsValue, JsValue> {
+        self.binary_op(other, __wbindgen_mul)
+    }
+
+    /// Computes `self / other` in JS, returning the exception if one is
+    /// thrown.
+    pub fn checked_div(&self, other: &JsValue) -> Result<JsValue, JsValue> {
+        self.binary_op(other, __wbindgen_div)
+    }
+
+    /// Computes `self % other` in JS, returning the exception if one is
+    /// thrown.
+    pub fn checked_rem(&self, other: &JsValue) -> Result<JsValue, JsValue> {
+        self.binary_op(other, __wbindgen_rem)
+    }
+
+    /// Computes `-self` in JS, returning the exception if one is thrown.
+    pub fn checked_neg(&self) -> Result<JsValue, JsValue> {
+        unsafe {
+            let mut threw = 0;
+            let idx = __wbindgen_neg(self.idx, &mut threw);
+            if threw == 0 {
+                Ok(JsValue { idx })
+            } else {
+                Err(JsValue { idx })
+            }
+        }
+    }
+}
+
+macro_rules! binary_ops {
+    ($(($trait:ident, $method:ident, $checked:ident))*) => ($(
+        impl<'a> ops::$trait<&'a JsValue> for &'a JsValue {
+            type Output = JsValue;
+
+            /// Applies the JS operator, panicking if it throws.
+            fn $method(self, other: &'a JsValue) -> JsValue {
+                match self.$checked(other) {
+                    Ok(v) => v,
+                    Err(e) => panic!(
+                        "JS operator `{}` threw: {:?}",
+                        stringify!($method),
+                        e,
+                    ),
+                }
+            }
+        }
+    )*)
+}
+
+binary_ops! {
+    (Add, add, checked_add)
+    (Sub, sub, checked_sub)
+    (Mul, mul, checked_mul)
+    (Div, div, checked_div)
+    (Rem, rem, checked_rem)
+}
+
+impl<'a> ops::Neg for &'a JsValue {
+    type Output = JsValue;
+
+    /// Applies the JS negation operator, panicking if it throws.
+    fn neg(self) -> JsValue {
+        match self.checked_neg() {
+            Ok(v) => v,
+            Err(e) => panic!("JS operator `neg` threw: {:?}", e),
+        }
+    }
 }
 
 impl PartialEq for JsValue {
@@ -331,6 +517,11 @@ macro_rules! externs {
 externs! {
     fn __wbindgen_object_clone_ref(idx: u32) -> u32;
     fn __wbindgen_object_drop_ref(idx: u32) -> ();
+    fn __wbindgen_object_drop_refs(ptr: *const u32, len: usize) -> ();
+    fn __wbindgen_anyref_table_grow(delta: usize) -> usize;
+    fn __wbindgen_thread_ptr() -> *mut u8;
+    fn __wbindgen_lock(ptr: *mut u32) -> ();
+    fn __wbindgen_unlock(ptr: *mut u32) -> ();
     fn __wbindgen_string_new(ptr: *const u8, len: usize) -> u32;
     fn __wbindgen_number_new(f: f64) -> u32;
     fn __wbindgen_number_get(idx: u32, invalid: *mut u8) -> f64;
@@ -344,6 +535,7 @@ externs! {
     fn __wbindgen_is_symbol(idx: u32) -> u32;
     fn __wbindgen_string_get(idx: u32, len: *mut usize) -> *mut u8;
     fn __wbindgen_throw(a: *const u8, b: usize) -> !;
+    fn __wbindgen_panic(ptr: *const u8, len: usize) -> ();
 
     fn __wbindgen_cb_drop(idx: u32) -> ();
     fn __wbindgen_cb_forget(idx: u32) -> ();
@@ -353,6 +545,45 @@ externs! {
     fn __wbindgen_json_parse(ptr: *const u8, len: usize) -> u32;
     fn __wbindgen_json_serialize(idx: u32, ptr: *mut *mut u8) -> usize;
     fn __wbindgen_jsval_eq(a: u32, b: u32) -> u32;
+    fn __wbindgen_jsval_loose_eq(a: u32, b: u32) -> u32;
+
+    fn __wbindgen_is_object(idx: u32) -> u32;
+    fn __wbindgen_is_function(idx: u32) -> u32;
+    fn __wbindgen_is_string(idx: u32) -> u32;
+    fn __wbindgen_is_array(idx: u32) -> u32;
+    fn __wbindgen_typeof(idx: u32) -> u32;
+    fn __wbindgen_has_type(idx: u32, ptr: *const u8, len: usize) -> u32;
+    fn __wbindgen_instanceof(idx: u32, constructor: u32) -> u32;
+
+    fn __wbindgen_lt(a: u32, b: u32) -> u32;
+    fn __wbindgen_le(a: u32, b: u32) -> u32;
+    fn __wbindgen_gt(a: u32, b: u32) -> u32;
+    fn __wbindgen_ge(a: u32, b: u32) -> u32;
+    fn __wbindgen_add(a: u32, b: u32, threw: *mut u32) -> u32;
+    fn __wbindgen_sub(a: u32, b: u32, threw: *mut u32) -> u32;
+    fn __wbindgen_mul(a: u32, b: u32, threw: *mut u32) -> u32;
+    fn __wbindgen_div(a: u32, b: u32, threw: *mut u32) -> u32;
+    fn __wbindgen_rem(a: u32, b: u32, threw: *mut u32) -> u32;
+    fn __wbindgen_neg(a: u32, threw: *mut u32) -> u32;
+
+    fn __wbindgen_debug_string(idx: u32, len: *mut usize) -> *mut u8;
+
+    fn __wbindgen_object_new() -> u32;
+    fn __wbindgen_object_set(obj: u32, key: u32, value: u32) -> ();
+    fn __wbindgen_object_get(obj: u32, ptr: *const u8, len: usize) -> u32;
+    fn __wbindgen_object_entries(obj: u32) -> u32;
+    fn __wbindgen_array_new() -> u32;
+    fn __wbindgen_array_push(array: u32, value: u32) -> ();
+    fn __wbindgen_array_length(array: u32) -> u32;
+    fn __wbindgen_array_get(array: u32, index: u32) -> u32;
+    fn __wbindgen_map_new() -> u32;
+    fn __wbindgen_map_set(map: u32, key: u32, value: u32) -> ();
+    fn __wbindgen_map_entries(map: u32) -> u32;
+    fn __wbindgen_uint8_array_new(ptr: *const u8, len: usize) -> u32;
+    fn __wbindgen_uint8_array_copy(array: u32, ptr: *mut u8) -> ();
+    fn __wbindgen_bigint_new(ptr: *const u8, len: usize) -> u32;
+    fn __wbindgen_serde_kind(idx: u32) -> u32;
+    fn __wbindgen_to_string(idx: u32, len: *mut usize) -> *mut u8;
 }
 
 impl Clone for JsValue {
@@ -365,16 +596,22 @@ impl Clone for JsValue {
 }
 
 impl fmt::Debug for JsValue {
+    #[cfg(feature = "std")]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = unsafe {
+            let mut len = 0;
+            let ptr = __wbindgen_debug_string(self.idx, &mut len);
+            let data = Vec::from_raw_parts(ptr, len, len);
+            String::from_utf8_unchecked(data)
+        };
+        fmt::Display::fmt(&s, f)
+    }
+
+    #[cfg(not(feature = "std"))]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let Some(n) = self.as_f64() {
             return n.fmt(f)
         }
-        #[cfg(feature = "std")]
-        {
-            if let Some(n) = self.as_string() {
-                return n.fmt(f)
-            }
-        }
         if let Some(n) = self.as_bool() {
             return n.fmt(f)
         }
@@ -397,13 +634,55 @@ impl Drop for JsValue {
             // if the first bit is set then this is a stack value, so we for
             // sure need to drop it. Otherwise if this is one of the special
             // reserved values there's no need to drop it.
-            if (self.idx & 1) == 1 || self.idx >= JSIDX_RESERVED {
+            if (self.idx & 1) == 1 {
                 __wbindgen_object_drop_ref(self.idx);
+            } else if self.idx >= JSIDX_RESERVED {
+                drop_later(self.idx);
             }
         }
     }
 }
 
+// Heap objects are dropped in batches to cut down on the number of calls into
+// JS, which otherwise dominate code creating lots of short-lived `JsValue`s.
+// Until a batch is flushed its objects simply stay alive in JS. Each thread has
+// its own batch, as it's the JS of the thread which owns the objects.
+const DROP_BATCH_CAP: usize = 64;
+
+#[doc(hidden)]
+pub struct DropBatch {
+    idx: [u32; DROP_BATCH_CAP],
+    len: usize,
+}
+
+impl DropBatch {
+    pub const INIT: DropBatch = DropBatch {
+        idx: [0; DROP_BATCH_CAP],
+        len: 0,
+    };
+}
+
+unsafe fn drop_later(idx: u32) {
+    let batch = &mut threads::local().drop_batch;
+    batch.idx[batch.len] = idx;
+    batch.len += 1;
+    if batch.len == DROP_BATCH_CAP {
+        __wbindgen_drop_flush();
+    }
+}
+
+/// Drops all heap objects in the current batch.
+#[doc(hidden)]
+#[no_mangle]
+pub unsafe extern "C" fn __wbindgen_drop_flush() {
+    let batch = &mut threads::local().drop_batch;
+    let len = batch.len;
+    batch.len = 0;
+    if len > 0 {
+        __wbindgen_object_drop_refs(batch.idx.as_ptr(), len);
+    }
+}
+
 /// Wrapper type for imported statics.
 ///
 /// This type is used whenever a `static` is imported from a JS module, for
@@ -465,6 +744,28 @@ impl<T: FromWasmAbi + 'static> Deref for JsStatic<T> {
     }
 }
 
+/// A wrapper for `u8` slices and vectors which are `Uint8ClampedArray`s rather
+/// than `Uint8Array`s in JS, as with the pixels of an `ImageData`.
+///
+/// `Clamped<&[u8]>` and `Clamped<&mut [u8]>` can be passed to JS, and
+/// `Clamped<Vec<u8>>` both ways.
+#[derive(Copy, Clone, PartialEq, Debug, Eq)]
+pub struct Clamped<T>(pub T);
+
+impl<T> Deref for Clamped<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Clamped<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
 /// Throws a JS exception.
 ///
 /// This function will throw a JS exception with the message provided. The
@@ -513,6 +814,32 @@ pub mod __rt {
         super::throw("null pointer passed to rust");
     }
 
+    /// Creates a new empty plain object, used by `#[derive(JsObject)]`.
+    pub fn object_new() -> super::JsValue {
+        unsafe {
+            super::JsValue {
+                idx: super::__wbindgen_object_new(),
+            }
+        }
+    }
+
+    /// Reads the property `key` of `obj`, used by `#[derive(JsObject)]`.
+    pub fn object_get(obj: &super::JsValue, key: &str) -> super::JsValue {
+        unsafe {
+            super::JsValue {
+                idx: super::__wbindgen_object_get(obj.idx, key.as_ptr(), key.len()),
+            }
+        }
+    }
+
+    /// Sets the property `key` of `obj`, used by `#[derive(JsObject)]`.
+    pub fn object_set(obj: &super::JsValue, key: &str, value: super::JsValue) {
+        let key = super::JsValue::from_str(key);
+        unsafe {
+            super::__wbindgen_object_set(obj.idx, key.idx, value.idx);
+        }
+    }
+
     /// A vendored version of `RefCell` from the standard library.
     ///
     /// Now why, you may ask, would we do that? Surely `RefCell` in libstd is
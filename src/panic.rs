@@ -0,0 +1,38 @@
+//! Forwarding of panics to JS.
+//!
+//! A panic in wasm ends in an `unreachable` trap, so all JS sees of it is a
+//! `RuntimeError` without the panic's message. The panic hook installed by
+//! `set_hook` passes the message and location of panics to JS first, which
+//! logs them with `console.error` along with the JS stack, or throws them as an
+//! `Error` with `wasm-bindgen --rethrow-panics`.
+
+use std::panic::{self, PanicInfo};
+use std::prelude::v1::*;
+
+use super::__wbindgen_panic;
+
+/// Installs a panic hook forwarding the message and location of panics to JS,
+/// replacing the current hook.
+///
+/// Panics are only forwarded once this has been called, so it's best called
+/// early on, such as from the first export JS calls:
+///
+/// ```ignore
+/// #[wasm_bindgen]
+/// pub fn init() {
+///     wasm_bindgen::panic::set_hook();
+/// }
+/// ```
+///
+/// Nothing in the module should be called after a panic, as whatever the
+/// panic interrupted is left half done.
+pub fn set_hook() {
+    panic::set_hook(Box::new(hook));
+}
+
+fn hook(info: &PanicInfo) {
+    let msg = info.to_string();
+    unsafe {
+        __wbindgen_panic(msg.as_ptr(), msg.len());
+    }
+}
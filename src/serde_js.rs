@@ -0,0 +1,883 @@
+//! Conversions between Rust values and `JsValue` through serde, without a
+//! JSON round-trip.
+//!
+//! `JsValue::from_serde` and `JsValue::into_serde` go through a JSON string,
+//! which is slow for large payloads and can't represent `undefined`, `Map`,
+//! `Uint8Array` or `BigInt`. The `Serializer` and `Deserializer` here instead
+//! build and inspect JS values directly:
+//!
+//! * structs are plain objects, and sequences and tuples are arrays
+//! * maps are `Map`s, or optionally plain objects
+//! * bytes are a `Uint8Array`, or optionally an array of numbers
+//! * `None` is `undefined`, or optionally `null`
+//! * 64-bit integers outside of the range a JS number can represent exactly
+//!   are a `BigInt`
+//! * enums are externally tagged, like `serde_json` does
+//!
+//! ```ignore
+//! let js = serde_js::to_value(&foo)?;
+//! let foo: Foo = serde_js::from_value(js)?;
+//! ```
+
+use std::error;
+use std::fmt;
+use std::prelude::v1::*;
+
+use serde::de::{self, IntoDeserializer};
+use serde::ser::{self, Serialize};
+
+use JsValue;
+
+// Kinds of JS values returned by `__wbindgen_serde_kind`
+const KIND_UNDEFINED: u32 = 0;
+const KIND_NULL: u32 = 1;
+const KIND_BOOLEAN: u32 = 2;
+const KIND_NUMBER: u32 = 3;
+const KIND_STRING: u32 = 4;
+const KIND_BIGINT: u32 = 5;
+const KIND_ARRAY: u32 = 6;
+const KIND_UINT8_ARRAY: u32 = 7;
+const KIND_MAP: u32 = 8;
+const KIND_OBJECT: u32 = 9;
+
+/// The largest integer a JS number can represent exactly, `2^53 - 1`.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Converts `value` to a `JsValue` with the default `Serializer`.
+pub fn to_value<T>(value: &T) -> Result<JsValue, Error>
+where
+    T: Serialize + ?Sized,
+{
+    Serializer::new().to_value(value)
+}
+
+/// Converts the JS value `value` to a `T`.
+pub fn from_value<T>(value: JsValue) -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+{
+    T::deserialize(Deserializer::from(value))
+}
+
+/// An error converting between Rust values and JS values.
+#[derive(Debug)]
+pub struct Error {
+    msg: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.msg, f)
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        &self.msg
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+/// A serde `Serializer` producing JS values.
+#[derive(Clone, Debug, Default)]
+pub struct Serializer {
+    maps_as_objects: bool,
+    bytes_as_arrays: bool,
+    none_as_null: bool,
+}
+
+impl Serializer {
+    /// Creates a new serializer with the default configuration.
+    pub fn new() -> Serializer {
+        Serializer::default()
+    }
+
+    /// Serialize maps as plain JS objects rather than `Map`s.
+    ///
+    /// Keys of plain objects are always strings, so this is only lossless for
+    /// maps with string keys.
+    pub fn serialize_maps_as_objects(&mut self, maps_as_objects: bool) -> &mut Serializer {
+        self.maps_as_objects = maps_as_objects;
+        self
+    }
+
+    /// Serialize bytes as arrays of numbers rather than a `Uint8Array`.
+    pub fn serialize_bytes_as_arrays(&mut self, bytes_as_arrays: bool) -> &mut Serializer {
+        self.bytes_as_arrays = bytes_as_arrays;
+        self
+    }
+
+    /// Serialize `None` as `null` rather than `undefined`, which is how
+    /// `JsValue::from_serde` represents it.
+    pub fn serialize_none_as_null(&mut self, none_as_null: bool) -> &mut Serializer {
+        self.none_as_null = none_as_null;
+        self
+    }
+
+    /// Converts `value` to a `JsValue` with this serializer's configuration.
+    pub fn to_value<T>(&self, value: &T) -> Result<JsValue, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn integer(&self, n: i128) -> JsValue {
+        if n.abs() as u128 <= MAX_SAFE_INTEGER as u128 {
+            return JsValue::from_f64(n as f64);
+        }
+        let s = n.to_string();
+        unsafe {
+            JsValue {
+                idx: super::__wbindgen_bigint_new(s.as_ptr(), s.len()),
+            }
+        }
+    }
+}
+
+impl<'a> ser::Serializer for &'a Serializer {
+    type Ok = JsValue;
+    type Error = Error;
+
+    type SerializeSeq = ArraySerializer<'a>;
+    type SerializeTuple = ArraySerializer<'a>;
+    type SerializeTupleStruct = ArraySerializer<'a>;
+    type SerializeTupleVariant = VariantSerializer<ArraySerializer<'a>>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = ObjectSerializer<'a>;
+    type SerializeStructVariant = VariantSerializer<ObjectSerializer<'a>>;
+
+    fn serialize_bool(self, v: bool) -> Result<JsValue, Error> {
+        Ok(JsValue::from_bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<JsValue, Error> {
+        Ok(JsValue::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<JsValue, Error> {
+        Ok(JsValue::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<JsValue, Error> {
+        Ok(JsValue::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<JsValue, Error> {
+        Ok(self.integer(v as i128))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<JsValue, Error> {
+        Ok(JsValue::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<JsValue, Error> {
+        Ok(JsValue::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<JsValue, Error> {
+        Ok(JsValue::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<JsValue, Error> {
+        Ok(self.integer(v as i128))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<JsValue, Error> {
+        Ok(JsValue::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<JsValue, Error> {
+        Ok(JsValue::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<JsValue, Error> {
+        let mut buf = [0; 4];
+        Ok(JsValue::from_str(v.encode_utf8(&mut buf)))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<JsValue, Error> {
+        Ok(JsValue::from_str(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<JsValue, Error> {
+        if self.bytes_as_arrays {
+            return v.serialize(self);
+        }
+        unsafe {
+            Ok(JsValue {
+                idx: super::__wbindgen_uint8_array_new(v.as_ptr(), v.len()),
+            })
+        }
+    }
+
+    fn serialize_none(self) -> Result<JsValue, Error> {
+        if self.none_as_null {
+            Ok(JsValue::null())
+        } else {
+            Ok(JsValue::undefined())
+        }
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<JsValue, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<JsValue, Error> {
+        Ok(JsValue::null())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<JsValue, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<JsValue, Error> {
+        Ok(JsValue::from_str(variant))
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<JsValue, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<JsValue, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let value = value.serialize(self)?;
+        Ok(tagged(variant, &value))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<ArraySerializer<'a>, Error> {
+        Ok(ArraySerializer::new(self))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<ArraySerializer<'a>, Error> {
+        Ok(ArraySerializer::new(self))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<ArraySerializer<'a>, Error> {
+        Ok(ArraySerializer::new(self))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<VariantSerializer<ArraySerializer<'a>>, Error> {
+        Ok(VariantSerializer {
+            variant,
+            inner: ArraySerializer::new(self),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'a>, Error> {
+        let map = unsafe {
+            if self.maps_as_objects {
+                super::__wbindgen_object_new()
+            } else {
+                super::__wbindgen_map_new()
+            }
+        };
+        Ok(MapSerializer {
+            ser: self,
+            map: JsValue { idx: map },
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<ObjectSerializer<'a>, Error> {
+        Ok(ObjectSerializer::new(self))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<VariantSerializer<ObjectSerializer<'a>>, Error> {
+        Ok(VariantSerializer {
+            variant,
+            inner: ObjectSerializer::new(self),
+        })
+    }
+}
+
+/// Wraps `value` in an object with the single property `variant`.
+fn tagged(variant: &str, value: &JsValue) -> JsValue {
+    let key = JsValue::from_str(variant);
+    unsafe {
+        let obj = JsValue {
+            idx: super::__wbindgen_object_new(),
+        };
+        super::__wbindgen_object_set(obj.idx, key.idx, value.idx);
+        obj
+    }
+}
+
+/// Serializes sequences, tuples and tuple structs to JS arrays.
+pub struct ArraySerializer<'a> {
+    ser: &'a Serializer,
+    array: JsValue,
+}
+
+impl<'a> ArraySerializer<'a> {
+    fn new(ser: &'a Serializer) -> ArraySerializer<'a> {
+        let array = unsafe {
+            JsValue {
+                idx: super::__wbindgen_array_new(),
+            }
+        };
+        ArraySerializer { ser, array }
+    }
+
+    fn push<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let value = value.serialize(self.ser)?;
+        unsafe {
+            super::__wbindgen_array_push(self.array.idx, value.idx);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeSeq for ArraySerializer<'a> {
+    type Ok = JsValue;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsValue, Error> {
+        Ok(self.array)
+    }
+}
+
+impl<'a> ser::SerializeTuple for ArraySerializer<'a> {
+    type Ok = JsValue;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsValue, Error> {
+        Ok(self.array)
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for ArraySerializer<'a> {
+    type Ok = JsValue;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsValue, Error> {
+        Ok(self.array)
+    }
+}
+
+/// Serializes maps to either a JS `Map` or a plain object.
+pub struct MapSerializer<'a> {
+    ser: &'a Serializer,
+    map: JsValue,
+    key: Option<JsValue>,
+}
+
+impl<'a> ser::SerializeMap for MapSerializer<'a> {
+    type Ok = JsValue;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.key = Some(key.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = match self.key.take() {
+            Some(key) => key,
+            None => return Err(ser::Error::custom("map value serialized before its key")),
+        };
+        let value = value.serialize(self.ser)?;
+        unsafe {
+            if self.ser.maps_as_objects {
+                super::__wbindgen_object_set(self.map.idx, key.idx, value.idx);
+            } else {
+                super::__wbindgen_map_set(self.map.idx, key.idx, value.idx);
+            }
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<JsValue, Error> {
+        Ok(self.map)
+    }
+}
+
+/// Serializes structs to plain JS objects.
+pub struct ObjectSerializer<'a> {
+    ser: &'a Serializer,
+    obj: JsValue,
+}
+
+impl<'a> ObjectSerializer<'a> {
+    fn new(ser: &'a Serializer) -> ObjectSerializer<'a> {
+        let obj = unsafe {
+            JsValue {
+                idx: super::__wbindgen_object_new(),
+            }
+        };
+        ObjectSerializer { ser, obj }
+    }
+
+    fn set<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = JsValue::from_str(key);
+        let value = value.serialize(self.ser)?;
+        unsafe {
+            super::__wbindgen_object_set(self.obj.idx, key.idx, value.idx);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStruct for ObjectSerializer<'a> {
+    type Ok = JsValue;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.set(key, value)
+    }
+
+    fn end(self) -> Result<JsValue, Error> {
+        Ok(self.obj)
+    }
+}
+
+/// Serializes tuple and struct variants, wrapping the array or object
+/// serialized by `inner` in an object tagged with the variant name.
+pub struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<'a> ser::SerializeTupleVariant for VariantSerializer<ArraySerializer<'a>> {
+    type Ok = JsValue;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.inner.push(value)
+    }
+
+    fn end(self) -> Result<JsValue, Error> {
+        Ok(tagged(self.variant, &self.inner.array))
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for VariantSerializer<ObjectSerializer<'a>> {
+    type Ok = JsValue;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.inner.set(key, value)
+    }
+
+    fn end(self) -> Result<JsValue, Error> {
+        Ok(tagged(self.variant, &self.inner.obj))
+    }
+}
+
+/// A serde `Deserializer` reading from a JS value.
+///
+/// Both `Map`s and plain objects deserialize as maps or structs, and both
+/// `Uint8Array`s and arrays of numbers deserialize as bytes. `null` and
+/// `undefined` both deserialize as `None`.
+pub struct Deserializer {
+    value: JsValue,
+}
+
+impl From<JsValue> for Deserializer {
+    fn from(value: JsValue) -> Deserializer {
+        Deserializer { value }
+    }
+}
+
+impl Deserializer {
+    fn kind(&self) -> u32 {
+        unsafe { super::__wbindgen_serde_kind(self.value.idx) }
+    }
+
+    /// `String(value)` in JS.
+    fn to_js_string(&self) -> String {
+        unsafe {
+            let mut len = 0;
+            let ptr = super::__wbindgen_to_string(self.value.idx, &mut len);
+            let data = Vec::from_raw_parts(ptr, len, len);
+            String::from_utf8_unchecked(data)
+        }
+    }
+
+    fn invalid_type(&self, exp: &de::Expected) -> Error {
+        let unexpected = match self.kind() {
+            KIND_UNDEFINED | KIND_NULL => de::Unexpected::Unit,
+            KIND_BOOLEAN => de::Unexpected::Bool(self.value.as_bool().unwrap_or(false)),
+            KIND_NUMBER => de::Unexpected::Float(self.value.as_f64().unwrap_or(0.0)),
+            KIND_ARRAY => de::Unexpected::Seq,
+            KIND_UINT8_ARRAY => de::Unexpected::Other("Uint8Array"),
+            KIND_MAP | KIND_OBJECT => de::Unexpected::Map,
+            _ => de::Unexpected::Other("JS value"),
+        };
+        de::Error::invalid_type(unexpected, exp)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.kind() {
+            KIND_UNDEFINED | KIND_NULL => visitor.visit_unit(),
+            KIND_BOOLEAN => visitor.visit_bool(self.value.as_bool().unwrap()),
+            KIND_NUMBER => {
+                let n = self.value.as_f64().unwrap();
+                // Integral numbers are visited as integers so they can be
+                // deserialized into integer types, like `serde_json` does.
+                if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER as f64 {
+                    if n < 0.0 {
+                        visitor.visit_i64(n as i64)
+                    } else {
+                        visitor.visit_u64(n as u64)
+                    }
+                } else {
+                    visitor.visit_f64(n)
+                }
+            }
+            KIND_STRING => visitor.visit_string(self.value.as_string().unwrap()),
+            KIND_BIGINT => {
+                let s = self.to_js_string();
+                if let Ok(n) = s.parse::<u64>() {
+                    visitor.visit_u64(n)
+                } else if let Ok(n) = s.parse::<i64>() {
+                    visitor.visit_i64(n)
+                } else {
+                    Err(de::Error::custom(format_args!(
+                        "BigInt {} is out of range for a 64-bit integer",
+                        s
+                    )))
+                }
+            }
+            KIND_ARRAY => visitor.visit_seq(SeqDeserializer::new(self.value)),
+            KIND_UINT8_ARRAY => {
+                let bytes = unsafe {
+                    let len = super::__wbindgen_array_length(self.value.idx) as usize;
+                    let mut bytes = Vec::with_capacity(len);
+                    super::__wbindgen_uint8_array_copy(self.value.idx, bytes.as_mut_ptr());
+                    bytes.set_len(len);
+                    bytes
+                };
+                visitor.visit_byte_buf(bytes)
+            }
+            KIND_MAP => {
+                let entries = unsafe { super::__wbindgen_map_entries(self.value.idx) };
+                visitor.visit_map(MapDeserializer::new(JsValue { idx: entries }))
+            }
+            KIND_OBJECT => {
+                let entries = unsafe { super::__wbindgen_object_entries(self.value.idx) };
+                visitor.visit_map(MapDeserializer::new(JsValue { idx: entries }))
+            }
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.kind() {
+            KIND_UNDEFINED | KIND_NULL => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.kind() {
+            KIND_STRING => {
+                let variant: de::value::StringDeserializer<Error> =
+                    self.value.as_string().unwrap().into_deserializer();
+                visitor.visit_enum(variant)
+            }
+            KIND_OBJECT => {
+                let entries = unsafe {
+                    JsValue {
+                        idx: super::__wbindgen_object_entries(self.value.idx),
+                    }
+                };
+                let mut entries = MapDeserializer::new(entries);
+                if entries.len != 1 {
+                    return Err(de::Error::invalid_length(
+                        entries.len as usize,
+                        &"an object with a single property",
+                    ));
+                }
+                let (variant, value) = entries.next_entry().unwrap();
+                visitor.visit_enum(EnumDeserializer {
+                    variant: Deserializer::from(variant),
+                    value: Deserializer::from(value),
+                })
+            }
+            _ => Err(self.invalid_type(&"a string or an object with a single property")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+/// Deserializes the elements of a JS array.
+struct SeqDeserializer {
+    array: JsValue,
+    index: u32,
+    len: u32,
+}
+
+impl SeqDeserializer {
+    fn new(array: JsValue) -> SeqDeserializer {
+        let len = unsafe { super::__wbindgen_array_length(array.idx) };
+        SeqDeserializer {
+            array,
+            index: 0,
+            len,
+        }
+    }
+
+    fn next(&mut self) -> Option<JsValue> {
+        if self.index == self.len {
+            return None;
+        }
+        let idx = unsafe { super::__wbindgen_array_get(self.array.idx, self.index) };
+        self.index += 1;
+        Some(JsValue { idx })
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match self.next() {
+            Some(value) => seed.deserialize(Deserializer::from(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some((self.len - self.index) as usize)
+    }
+}
+
+/// Deserializes an array of `[key, value]` entries, as returned by
+/// `Object.entries` or `Map.prototype.entries`.
+struct MapDeserializer {
+    entries: SeqDeserializer,
+    len: u32,
+    value: Option<JsValue>,
+}
+
+impl MapDeserializer {
+    fn new(entries: JsValue) -> MapDeserializer {
+        let entries = SeqDeserializer::new(entries);
+        MapDeserializer {
+            len: entries.len,
+            entries,
+            value: None,
+        }
+    }
+
+    fn next_entry(&mut self) -> Option<(JsValue, JsValue)> {
+        self.entries.next().map(|entry| unsafe {
+            let key = super::__wbindgen_array_get(entry.idx, 0);
+            let value = super::__wbindgen_array_get(entry.idx, 1);
+            (JsValue { idx: key }, JsValue { idx: value })
+        })
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.next_entry() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Deserializer::from(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(Deserializer::from(value)),
+            None => Err(de::Error::custom("map value deserialized before its key")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some((self.entries.len - self.entries.index) as usize)
+    }
+}
+
+/// Deserializes an externally tagged enum variant, `{ variant: value }`.
+struct EnumDeserializer {
+    variant: Deserializer,
+    value: Deserializer,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = Deserializer;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Deserializer), Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.variant)?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
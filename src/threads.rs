@@ -0,0 +1,178 @@
+//! Support for running on several threads, for `wasm-bindgen --threads`.
+//!
+//! In that mode the module is instantiated once per web worker with all the
+//! instances sharing one memory, so statics are shared between threads too.
+//! The buffers used to pass values between JS and Rust belong to the JS of one
+//! particular thread though, so each thread has its own `Local` instead. The
+//! main thread uses a static one, while the generated JS allocates those of
+//! other threads, along with their stacks, with `__wbindgen_thread_alloc`.
+//!
+//! The allocator needs to be protected from concurrent use as well, which is
+//! what `LockedAllocator` is for.
+//!
+//! Each thread also gets its own block of thread-local storage, so values of
+//! `thread_local!` aren't shared between threads. That requires the `atomics`
+//! target feature, without which `thread_local!` compiles to ordinary statics.
+
+use convert::GlobalBuffers;
+use {DropBatch, __wbindgen_thread_ptr};
+
+/// The state of the runtime which is local to each thread.
+#[doc(hidden)]
+#[repr(C)]
+pub struct Local {
+    // The generated JS reads these three when starting a thread, so they must
+    // come first.
+    stack: *mut u8,
+    stack_size: usize,
+    tls: *mut u8,
+    tls_size: usize,
+    tls_align: usize,
+    pub(crate) global_buffers: GlobalBuffers,
+    pub(crate) drop_batch: DropBatch,
+}
+
+static mut MAIN: Local = Local {
+    stack: 0 as *mut u8,
+    stack_size: 0,
+    tls: 0 as *mut u8,
+    tls_size: 0,
+    tls_align: 1,
+    global_buffers: GlobalBuffers::INIT,
+    drop_batch: DropBatch::INIT,
+};
+
+/// Returns the `Local` of the current thread.
+///
+/// The pointer to it is kept in a wasm global, which unlike memory isn't
+/// shared between instances. `__wbindgen_thread_ptr` is implemented in wasm by
+/// `wasm-bindgen` to read it, and always returns null without `--threads`.
+pub(crate) unsafe fn local() -> &'static mut Local {
+    let ptr = __wbindgen_thread_ptr() as *mut Local;
+    if ptr.is_null() {
+        &mut MAIN
+    } else {
+        &mut *ptr
+    }
+}
+
+if_std! {
+    use std::alloc::{self, GlobalAlloc, Layout};
+    use std::prelude::v1::*;
+
+    const STACK_ALIGN: usize = 16;
+
+    /// Allocates the `Local`, the stack and the TLS block of a new thread,
+    /// which is called by the thread starting it as nothing may run on the new
+    /// thread before it has a stack.
+    ///
+    /// The stack is zeroed, as the TLS block is initialized from the part of it
+    /// which the data segments are copied to, and that only covers the
+    /// thread-locals which don't start out zeroed.
+    #[doc(hidden)]
+    #[no_mangle]
+    pub unsafe extern "C" fn __wbindgen_thread_alloc(
+        stack_size: usize,
+        tls_size: usize,
+        tls_align: usize,
+    ) -> *mut Local {
+        let stack_size = (stack_size + STACK_ALIGN - 1) & !(STACK_ALIGN - 1);
+        let layout = Layout::from_size_align_unchecked(stack_size, STACK_ALIGN);
+        let stack = alloc::alloc_zeroed(layout);
+        if stack.is_null() {
+            ::throw("failed to allocate the stack of a new thread");
+        }
+        let tls = if tls_size == 0 {
+            0 as *mut u8
+        } else {
+            let tls = alloc::alloc(Layout::from_size_align_unchecked(tls_size, tls_align));
+            if tls.is_null() {
+                ::throw("failed to allocate the thread-local storage of a new thread");
+            }
+            tls
+        };
+        Box::into_raw(Box::new(Local {
+            stack,
+            stack_size,
+            tls,
+            tls_size,
+            tls_align,
+            global_buffers: GlobalBuffers::INIT,
+            drop_batch: DropBatch::INIT,
+        }))
+    }
+
+    /// Frees a thread allocated with `__wbindgen_thread_alloc`, which must no
+    /// longer be running any Rust code.
+    #[doc(hidden)]
+    #[no_mangle]
+    pub unsafe extern "C" fn __wbindgen_thread_free(local: *mut Local) {
+        let mut local = Box::from_raw(local);
+        local.global_buffers.free();
+        let layout = Layout::from_size_align_unchecked(local.stack_size, STACK_ALIGN);
+        alloc::dealloc(local.stack, layout);
+        if !local.tls.is_null() {
+            let layout = Layout::from_size_align_unchecked(local.tls_size, local.tls_align);
+            alloc::dealloc(local.tls, layout);
+        }
+    }
+
+    /// A global allocator which can be used from several threads at once by
+    /// only letting one of them use the allocator `A` at a time.
+    ///
+    /// The allocator of the standard library isn't thread safe on wasm, so a
+    /// program using `wasm-bindgen --threads` needs to wrap it with this:
+    ///
+    /// ```ignore
+    /// use std::alloc::System;
+    /// use wasm_bindgen::threads::LockedAllocator;
+    ///
+    /// #[global_allocator]
+    /// static ALLOC: LockedAllocator<System> = LockedAllocator(System);
+    /// ```
+    ///
+    /// The lock is implemented in JS with `Atomics` on the shared memory,
+    /// without `--threads` it does nothing.
+    pub struct LockedAllocator<A>(pub A);
+
+    static mut ALLOC_LOCK: u32 = 0;
+
+    struct Guard;
+
+    impl Guard {
+        unsafe fn lock() -> Guard {
+            ::__wbindgen_lock(&mut ALLOC_LOCK);
+            Guard
+        }
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            unsafe {
+                ::__wbindgen_unlock(&mut ALLOC_LOCK);
+            }
+        }
+    }
+
+    unsafe impl<A: GlobalAlloc> GlobalAlloc for LockedAllocator<A> {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _guard = Guard::lock();
+            self.0.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            let _guard = Guard::lock();
+            self.0.dealloc(ptr, layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let _guard = Guard::lock();
+            self.0.alloc_zeroed(layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let _guard = Guard::lock();
+            self.0.realloc(ptr, layout, new_size)
+        }
+    }
+}
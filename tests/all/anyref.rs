@@ -0,0 +1,96 @@
+use super::project;
+
+#[test]
+fn works() {
+    project()
+        .anyref(true)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen(module = "./test")]
+                extern {
+                    fn take(a: JsValue);
+                    fn borrow(a: &JsValue);
+                    fn create() -> JsValue;
+                    fn call(f: &Fn(JsValue, JsValue) -> JsValue) -> JsValue;
+                }
+
+                #[wasm_bindgen]
+                pub fn round_trip(a: JsValue) -> JsValue {
+                    a
+                }
+
+                #[wasm_bindgen]
+                pub fn pass_through(a: &JsValue, b: JsValue) -> JsValue {
+                    borrow(a);
+                    take(b);
+                    create()
+                }
+
+                #[wasm_bindgen]
+                pub fn reserved() -> JsValue {
+                    borrow(&JsValue::null());
+                    borrow(&JsValue::undefined());
+                    take(JsValue::from(true));
+                    take(JsValue::from(false));
+                    JsValue::undefined()
+                }
+
+                #[wasm_bindgen]
+                pub fn many(a: &JsValue) -> u32 {
+                    let clones = (0..100).map(|_| a.clone()).collect::<Vec<_>>();
+                    clones.iter().filter(|c| **c == *a).count() as u32
+                }
+
+                #[wasm_bindgen]
+                pub fn closure(a: &JsValue) -> JsValue {
+                    call(&|b, c| {
+                        assert_eq!(&c, a);
+                        b
+                    })
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as wasm from "./out";
+                import * as assert from "assert";
+
+                const sym = Symbol('test');
+                let taken = [];
+                let borrowed = [];
+
+                export function take(a) { taken.push(a); }
+                export function borrow(a) { borrowed.push(a); }
+                export function create() { return sym; }
+                export function call(f) { return f(borrowed, sym); }
+
+                export function test() {
+                    const obj = {};
+                    assert.strictEqual(wasm.round_trip(obj), obj);
+                    assert.strictEqual(wasm.round_trip(sym), sym);
+
+                    assert.strictEqual(wasm.pass_through(obj, 'b'), sym);
+                    assert.deepStrictEqual(borrowed, [obj]);
+                    assert.deepStrictEqual(taken, ['b']);
+
+                    taken = [];
+                    borrowed = [];
+                    assert.strictEqual(wasm.reserved(), undefined);
+                    assert.deepStrictEqual(borrowed, [null, undefined]);
+                    assert.deepStrictEqual(taken, [true, false]);
+
+                    assert.strictEqual(wasm.many(obj), 100);
+                    assert.strictEqual(wasm.closure(sym), borrowed);
+                }
+            "#,
+        )
+        .test();
+}
@@ -85,7 +85,7 @@ fn works() {
                 pub fn mk_symbol() -> JsValue {
                     let a = JsValue::symbol(None);
                     assert!(a.is_symbol());
-                    assert_eq!(format!("{:?}", a), "Symbol(..)");
+                    assert_eq!(format!("{:?}", a), "Symbol()");
                     return a
                 }
 
@@ -194,3 +194,135 @@ fn eq_works() {
         )
         .test();
 }
+
+#[test]
+fn inspection_and_operators() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+            #![feature(proc_macro, wasm_custom_section)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+            use wasm_bindgen::js;
+
+            #[wasm_bindgen]
+            pub fn kinds(a: &JsValue) -> String {
+                let mut s = String::new();
+                if a.is_object() { s.push('o'); }
+                if a.is_function() { s.push('f'); }
+                if a.is_string() { s.push('s'); }
+                if a.is_array() { s.push('a'); }
+                s
+            }
+
+            #[wasm_bindgen]
+            pub fn js_typeof(a: &JsValue) -> JsValue {
+                a.js_typeof()
+            }
+
+            #[wasm_bindgen]
+            pub fn has_type(a: &JsValue, ty: &str) -> bool {
+                a.has_type(ty)
+            }
+
+            #[wasm_bindgen]
+            pub fn instance_of(a: &JsValue, b: &js::Function) -> bool {
+                a.instance_of(b)
+            }
+
+            #[wasm_bindgen]
+            pub fn loose_eq(a: &JsValue, b: &JsValue) -> bool {
+                a.loose_eq(b)
+            }
+
+            #[wasm_bindgen]
+            pub fn compare(a: &JsValue, b: &JsValue) -> String {
+                format!("{}{}{}{}", a.lt(b) as u8, a.le(b) as u8, a.gt(b) as u8, a.ge(b) as u8)
+            }
+
+            #[wasm_bindgen]
+            pub fn arithmetic(a: &JsValue, b: &JsValue, op: &str) -> JsValue {
+                match op {
+                    "+" => a + b,
+                    "-" => a - b,
+                    "*" => a * b,
+                    "/" => a / b,
+                    "%" => a % b,
+                    _ => -a,
+                }
+            }
+
+            #[wasm_bindgen]
+            pub fn checked_add(a: &JsValue, b: &JsValue) -> JsValue {
+                match a.checked_add(b) {
+                    Ok(_) => JsValue::null(),
+                    Err(e) => e,
+                }
+            }
+
+            #[wasm_bindgen]
+            pub fn debug(a: &JsValue) -> String {
+                format!("{:?}", a)
+            }
+        "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as assert from "assert";
+                import * as wasm from "./out";
+
+                export function test() {
+                    assert.strictEqual(wasm.kinds({}), 'o');
+                    assert.strictEqual(wasm.kinds(null), '');
+                    assert.strictEqual(wasm.kinds(() => {}), 'f');
+                    assert.strictEqual(wasm.kinds('x'), 's');
+                    assert.strictEqual(wasm.kinds([]), 'oa');
+
+                    assert.strictEqual(wasm.js_typeof(1), 'number');
+                    assert.strictEqual(wasm.js_typeof(null), 'object');
+                    assert.strictEqual(wasm.js_typeof(undefined), 'undefined');
+                    assert.strictEqual(wasm.has_type(1, 'number'), true);
+                    assert.strictEqual(wasm.has_type('1', 'number'), false);
+
+                    assert.strictEqual(wasm.instance_of([], Array), true);
+                    assert.strictEqual(wasm.instance_of({}, Array), false);
+                    assert.strictEqual(wasm.instance_of(new TypeError(), Error), true);
+
+                    assert.strictEqual(wasm.loose_eq(1, '1'), true);
+                    assert.strictEqual(wasm.loose_eq(null, undefined), true);
+                    assert.strictEqual(wasm.loose_eq(1, 2), false);
+
+                    assert.strictEqual(wasm.compare(1, 2), '1100');
+                    assert.strictEqual(wasm.compare(2, 2), '0101');
+                    assert.strictEqual(wasm.compare('b', 'a'), '0011');
+
+                    assert.strictEqual(wasm.arithmetic(7, 2, '+'), 9);
+                    assert.strictEqual(wasm.arithmetic(7, 2, '-'), 5);
+                    assert.strictEqual(wasm.arithmetic(7, 2, '*'), 14);
+                    assert.strictEqual(wasm.arithmetic(7, 2, '/'), 3.5);
+                    assert.strictEqual(wasm.arithmetic(7, 2, '%'), 1);
+                    assert.strictEqual(wasm.arithmetic(7, 2, 'neg'), -7);
+                    assert.strictEqual(wasm.arithmetic('a', 'b', '+'), 'ab');
+
+                    assert.strictEqual(wasm.checked_add(1, 2), null);
+                    assert.ok(wasm.checked_add(Symbol(), 1) instanceof TypeError);
+
+                    assert.strictEqual(wasm.debug(1.5), '1.5');
+                    assert.strictEqual(wasm.debug('a'), '"a"');
+                    assert.strictEqual(wasm.debug(null), 'null');
+                    assert.strictEqual(wasm.debug(undefined), 'undefined');
+                    assert.strictEqual(wasm.debug(Symbol('x')), 'Symbol(x)');
+                    assert.strictEqual(wasm.debug({a: [1, 'b']}), '{"a":[1,"b"]}');
+                    assert.strictEqual(wasm.debug(function foo() {}), 'Function(foo)');
+                    const cyclic = {};
+                    cyclic.self = cyclic;
+                    assert.strictEqual(wasm.debug(cyclic), '[object Object]');
+                }
+            "#,
+        )
+        .test();
+}
@@ -709,3 +709,96 @@ fn double_consume() {
         "#)
         .test();
 }
+
+#[test]
+fn weak_refs() {
+    project()
+        .weak_refs(true)
+        .node_arg("--expose-gc")
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                static mut LIVE: u32 = 0;
+
+                #[wasm_bindgen]
+                pub fn live() -> u32 {
+                    unsafe { LIVE }
+                }
+
+                #[wasm_bindgen]
+                pub struct Counter {
+                    count: u32,
+                }
+
+                #[wasm_bindgen]
+                impl Counter {
+                    #[wasm_bindgen(constructor)]
+                    pub fn new(count: u32) -> Counter {
+                        unsafe {
+                            LIVE += 1;
+                        }
+                        Counter { count }
+                    }
+
+                    pub fn take(self) -> u32 {
+                        self.count
+                    }
+                }
+
+                impl Drop for Counter {
+                    fn drop(&mut self) {
+                        unsafe {
+                            LIVE -= 1;
+                        }
+                    }
+                }
+
+                #[wasm_bindgen]
+                pub fn take_counter(c: Counter) -> u32 {
+                    c.count
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as assert from "assert";
+                import { Counter, live, take_counter } from "./out";
+
+                function forget() {
+                    for (let i = 0; i < 10; i++)
+                        new Counter(i);
+                }
+
+                export async function test() {
+                    // Manually freeing and moving into Rust still work, and
+                    // collecting those instances later doesn't free again.
+                    const a = new Counter(1);
+                    const b = new Counter(2);
+                    const c = new Counter(3);
+                    a.free();
+                    assert.strictEqual(b.take(), 2);
+                    assert.strictEqual(take_counter(c), 3);
+                    assert.strictEqual(live(), 0);
+
+                    forget();
+                    assert.strictEqual(live(), 10);
+                    if (typeof FinalizationRegistry === 'undefined')
+                        return;
+
+                    for (let i = 0; i < 100 && live() > 0; i++) {
+                        global.gc();
+                        await new Promise(resolve => setTimeout(resolve, 10));
+                    }
+                    assert.strictEqual(live(), 0);
+                }
+            "#,
+        )
+        .test();
+}
@@ -571,3 +571,81 @@ fn string_ret() {
         )
         .test();
 }
+
+#[test]
+fn many_arguments() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen(module = "./test")]
+                extern {
+                    fn call_all(
+                        a0: &Fn(u32) -> u32, a1: &Fn(u32) -> u32, a2: &Fn(u32) -> u32, a3: &Fn(u32) -> u32,
+                        a4: &Fn(u32) -> u32, a5: &Fn(u32) -> u32, a6: &Fn(u32) -> u32, a7: &Fn(u32) -> u32,
+                        a8: &Fn(u32) -> u32, a9: &Fn(u32) -> u32, a10: &Fn(u32) -> u32, a11: &Fn(u32) -> u32,
+                        a12: &Fn(u32) -> u32, a13: &Fn(u32) -> u32, a14: &Fn(u32) -> u32, a15: &Fn(u32) -> u32,
+                        c0: &Closure<Fn(u32) -> u32>, c1: &Closure<Fn(u32) -> u32>,
+                        c2: &Closure<Fn(u32) -> u32>, c3: &Closure<Fn(u32) -> u32>,
+                    ) -> u32;
+                }
+
+                #[wasm_bindgen]
+                pub fn run() {
+                    let add = |k: u32| move |x: u32| x + k;
+                    let c = (0..4)
+                        .map(|k| Closure::new(move |x: u32| x * 100 + k))
+                        .collect::<Vec<Closure<Fn(u32) -> u32>>>();
+                    let sum = call_all(
+                        &add(0), &add(1), &add(2), &add(3),
+                        &add(4), &add(5), &add(6), &add(7),
+                        &add(8), &add(9), &add(10), &add(11),
+                        &add(12), &add(13), &add(14), &add(15),
+                        &c[0], &c[1], &c[2], &c[3],
+                    );
+                    assert_eq!(sum, 240 + 7006);
+
+                    // Calls made from within the closures above use the global
+                    // argument area as well.
+                    let nested = |x: u32| {
+                        x + call_all(
+                            &add(0), &add(1), &add(2), &add(3),
+                            &add(4), &add(5), &add(6), &add(7),
+                            &add(8), &add(9), &add(10), &add(11),
+                            &add(12), &add(13), &add(14), &add(15),
+                            &c[0], &c[1], &c[2], &c[3],
+                        )
+                    };
+                    let sum = call_all(
+                        &nested, &add(1), &add(2), &add(3),
+                        &add(4), &add(5), &add(6), &add(7),
+                        &add(8), &add(9), &add(10), &add(11),
+                        &add(12), &add(13), &add(14), &add(15),
+                        &c[0], &c[1], &c[2], &c[3],
+                    );
+                    assert_eq!(sum, 2 * (240 + 7006));
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import { run } from "./out";
+
+                export function call_all(...fs) {
+                    return fs.reduce((sum, f, i) => sum + f(i), 0);
+                }
+
+                export function test() {
+                    run();
+                }
+            "#,
+        )
+        .test();
+}
@@ -0,0 +1,183 @@
+use std::process::Command;
+
+use super::{project, run};
+
+// The generated JS for `--target deno` is compared against these snapshots of
+// the module header and of the code which loads and instantiates the wasm, and
+// `runs_under_deno` checks that it works when `deno` is installed.
+const HEADER: &str = include_str!("deno/header.js");
+const LOADER: &str = include_str!("deno/loader.js");
+
+#[test]
+fn snapshot() {
+    let mut p = project();
+    p.deno(true).debug(false).file(
+        "src/lib.rs",
+        r#"
+            #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            #[wasm_bindgen(module = "https://example.com/log.js")]
+            extern {
+                fn log(s: &str);
+            }
+
+            #[wasm_bindgen]
+            pub fn greet(name: &str) {
+                log(&format!("Hello, {}!", name));
+            }
+        "#,
+    );
+
+    p.gen_bindings();
+    let js = p.read_js();
+    assert!(
+        js.trim_left().starts_with(HEADER),
+        "header doesn't match snapshot:\n{}",
+        js
+    );
+    assert!(
+        js.ends_with(LOADER),
+        "loader doesn't match snapshot:\n{}",
+        js
+    );
+    assert!(js.contains("__exports.greet = greet;"));
+    assert!(!js.contains("require("));
+}
+
+#[test]
+fn runs_under_deno() {
+    if Command::new("deno").arg("--version").output().is_err() {
+        println!("skipping, `deno` isn't installed");
+        return;
+    }
+
+    let mut p = project();
+    p.deno(true)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen(module = "./log.js")]
+                extern {
+                    fn log(s: &str);
+                }
+
+                #[wasm_bindgen]
+                pub fn greet(name: &str) -> u32 {
+                    log(&format!("Hello, {}!", name));
+                    name.len() as u32
+                }
+            "#,
+        )
+        .file(
+            "log.js",
+            r#"
+                export function log(s) {
+                    globalThis.logged.push(s);
+                }
+            "#,
+        )
+        .file(
+            "main.js",
+            r#"
+                globalThis.logged = [];
+
+                function assertEquals(a, b) {
+                    if (JSON.stringify(a) !== JSON.stringify(b))
+                        throw new Error(`${JSON.stringify(a)} !== ${JSON.stringify(b)}`);
+                }
+
+                // Loaded from the file system, the wasm is read with
+                // `Deno.readFile`
+                const local = await import('./out.js');
+                assertEquals(local.greet('file'), 4);
+
+                // Loaded over HTTP, it's fetched instead
+                const server = Deno.serve({ hostname: '127.0.0.1', port: 0 }, async req => {
+                    const path = new URL(req.url).pathname;
+                    const body = await Deno.readFile(new URL('.' + path, import.meta.url));
+                    const type = path.endsWith('.wasm')
+                        ? 'application/wasm'
+                        : 'application/javascript';
+                    return new Response(body, { headers: { 'Content-Type': type } });
+                });
+                const remote = await import(`http://127.0.0.1:${server.addr.port}/out.js`);
+                assertEquals(remote.greet('http'), 4);
+                await server.shutdown();
+
+                assertEquals(globalThis.logged, ['Hello, file!', 'Hello, http!']);
+            "#,
+        );
+
+    let (root, _) = p.gen_bindings();
+    let mut cmd = Command::new("deno");
+    cmd.arg("run").arg("-A").arg("main.js").current_dir(&root);
+    run(&mut cmd, "deno");
+}
+
+#[test]
+#[should_panic(expected = "not allowed with `--target deno`")]
+fn bare_module_imports_rejected() {
+    let mut p = project();
+    p.deno(true).file(
+        "src/lib.rs",
+        r#"
+            #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            #[wasm_bindgen(module = "log")]
+            extern {
+                fn log(s: &str);
+            }
+
+            #[wasm_bindgen]
+            pub fn greet(name: &str) {
+                log(name);
+            }
+        "#,
+    );
+
+    // Deno can't resolve `log` without an import map
+    p.gen_bindings();
+}
+
+#[test]
+#[should_panic(expected = "relative paths must include the file extension")]
+fn extensionless_imports_rejected() {
+    let mut p = project();
+    p.deno(true).file(
+        "src/lib.rs",
+        r#"
+            #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            #[wasm_bindgen(module = "./log")]
+            extern {
+                fn log(s: &str);
+            }
+
+            #[wasm_bindgen]
+            pub fn greet(name: &str) {
+                log(name);
+            }
+        "#,
+    );
+
+    // Deno resolves `./log` to a file without an extension
+    p.gen_bindings();
+}
@@ -0,0 +1,5 @@
+/* tslint:disable */
+import { log } from 'https://example.com/log.js';
+
+let wasm;
+const __exports = {};
@@ -0,0 +1,7 @@
+const file = new URL('./out_bg.wasm', import.meta.url);
+const bytes = file.protocol === 'file:'
+    ? await Deno.readFile(file)
+    : new Uint8Array(await (await fetch(file)).arrayBuffer());
+const wasmModule = new WebAssembly.Module(bytes);
+const wasmInstance = new WebAssembly.Instance(wasmModule, { './out': __exports });
+wasm = wasmInstance.exports;
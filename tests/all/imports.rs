@@ -1,3 +1,5 @@
+use std::fs;
+
 use super::project;
 
 #[test]
@@ -491,6 +493,135 @@ fn versions() {
         .test();
 }
 
+#[test]
+fn package_json() {
+    project()
+        .debug(false)
+        .package_json(true)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen(module = "webpack", version = "^0.2.0")]
+                extern {
+                    fn foo();
+                }
+
+                #[wasm_bindgen]
+                pub fn run() {
+                    foo();
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as fs from 'fs';
+                import * as assert from 'assert';
+
+                export function test() {
+                    const pkg = JSON.parse(fs.readFileSync('package.json'));
+                    assert.deepStrictEqual(pkg, {
+                        name: 'out',
+                        version: '0.1.0',
+                        files: ['out.mjs', 'out_bg.wasm', 'out_bg.mjs'],
+                        main: 'out.mjs',
+                        dependencies: {
+                            webpack: '^0.2.0',
+                        },
+                    });
+                };
+            "#,
+        )
+        .test();
+}
+
+#[test]
+#[should_panic(expected = "conflicting versions of npm package")]
+fn package_json_conflicting_versions() {
+    project()
+        .package_json(true)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen(module = "webpack", version = "^0.2.0")]
+                extern {
+                    fn foo();
+                }
+
+                #[wasm_bindgen(module = "webpack/lib/bar", version = "^0.3.0")]
+                extern {
+                    fn bar();
+                }
+
+                #[wasm_bindgen]
+                pub fn run() {
+                    foo();
+                    bar();
+                }
+            "#,
+        )
+        .gen_bindings();
+}
+
+#[test]
+fn package_json_bundler_scoped_packages() {
+    let mut p = project();
+    p.webpack(true).package_json(true).file(
+        "src/lib.rs",
+        r#"
+            #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            #[wasm_bindgen(module = "@scope/pkg", version = "^1.0.0")]
+            extern {
+                fn foo();
+            }
+
+            #[wasm_bindgen(module = "@scope/pkg/sub", version = "^1.0.0")]
+            extern {
+                fn bar();
+            }
+
+            #[wasm_bindgen(module = "@scope/other/a/b", version = "^2.0.0")]
+            extern {
+                fn baz();
+            }
+
+            #[wasm_bindgen]
+            pub fn run() {
+                foo();
+                bar();
+                baz();
+            }
+        "#,
+    );
+
+    let (root, _) = p.gen_bindings();
+    let pkg = fs::read_to_string(root.join("package.json")).unwrap();
+    assert!(pkg.contains(r#""main": "out.js""#));
+    assert!(pkg.contains(r#""module": "out.js""#));
+    assert!(pkg.contains(r#""sideEffects": false"#));
+    assert!(pkg.contains(r#""@scope/pkg": "^1.0.0""#));
+    assert!(pkg.contains(r#""@scope/other": "^2.0.0""#));
+    assert!(!pkg.contains("@scope/pkg/sub"));
+    assert!(!pkg.contains("@scope/other/a"));
+}
+
 #[test]
 fn underscore_pattern() {
     project()
@@ -0,0 +1,105 @@
+#![allow(non_snake_case)]
+
+use super::project;
+
+#[test]
+fn new() {
+    project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro, wasm_custom_section)]
+
+            extern crate wasm_bindgen;
+            use wasm_bindgen::prelude::*;
+            use wasm_bindgen::js::{ArrayBuffer, DataView};
+
+            #[wasm_bindgen]
+            pub fn new_dataview(buffer: &ArrayBuffer, offset: u32, length: u32) -> DataView {
+                DataView::new(buffer, offset, length)
+            }
+        "#)
+        .file("test.js", r#"
+            import * as assert from "assert";
+            import * as wasm from "./out";
+
+            export function test() {
+                const buffer = new ArrayBuffer(8);
+                const view = wasm.new_dataview(buffer, 2, 4);
+                assert.ok(view instanceof DataView);
+                assert.strictEqual(view.buffer, buffer);
+                assert.equal(view.byteOffset, 2);
+                assert.equal(view.byteLength, 4);
+            }
+        "#)
+        .test()
+}
+
+#[test]
+fn accessors() {
+    project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro, wasm_custom_section)]
+
+            extern crate wasm_bindgen;
+            use wasm_bindgen::prelude::*;
+            use wasm_bindgen::js::DataView;
+
+            #[wasm_bindgen]
+            pub fn byte_offset(view: &DataView) -> u32 {
+                view.byte_offset()
+            }
+
+            #[wasm_bindgen]
+            pub fn byte_length(view: &DataView) -> u32 {
+                view.byte_length()
+            }
+
+            #[wasm_bindgen]
+            pub fn buffer_is(view: &DataView, other: &JsValue) -> bool {
+                JsValue::from(view.buffer()) == *other
+            }
+        "#)
+        .file("test.js", r#"
+            import * as assert from "assert";
+            import * as wasm from "./out";
+
+            export function test() {
+                const buffer = new ArrayBuffer(8);
+                const view = new DataView(buffer, 1, 3);
+                assert.equal(wasm.byte_offset(view), 1);
+                assert.equal(wasm.byte_length(view), 3);
+                assert.ok(wasm.buffer_is(view, buffer));
+            }
+        "#)
+        .test()
+}
+
+#[test]
+fn get_and_set_uint8() {
+    project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro, wasm_custom_section)]
+
+            extern crate wasm_bindgen;
+            use wasm_bindgen::prelude::*;
+            use wasm_bindgen::js::DataView;
+
+            #[wasm_bindgen]
+            pub fn swap_first_two(view: &DataView) {
+                let a = view.get_uint8(0);
+                let b = view.get_uint8(1);
+                view.set_uint8(0, b);
+                view.set_uint8(1, a);
+            }
+        "#)
+        .file("test.js", r#"
+            import * as assert from "assert";
+            import * as wasm from "./out";
+
+            export function test() {
+                const bytes = new Uint8Array([1, 2, 3]);
+                wasm.swap_first_two(new DataView(bytes.buffer));
+                assert.deepStrictEqual(Array.from(bytes), [2, 1, 3]);
+            }
+        "#)
+        .test()
+}
@@ -0,0 +1,96 @@
+#![allow(non_snake_case)]
+
+use super::project;
+
+#[test]
+fn resolve() {
+    project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro, wasm_custom_section)]
+
+            extern crate wasm_bindgen;
+            use wasm_bindgen::prelude::*;
+            use wasm_bindgen::js::Promise;
+
+            #[wasm_bindgen]
+            pub fn resolve(value: &JsValue) -> Promise {
+                Promise::resolve(value)
+            }
+        "#)
+        .file("test.js", r#"
+            import * as assert from "assert";
+            import * as wasm from "./out";
+
+            export function test() {
+                assert.ok(wasm.resolve(42) instanceof Promise);
+            }
+        "#)
+        .test()
+}
+
+#[test]
+fn reject() {
+    project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro, wasm_custom_section)]
+
+            extern crate wasm_bindgen;
+            use wasm_bindgen::prelude::*;
+            use wasm_bindgen::js::Promise;
+
+            #[wasm_bindgen]
+            pub fn reject(reason: &JsValue) -> Promise {
+                Promise::reject(reason)
+            }
+        "#)
+        .file("test.js", r#"
+            import * as assert from "assert";
+            import * as wasm from "./out";
+
+            export function test() {
+                const promise = wasm.reject("nope");
+                assert.ok(promise instanceof Promise);
+                promise.catch(() => {});
+            }
+        "#)
+        .test()
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn then_and_catch() {
+    project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro, wasm_custom_section)]
+
+            extern crate wasm_bindgen;
+            use wasm_bindgen::prelude::*;
+            use wasm_bindgen::js::Promise;
+
+            #[wasm_bindgen]
+            pub fn then(promise: &Promise) -> Promise {
+                let cb = Closure::new(|_: JsValue| {});
+                let ret = promise.then(&cb);
+                cb.forget();
+                ret
+            }
+
+            #[wasm_bindgen]
+            pub fn catch(promise: &Promise) -> Promise {
+                let cb = Closure::new(|_: JsValue| {});
+                let ret = promise.catch(&cb);
+                cb.forget();
+                ret
+            }
+        "#)
+        .file("test.js", r#"
+            import * as assert from "assert";
+            import * as wasm from "./out";
+
+            export function test() {
+                assert.ok(wasm.then(Promise.resolve(1)) instanceof Promise);
+                assert.ok(wasm.catch(Promise.reject(2)) instanceof Promise);
+            }
+        "#)
+        .test()
+}
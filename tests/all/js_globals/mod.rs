@@ -6,6 +6,7 @@ mod Array;
 mod ArrayBuffer;
 mod ArrayIterator;
 mod Boolean;
+mod DataView;
 mod Date;
 mod Error;
 mod Function;
@@ -16,6 +17,7 @@ mod MapIterator;
 mod Math;
 mod Number;
 mod Object;
+mod Promise;
 mod Proxy;
 mod Reflect;
 mod Set;
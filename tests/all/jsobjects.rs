@@ -373,3 +373,313 @@ fn serde() {
         )
         .test();
 }
+
+#[test]
+fn serde_direct() {
+    project()
+        .serde(true)
+        .depend("serde = '1.0'")
+        .depend("serde_derive = '1.0'")
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+                #[macro_use]
+                extern crate serde_derive;
+                extern crate serde_bytes;
+
+                use std::collections::BTreeMap;
+
+                use wasm_bindgen::prelude::*;
+                use wasm_bindgen::serde_js::{self, Serializer};
+
+                #[derive(Deserialize, Serialize, PartialEq, Debug)]
+                pub struct Foo {
+                    a: u32,
+                    b: String,
+                    c: Option<u32>,
+                    d: Vec<Bar>,
+                    e: BTreeMap<String, u32>,
+                    f: u64,
+                    g: Baz,
+                    h: Baz,
+                    i: (i32, bool),
+                }
+
+                #[derive(Deserialize, Serialize, PartialEq, Debug)]
+                pub struct Bar {
+                    a: f64,
+                }
+
+                #[derive(Deserialize, Serialize, PartialEq, Debug)]
+                pub enum Baz {
+                    A,
+                    B { x: i8 },
+                }
+
+                #[wasm_bindgen(module = "./test")]
+                extern {
+                    fn verify(a: JsValue) -> JsValue;
+                    fn verify_objects(a: JsValue);
+                }
+
+                fn foo() -> Foo {
+                    let mut e = BTreeMap::new();
+                    e.insert("x".to_string(), 1);
+                    Foo {
+                        a: 0,
+                        b: "foo".to_string(),
+                        c: None,
+                        d: vec![Bar { a: 1.5 }],
+                        e,
+                        f: u64::max_value(),
+                        g: Baz::A,
+                        h: Baz::B { x: -1 },
+                        i: (-2, true),
+                    }
+                }
+
+                #[wasm_bindgen]
+                pub fn run() {
+                    let ret = verify(serde_js::to_value(&foo()).unwrap());
+                    let foo = serde_js::from_value::<Foo>(ret).unwrap();
+                    assert_eq!(foo.a, 2);
+                    assert_eq!(foo.c, Some(3));
+                    assert_eq!(foo.d, vec![Bar { a: 4.0 }]);
+                    assert_eq!(foo.e["y"], 5);
+                    assert_eq!(foo.f, 6);
+                    assert_eq!(foo.g, Baz::B { x: 7 });
+
+                    let mut ser = Serializer::new();
+                    ser.serialize_maps_as_objects(true)
+                        .serialize_none_as_null(true);
+                    verify_objects(ser.to_value(&foo()).unwrap());
+                }
+
+                #[wasm_bindgen]
+                pub fn bytes(a: JsValue) -> JsValue {
+                    let bytes = serde_js::from_value::<serde_bytes::ByteBuf>(a).unwrap();
+                    serde_js::to_value(&serde_bytes::Bytes::new(&bytes)).unwrap()
+                }
+
+                #[wasm_bindgen]
+                pub fn invalid(a: JsValue) -> String {
+                    serde_js::from_value::<Foo>(a).unwrap_err().to_string()
+                }
+            "#,
+        )
+        .depend("serde_bytes = '0.10'")
+        .file(
+            "test.js",
+            r#"
+                import { run, bytes, invalid } from "./out";
+                import * as assert from "assert";
+
+                export function verify(a) {
+                    assert.deepStrictEqual(a, {
+                        a: 0,
+                        b: 'foo',
+                        c: undefined,
+                        d: [{ a: 1.5 }],
+                        e: new Map([['x', 1]]),
+                        f: BigInt('18446744073709551615'),
+                        g: 'A',
+                        h: { B: { x: -1 } },
+                        i: [-2, true],
+                    });
+
+                    return {
+                        a: 2,
+                        b: 'bar',
+                        c: 3,
+                        d: [{ a: 4 }],
+                        e: { y: 5 },
+                        f: BigInt(6),
+                        g: { B: { x: 7 } },
+                        h: 'A',
+                        i: [0, false],
+                    }
+                }
+
+                export function verify_objects(a) {
+                    assert.strictEqual(a.c, null);
+                    assert.deepStrictEqual(a.e, { x: 1 });
+                }
+
+                export function test() {
+                    run();
+                    assert.deepStrictEqual(bytes(new Uint8Array([1, 2, 3])), new Uint8Array([1, 2, 3]));
+                    assert.deepStrictEqual(bytes([4, 5]), new Uint8Array([4, 5]));
+                    assert.ok(invalid('foo').length > 0);
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+fn heap_slots() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen(module = "./test")]
+                extern {
+                    fn fresh() -> JsValue;
+                }
+
+                #[wasm_bindgen]
+                pub fn clones(a: &JsValue) -> JsValue {
+                    // More clones than fit in one batch of drops
+                    let clones = (0..1000).map(|_| a.clone()).collect::<Vec<_>>();
+                    for c in clones.iter() {
+                        assert!(c == a);
+                    }
+                    let ret = clones[500].clone();
+                    drop(clones);
+                    ret
+                }
+
+                #[wasm_bindgen]
+                pub fn churn() {
+                    let mut keep = Vec::new();
+                    for i in 0..1000 {
+                        let v = fresh();
+                        if i % 3 == 0 {
+                            keep.push(v.clone());
+                        }
+                    }
+                    for (i, v) in keep.iter().enumerate() {
+                        assert_eq!(v.as_f64(), Some((i * 3) as f64));
+                    }
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as assert from "assert";
+                import * as wasm from "./out";
+
+                let next = 0;
+
+                export function fresh() {
+                    return next++;
+                }
+
+                export function test() {
+                    const a = { a: 1 };
+                    assert.strictEqual(wasm.clones(a), a);
+                    wasm.churn();
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+fn debug_heap_checks() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use std::ptr;
+                use wasm_bindgen::prelude::*;
+
+                static mut KEPT: Option<Vec<JsValue>> = None;
+
+                #[wasm_bindgen]
+                pub fn keep(a: JsValue) {
+                    unsafe {
+                        KEPT.get_or_insert_with(Vec::new).push(a);
+                    }
+                }
+
+                #[wasm_bindgen]
+                pub fn release() {
+                    unsafe {
+                        KEPT = None;
+                    }
+                }
+
+                #[wasm_bindgen]
+                pub fn use_after_drop(a: JsValue) {
+                    let stale = unsafe { ptr::read(&a) };
+                    drop(a);
+                    unsafe {
+                        wasm_bindgen::__wbindgen_drop_flush();
+                    }
+                    stale.as_f64();
+                }
+
+                #[wasm_bindgen]
+                pub struct Counter {
+                    count: u32,
+                }
+
+                #[wasm_bindgen]
+                impl Counter {
+                    pub fn new() -> Counter {
+                        Counter { count: 3 }
+                    }
+
+                    pub fn take(self) -> u32 {
+                        self.count
+                    }
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as assert from "assert";
+                import * as wasm from "./out";
+
+                export function test() {
+                    assert.deepStrictEqual(wasm.__wbindgen_heap_stats(), {
+                        heapObjects: 0,
+                        classes: {},
+                    });
+
+                    wasm.keep({});
+                    wasm.keep('a');
+                    const a = wasm.Counter.new();
+                    const b = wasm.Counter.new();
+                    assert.deepStrictEqual(wasm.__wbindgen_heap_stats(), {
+                        heapObjects: 2,
+                        classes: { Counter: 2 },
+                    });
+
+                    // Minifiers may rename the class, which mustn't affect
+                    // which class the pointers are tracked under.
+                    Object.defineProperty(wasm.Counter, 'name', { value: 'c' });
+
+                    a.free();
+                    assert.strictEqual(b.take(), 3);
+                    assert.throws(() => a.free(), /`Counter` instance used after being freed/);
+                    assert.throws(() => b.free(), /`Counter` instance used after being freed/);
+
+                    wasm.release();
+                    assert.deepStrictEqual(wasm.__wbindgen_heap_stats(), {
+                        heapObjects: 0,
+                        classes: {},
+                    });
+
+                    assert.throws(() => wasm.use_after_drop({}), /use of a dropped JS object/);
+                }
+            "#,
+        )
+        .test();
+}
@@ -2,11 +2,13 @@ extern crate wasm_bindgen_test_project_builder as project_builder;
 
 use project_builder::{project, run};
 
+mod anyref;
 mod api;
 mod char;
 mod classes;
 mod closures;
 mod comments;
+mod deno;
 mod dependencies;
 mod enums;
 mod import_class;
@@ -18,10 +20,18 @@ mod math;
 mod node;
 mod non_debug;
 mod non_wasm;
+mod panics;
+mod plain_objects;
+#[cfg(feature = "js_globals")]
+mod promises;
 mod simple;
 mod slice;
+mod source_maps;
 mod structural;
+mod threads;
 mod typescript;
 mod u64;
 mod validate_prt;
+mod web;
+mod web_sys;
 mod webidl;
@@ -1,4 +1,7 @@
-use super::project;
+use std::fs;
+use std::process::Command;
+
+use super::{project, run};
 
 #[test]
 fn works() {
@@ -159,3 +162,93 @@ fn works() {
         )
         .test();
 }
+
+#[test]
+fn native_module() {
+    let mut p = project();
+    p.nodejs_module(true).file(
+        "src/lib.rs",
+        r#"
+            #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            #[wasm_bindgen]
+            pub fn greet(name: &str) -> String {
+                format!("Hello, {}!", name)
+            }
+        "#,
+    );
+
+    let (root, _) = p.gen_bindings();
+    let read = |name: &str| fs::read_to_string(root.join(name)).unwrap();
+
+    let js = read("out.js");
+    assert!(js.contains("import * as wasm from './out_bg.js';"));
+    assert!(js.contains("export function greet("));
+    assert!(!js.contains("require("));
+
+    let shim = read("out_bg.js");
+    assert!(shim.contains("from './out.js';"));
+    assert!(shim.contains("fileURLToPath(new URL('./out_bg.wasm', import.meta.url))"));
+    assert!(!shim.contains("url.parse"));
+
+    // A CommonJS entry point is emitted alongside for `require`
+    let cjs = read("out.cjs");
+    assert!(cjs.contains("require('./out_bg.cjs')"));
+    assert!(cjs.contains("module.exports.greet"));
+    assert!(read("out_bg.cjs").contains("require('./out.cjs')"));
+
+    // Node only loads `out.js` as an ES module in a `"type": "module"` package
+    assert!(read("package.json").contains(r#""type": "module""#));
+}
+
+#[test]
+fn native_module_top_level_await() {
+    let mut p = project();
+    p.nodejs_module(true)
+        .top_level_await(true)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen]
+                pub fn greet(name: &str) -> String {
+                    format!("Hello, {}!", name)
+                }
+            "#,
+        )
+        .file(
+            "main.js",
+            r#"
+                import * as assert from 'assert';
+                import { createRequire } from 'module';
+                import { greet } from './out.js';
+
+                // The wasm is instantiated by the time the import resolves
+                assert.strictEqual(greet('world'), 'Hello, world!');
+
+                // while the CommonJS entry point still loads it synchronously
+                const require = createRequire(import.meta.url);
+                assert.strictEqual(require('./out.cjs').greet('cjs'), 'Hello, cjs!');
+            "#,
+        );
+
+    let (root, _) = p.gen_bindings();
+    let shim = fs::read_to_string(root.join("out_bg.js")).unwrap();
+    assert!(shim.contains("await WebAssembly.instantiate(bytes, imports)"));
+    assert!(!shim.contains("new WebAssembly.Instance"));
+    let cjs = fs::read_to_string(root.join("out_bg.cjs")).unwrap();
+    assert!(cjs.contains("new WebAssembly.Instance"));
+
+    let mut cmd = Command::new("node");
+    cmd.arg("main.js").current_dir(&root);
+    run(&mut cmd, "node");
+}
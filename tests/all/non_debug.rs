@@ -46,3 +46,108 @@ fn works() {
         )
         .test();
 }
+
+#[test]
+fn name_section_kept() {
+    name_section_test(false);
+}
+
+#[test]
+fn name_section_removed() {
+    name_section_test(true);
+}
+
+fn name_section_test(remove: bool) {
+    project()
+        .remove_name_section(remove)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen]
+                pub fn add(a: u32, b: u32) -> u32 {
+                    a + b
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            &format!(
+                r#"
+                    import * as fs from 'fs';
+                    import * as assert from 'assert';
+                    import * as wasm from './out';
+
+                    export function test() {{
+                        assert.strictEqual(wasm.add(1, 2), 3);
+                        const bytes = fs.readFileSync('out_bg.wasm');
+                        const m = new WebAssembly.Module(bytes);
+                        const sections = WebAssembly.Module.customSections(m, 'name');
+                        assert.strictEqual(sections.length, {});
+                    }}
+                "#,
+                if remove { 0 } else { 1 }
+            ),
+        )
+        .test();
+}
+
+#[test]
+fn debug_sections_kept() {
+    debug_section_test(true);
+}
+
+#[test]
+fn debug_sections_removed() {
+    debug_section_test(false);
+}
+
+fn debug_section_test(keep: bool) {
+    project()
+        .keep_debug(keep)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                // Stands in for the DWARF sections rustc may or may not emit
+                #[allow(non_upper_case_globals)]
+                #[wasm_custom_section = ".debug_test"]
+                const debug_test: [u8; 4] = *b"dwrf";
+
+                #[wasm_bindgen]
+                pub fn add(a: u32, b: u32) -> u32 {
+                    a + b
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            &format!(
+                r#"
+                    import * as fs from 'fs';
+                    import * as assert from 'assert';
+                    import * as wasm from './out';
+
+                    export function test() {{
+                        assert.strictEqual(wasm.add(1, 2), 3);
+                        const bytes = fs.readFileSync('out_bg.wasm');
+                        const m = new WebAssembly.Module(bytes);
+                        const sections = WebAssembly.Module.customSections(m, '.debug_test');
+                        assert.strictEqual(sections.length, {});
+                    }}
+                "#,
+                if keep { 1 } else { 0 }
+            ),
+        )
+        .test();
+}
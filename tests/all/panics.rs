@@ -0,0 +1,81 @@
+use super::project;
+
+#[test]
+fn logged() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen]
+                pub fn boom() {
+                    wasm_bindgen::panic::set_hook();
+                    panic!("oh no: {}", 1);
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as wasm from "./out";
+                import * as assert from "assert";
+
+                export function test() {
+                    const errors = [];
+                    const error = console.error;
+                    console.error = msg => errors.push(msg);
+                    try {
+                        assert.throws(wasm.boom, WebAssembly.RuntimeError);
+                    } finally {
+                        console.error = error;
+                    }
+                    assert.strictEqual(errors.length, 1);
+                    assert.ok(errors[0].startsWith("panicked at 'oh no: 1', src/lib.rs:"));
+                    assert.ok(errors[0].includes("Stack:"));
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+fn rethrown() {
+    project()
+        .rethrow_panics(true)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen]
+                pub fn boom() {
+                    wasm_bindgen::panic::set_hook();
+                    panic!("oh no: {}", 1);
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as wasm from "./out";
+                import * as assert from "assert";
+
+                export function test() {
+                    assert.throws(wasm.boom, err => {
+                        return !(err instanceof WebAssembly.RuntimeError) &&
+                            err.message.startsWith("panicked at 'oh no: 1', src/lib.rs:");
+                    });
+                }
+            "#,
+        )
+        .test();
+}
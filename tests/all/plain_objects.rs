@@ -0,0 +1,181 @@
+use super::project;
+
+#[test]
+fn works() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[derive(JsObject)]
+                pub struct Options {
+                    name: String,
+                    width: u32,
+                    ratio: f64,
+                    visible: bool,
+                    tag: Option<String>,
+                    sizes: Vec<u8>,
+                    inner: Inner,
+                }
+
+                #[derive(JsObject)]
+                pub struct Inner {
+                    value: JsValue,
+                }
+
+                #[wasm_bindgen(module = "./test")]
+                extern {
+                    fn check(o: Options) -> Options;
+                }
+
+                #[wasm_bindgen]
+                pub fn run() {
+                    let o = check(Options {
+                        name: "foo".to_string(),
+                        width: 3,
+                        ratio: 0.5,
+                        visible: true,
+                        tag: None,
+                        sizes: vec![1, 2],
+                        inner: Inner { value: JsValue::null() },
+                    });
+                    assert_eq!(o.name, "bar");
+                    assert_eq!(o.width, 4);
+                    assert_eq!(o.ratio, 1.5);
+                    assert!(!o.visible);
+                    assert_eq!(o.tag, Some("baz".to_string()));
+                    assert_eq!(o.sizes, vec![3]);
+                    assert_eq!(o.inner.value.as_f64(), Some(2.0));
+                }
+
+                #[wasm_bindgen]
+                pub fn width(o: Options) -> u32 {
+                    o.width
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as assert from "assert";
+                import { run, width } from "./out";
+
+                export function check(o) {
+                    assert.deepStrictEqual(o, {
+                        name: 'foo',
+                        width: 3,
+                        ratio: 0.5,
+                        visible: true,
+                        tag: undefined,
+                        sizes: [1, 2],
+                        inner: { value: null },
+                    });
+                    assert.strictEqual(Object.getPrototypeOf(o), Object.prototype);
+                    assert.strictEqual(JSON.stringify(o.inner), '{"value":null}');
+
+                    return {
+                        name: 'bar',
+                        width: 4,
+                        ratio: 1.5,
+                        visible: false,
+                        tag: 'baz',
+                        sizes: [3],
+                        inner: { value: 2 },
+                    };
+                }
+
+                export function test() {
+                    run();
+                    const o = {
+                        name: 'a',
+                        width: 5,
+                        ratio: 0,
+                        visible: true,
+                        sizes: [],
+                        inner: { value: undefined },
+                    };
+                    assert.strictEqual(width(o), 5);
+                    assert.strictEqual(width({ ...o, tag: null }), 5);
+                    assert.throws(() => width({ ...o, width: 'a' }), /expected a `Options` object/);
+                    assert.throws(() => width(null), /expected a `Options` object/);
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+fn typescript_interface() {
+    project()
+        .webpack(true)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                /// A point.
+                #[derive(JsObject)]
+                pub struct Point {
+                    x: i32,
+                    y: i32,
+                    label: Option<String>,
+                }
+
+                #[derive(JsObject)]
+                pub struct Path {
+                    points: Vec<Point>,
+                    closed: bool,
+                }
+
+                #[wasm_bindgen]
+                pub fn len(p: Path) -> u32 {
+                    p.points.len() as u32
+                }
+
+                #[wasm_bindgen]
+                pub fn origin() -> Point {
+                    Point { x: 0, y: 0, label: Some("origin".to_string()) }
+                }
+            "#,
+        )
+        .file(
+            "test.ts",
+            r#"
+                import * as assert from 'assert';
+                import { len, origin, Path, Point } from './out';
+
+                export function test() {
+                    const a: Point = { x: 1, y: 2 };
+                    const b: Point = { x: 3, y: 4, label: 'b' };
+                    const path: Path = { points: [a, b], closed: false };
+                    assert.strictEqual(len(path), 2);
+
+                    // These only compile if the signatures use the interfaces
+                    // rather than `any`, and the fields have the right types.
+                    const argIsPath: IsAny<Parameters<typeof len>[0]> = false;
+                    const retIsPoint: IsAny<ReturnType<typeof origin>> = false;
+                    const o = origin();
+                    const x: number = o.x;
+                    const label: string | undefined = o.label;
+                    const closed: boolean = path.closed;
+                    assert.strictEqual(x, 0);
+                    assert.strictEqual(label, 'origin');
+                    assert.strictEqual(len({ points: [o], closed }), 1);
+                    assert.strictEqual(argIsPath || retIsPoint, false);
+                };
+
+                type IsAny<T> = 0 extends (1 & T) ? true : false;
+                type Parameters<F> = F extends (...args: infer A) => any ? A : never;
+            "#,
+        )
+        .test();
+}
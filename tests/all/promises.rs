@@ -0,0 +1,171 @@
+use super::project;
+
+#[test]
+fn test_returns_promise() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+                use wasm_bindgen::js::Promise;
+
+                #[wasm_bindgen]
+                pub fn answer() -> Promise {
+                    Promise::resolve(&JsValue::from(42))
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as assert from "assert";
+                import * as wasm from "./out";
+
+                export function test() {
+                    return wasm.answer()
+                        .then(value => new Promise(resolve => setTimeout(() => resolve(value), 10)))
+                        .then(value => assert.strictEqual(value, 42));
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+fn slow_promise_within_timeout() {
+    project()
+        .timeout(5_000)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+                use wasm_bindgen::js::Promise;
+
+                #[wasm_bindgen]
+                pub fn chain(p: Promise) -> Promise {
+                    p
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as assert from "assert";
+                import * as wasm from "./out";
+
+                export function test() {
+                    const p = new Promise(resolve => setTimeout(() => resolve("done"), 500));
+                    return wasm.chain(p).then(value => assert.strictEqual(value, "done"));
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+#[should_panic(expected = "exception: boom")]
+fn rejected_promise_fails() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+                use wasm_bindgen::js::Promise;
+
+                #[wasm_bindgen]
+                pub fn chain(p: Promise) -> Promise {
+                    p
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as wasm from "./out";
+
+                export function test() {
+                    return wasm.chain(Promise.reject(new Error("boom")));
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+#[should_panic(expected = "rejected with: nope")]
+fn rejected_promise_without_error_fails() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+                use wasm_bindgen::js::Promise;
+
+                #[wasm_bindgen]
+                pub fn reject() -> Promise {
+                    Promise::reject(&JsValue::from("nope"))
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as wasm from "./out";
+
+                export function test() {
+                    return wasm.reject();
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+#[should_panic(expected = "test timed out after 100ms")]
+fn never_settling_promise_times_out() {
+    project()
+        .timeout(100)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+                use wasm_bindgen::js::Promise;
+
+                #[wasm_bindgen]
+                pub fn chain(p: Promise) -> Promise {
+                    p
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as wasm from "./out";
+
+                export function test() {
+                    return wasm.chain(new Promise(() => {}));
+                }
+            "#,
+        )
+        .test();
+}
@@ -130,6 +130,140 @@ fn string_arguments() {
         .test();
 }
 
+#[test]
+fn many_global_stack_values() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                // Each closure takes two values on the global stack, which
+                // starts out with room for 16.
+                #[wasm_bindgen(module = "./test")]
+                extern {
+                    #[wasm_bindgen(js_name = sum)]
+                    fn sum9(
+                        a0: &Fn() -> u32, a1: &Fn() -> u32, a2: &Fn() -> u32, a3: &Fn() -> u32,
+                        a4: &Fn() -> u32, a5: &Fn() -> u32, a6: &Fn() -> u32, a7: &Fn() -> u32,
+                        a8: &Fn() -> u32,
+                    ) -> u32;
+                    #[wasm_bindgen(js_name = sum)]
+                    fn sum20(
+                        a0: &Fn() -> u32, a1: &Fn() -> u32, a2: &Fn() -> u32, a3: &Fn() -> u32,
+                        a4: &Fn() -> u32, a5: &Fn() -> u32, a6: &Fn() -> u32, a7: &Fn() -> u32,
+                        a8: &Fn() -> u32, a9: &Fn() -> u32, a10: &Fn() -> u32, a11: &Fn() -> u32,
+                        a12: &Fn() -> u32, a13: &Fn() -> u32, a14: &Fn() -> u32, a15: &Fn() -> u32,
+                        a16: &Fn() -> u32, a17: &Fn() -> u32, a18: &Fn() -> u32, a19: &Fn() -> u32,
+                    ) -> u32;
+                    #[wasm_bindgen(js_name = sum)]
+                    fn sum1(a0: &Fn() -> u32) -> u32;
+                }
+
+                #[wasm_bindgen]
+                pub fn run() {
+                    let k = |k: u32| move || k;
+                    // 18 values, which grows the stack once
+                    assert_eq!(sum9(
+                        &k(0), &k(1), &k(2), &k(3), &k(4),
+                        &k(5), &k(6), &k(7), &k(8),
+                    ), 36);
+                    // 40 values, which grows it twice more
+                    assert_eq!(sum20(
+                        &k(0), &k(1), &k(2), &k(3), &k(4),
+                        &k(5), &k(6), &k(7), &k(8), &k(9),
+                        &k(10), &k(11), &k(12), &k(13), &k(14),
+                        &k(15), &k(16), &k(17), &k(18), &k(19),
+                    ), 190);
+                    // The grown stack keeps being used afterwards
+                    assert_eq!(sum1(&k(7)), 7);
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import { run } from "./out";
+
+                export function sum(...fs) {
+                    return fs.reduce((sum, f) => sum + f(), 0);
+                }
+
+                export function test() {
+                    run();
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+fn global_stack_over_pop() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::convert::{GlobalStack, Stack};
+                use wasm_bindgen::prelude::*;
+
+                fn write(n: u32) {
+                    let mut stack = unsafe { GlobalStack::new() };
+                    for i in 0..n {
+                        stack.push(i);
+                    }
+                }
+
+                fn read(n: u32) {
+                    let mut stack = unsafe { GlobalStack::new() };
+                    for i in 0..n {
+                        assert_eq!(stack.pop(), i);
+                    }
+                }
+
+                #[wasm_bindgen]
+                pub fn pop_pushed() {
+                    write(20);
+                    read(20);
+                    write(3);
+                    read(3);
+                }
+
+                #[wasm_bindgen]
+                pub fn pop_more_than_pushed() {
+                    // The stack has grown to fit 20 values before, but only 3
+                    // of them were written this time.
+                    write(20);
+                    write(3);
+                    read(4);
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as assert from "assert";
+                import * as wasm from "./out";
+
+                export function test() {
+                    wasm.pop_pushed();
+                    assert.throws(
+                        wasm.pop_more_than_pushed,
+                        /popped more values than were pushed onto the global stack/
+                    );
+                }
+            "#,
+        )
+        .test();
+}
+
 #[test]
 fn return_a_string() {
     project()
@@ -368,3 +502,52 @@ fn no_std_class() {
         )
         .test();
 }
+
+#[test]
+fn descriptors_removed() {
+    project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen(module = "./test")]
+                extern {
+                    fn hit(a: &str) -> JsValue;
+                }
+
+                #[wasm_bindgen]
+                pub fn run(a: &[u8]) -> String {
+                    drop(a);
+                    hit("x").as_string().unwrap()
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as fs from 'fs';
+                import * as assert from 'assert';
+
+                export function hit() {
+                    return 'hit';
+                }
+
+                export function test() {
+                    const bytes = fs.readFileSync('out_bg.wasm');
+                    const m = new WebAssembly.Module(bytes);
+                    for (const i of WebAssembly.Module.imports(m)) {
+                        assert.notStrictEqual(i.name, '__wbindgen_describe');
+                    }
+                    for (const e of WebAssembly.Module.exports(m)) {
+                        assert.ok(!e.name.startsWith('__wbindgen_describe'));
+                    }
+                }
+            "#,
+        )
+        .test();
+}
@@ -0,0 +1,77 @@
+use std::fs;
+
+use super::project;
+
+#[test]
+fn maps_to_rust_source() {
+    let mut p = project();
+    p.source_map(true).file(
+        "src/lib.rs",
+        r#"
+            #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            #[wasm_bindgen(module = "./test")]
+            extern {
+                fn hit(a: &str);
+            }
+
+            #[wasm_bindgen]
+            pub fn run() {
+                hit("run");
+            }
+
+            #[wasm_bindgen]
+            pub struct Counter {
+                count: u32,
+            }
+
+            #[wasm_bindgen]
+            impl Counter {
+                pub fn new() -> Counter {
+                    Counter { count: 0 }
+                }
+
+                pub fn increment(&mut self) -> u32 {
+                    self.count += 1;
+                    self.count
+                }
+            }
+        "#,
+    )
+    .file(
+        "test.js",
+        r#"
+            import * as assert from "assert";
+            import { run, Counter } from "./out";
+
+            let hits = [];
+            export function hit(a) {
+                hits.push(a);
+            }
+
+            export function test() {
+                run();
+                assert.deepStrictEqual(hits, ["run"]);
+                const c = Counter.new();
+                assert.strictEqual(c.increment(), 1);
+                c.free();
+            }
+        "#,
+    );
+
+    let (root, _) = p.gen_bindings();
+    let js = p.read_js();
+    assert!(js.ends_with("//# sourceMappingURL=out.mjs.map\n"));
+    assert!(!js.contains("wbg-source-map"));
+
+    let map = fs::read_to_string(root.join("out.mjs.map")).unwrap();
+    assert!(map.contains(r#""version":3"#));
+    assert!(map.contains(r#""file":"out.mjs""#));
+    assert!(map.contains(r#""sources":["src/lib.rs"]"#));
+
+    p.test();
+}
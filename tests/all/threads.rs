@@ -0,0 +1,289 @@
+use std::fs;
+use std::process::Command;
+
+use super::{project, run};
+
+#[test]
+fn shares_memory() {
+    let mut p = project();
+    p.web(true).threads(true).file(
+        "src/lib.rs",
+        r#"
+            #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+            extern crate wasm_bindgen;
+
+            use std::alloc::System;
+
+            use wasm_bindgen::prelude::*;
+            use wasm_bindgen::threads::LockedAllocator;
+
+            #[global_allocator]
+            static ALLOC: LockedAllocator<System> = LockedAllocator(System);
+
+            static GREETING: &str = "Hello";
+
+            #[wasm_bindgen]
+            pub fn greet(name: &str) -> String {
+                format!("{}, {}!", GREETING, name)
+            }
+        "#,
+    );
+
+    let (root, _) = p.gen_bindings();
+    let js = p.read_js();
+    assert!(js.contains("function init(input, thread)"));
+    assert!(js.contains("__wbindgen_threads: threadImports(thread)"));
+    assert!(js.contains("shared: true"));
+    assert!(js.contains("export function threadData("));
+    assert!(js.contains("export function threadExit("));
+    assert!(js.contains("wasm.__wbindgen_stack_pointer_set("));
+    assert!(js.contains("wasm.__wbindgen_thread_ptr_set("));
+    assert!(js.contains("Atomics.compareExchange"));
+    assert!(js.contains("getUint8Memory().slice(ptr, ptr + len)"));
+
+    // The memory is imported rather than defined by the module
+    let wasm = fs::read(root.join("out_bg.wasm")).unwrap();
+    assert!(wasm
+        .windows(b"__wbindgen_threads".len())
+        .any(|w| w == b"__wbindgen_threads"));
+}
+
+#[test]
+fn runs_on_worker_threads() {
+    let mut p = project();
+    p.web(true)
+        .threads(true)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use std::alloc::System;
+
+                use wasm_bindgen::prelude::*;
+                use wasm_bindgen::threads::LockedAllocator;
+
+                #[global_allocator]
+                static ALLOC: LockedAllocator<System> = LockedAllocator(System);
+
+                static mut COUNTER: u32 = 0;
+
+                #[wasm_bindgen]
+                pub fn bump() -> u32 {
+                    unsafe {
+                        COUNTER += 1;
+                        COUNTER
+                    }
+                }
+
+                #[wasm_bindgen]
+                pub fn alloc_value(value: u32) -> u32 {
+                    Box::into_raw(Box::new(value)) as u32
+                }
+
+                #[wasm_bindgen]
+                pub fn read_value(ptr: u32) -> u32 {
+                    unsafe { *(ptr as *const u32) }
+                }
+
+                #[wasm_bindgen]
+                pub fn greet(name: &str) -> String {
+                    format!("Hello, {}!", name)
+                }
+            "#,
+        )
+        .file("package.json", r#"{ "type": "module" }"#)
+        .file(
+            "main.js",
+            r#"
+                import * as assert from 'assert';
+                import * as fs from 'fs';
+                import { Worker } from 'worker_threads';
+                import init, { bump, alloc_value, read_value, threadData } from './out.js';
+
+                init(fs.readFileSync(new URL('./out_bg.wasm', import.meta.url)))
+                    .then(() => {
+                        assert.strictEqual(bump(), 1);
+                        const data = threadData();
+                        data.ptr = alloc_value(42);
+                        return new Promise((resolve, reject) => {
+                            const worker = new Worker(new URL('./worker.js', import.meta.url), {
+                                workerData: data,
+                            });
+                            worker.on('message', resolve);
+                            worker.on('error', reject);
+                        });
+                    })
+                    .then(msg => {
+                        // The worker sees the static as modified by this thread
+                        // and the value allocated here, and the other way
+                        // around.
+                        assert.strictEqual(msg.counter, 2);
+                        assert.strictEqual(msg.value, 42);
+                        assert.strictEqual(msg.greeting, 'Hello, worker!');
+                        assert.strictEqual(bump(), 3);
+                        assert.strictEqual(read_value(msg.ptr), 7);
+                    })
+                    .catch(e => {
+                        console.error(e);
+                        process.exit(1);
+                    });
+            "#,
+        )
+        .file(
+            "worker.js",
+            r#"
+                import { parentPort, workerData } from 'worker_threads';
+                import init, { bump, alloc_value, read_value, greet, threadExit } from './out.js';
+
+                init(workerData.module, workerData).then(() => {
+                    parentPort.postMessage({
+                        counter: bump(),
+                        value: read_value(workerData.ptr),
+                        greeting: greet('worker'),
+                        ptr: alloc_value(7),
+                    });
+                    threadExit();
+                });
+            "#,
+        );
+
+    let (root, _) = p.gen_bindings();
+    let mut cmd = Command::new("node");
+    cmd.arg("main.js").current_dir(&root);
+    run(&mut cmd, "node");
+}
+
+#[test]
+fn thread_locals_per_thread() {
+    let mut p = project();
+    p.web(true)
+        .threads(true)
+        // `thread_local!` is only backed by thread-local storage with atomics
+        .rustflag("-Ctarget-feature=+atomics")
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use std::alloc::System;
+                use std::cell::Cell;
+
+                use wasm_bindgen::prelude::*;
+                use wasm_bindgen::threads::LockedAllocator;
+
+                #[global_allocator]
+                static ALLOC: LockedAllocator<System> = LockedAllocator(System);
+
+                thread_local!(static VALUE: Cell<u32> = Cell::new(7));
+
+                #[wasm_bindgen]
+                pub fn get_value() -> u32 {
+                    VALUE.with(|v| v.get())
+                }
+
+                #[wasm_bindgen]
+                pub fn set_value(value: u32) {
+                    VALUE.with(|v| v.set(value))
+                }
+            "#,
+        )
+        .file("package.json", r#"{ "type": "module" }"#)
+        .file(
+            "main.js",
+            r#"
+                import * as assert from 'assert';
+                import * as fs from 'fs';
+                import { Worker } from 'worker_threads';
+                import init, { get_value, set_value, threadData } from './out.js';
+
+                function spawn(value) {
+                    return new Promise((resolve, reject) => {
+                        const worker = new Worker(new URL('./worker.js', import.meta.url), {
+                            workerData: { thread: threadData(), value },
+                        });
+                        worker.on('message', resolve);
+                        worker.on('error', reject);
+                    });
+                }
+
+                init(fs.readFileSync(new URL('./out_bg.wasm', import.meta.url)))
+                    .then(() => {
+                        assert.strictEqual(get_value(), 7);
+                        set_value(1);
+                        return Promise.all([spawn(10), spawn(20)]);
+                    })
+                    .then(msgs => {
+                        // Each worker started out with the initial value rather
+                        // than this thread's, and kept its own.
+                        assert.deepStrictEqual(msgs, [
+                            { before: 7, after: 10 },
+                            { before: 7, after: 20 },
+                        ]);
+                        assert.strictEqual(get_value(), 1);
+                    })
+                    .catch(e => {
+                        console.error(e);
+                        process.exit(1);
+                    });
+            "#,
+        )
+        .file(
+            "worker.js",
+            r#"
+                import { parentPort, workerData } from 'worker_threads';
+                import init, { get_value, set_value, threadExit } from './out.js';
+
+                const { thread, value } = workerData;
+                init(thread.module, thread).then(() => {
+                    const before = get_value();
+                    set_value(value);
+                    // Give the other worker a chance to set its value too
+                    setTimeout(() => {
+                        parentPort.postMessage({ before, after: get_value() });
+                        threadExit();
+                    }, 50);
+                });
+            "#,
+        );
+
+    let (root, _) = p.gen_bindings();
+    let js = p.read_js();
+    assert!(js.contains("wasm.__wbindgen_tls_base_set(thread.tls)"));
+
+    let mut cmd = Command::new("node");
+    cmd.arg("main.js").current_dir(&root);
+    run(&mut cmd, "node");
+}
+
+#[test]
+fn main_thread_without_threads() {
+    let mut p = project();
+    p.web(true).file(
+        "src/lib.rs",
+        r#"
+            #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            #[wasm_bindgen]
+            pub fn greet(name: &str) -> String {
+                format!("Hello, {}!", name)
+            }
+        "#,
+    );
+
+    p.gen_bindings();
+    let js = p.read_js();
+    assert!(js.contains("function init(input)"));
+    assert!(!js.contains("threadImports"));
+    assert!(!js.contains("Atomics"));
+    assert!(js.contains("getUint8Memory().subarray(ptr, ptr + len)"));
+}
@@ -0,0 +1,91 @@
+use std::process::Command;
+
+use super::{project, run};
+
+#[test]
+fn init_instantiates_wasm() {
+    let mut p = project();
+    p.web(true)
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                #[wasm_bindgen]
+                pub fn greet(name: &str) -> String {
+                    format!("Hello, {}!", name)
+                }
+
+                #[wasm_bindgen]
+                pub struct Counter {
+                    count: u32,
+                }
+
+                #[wasm_bindgen]
+                impl Counter {
+                    pub fn new() -> Counter {
+                        Counter { count: 0 }
+                    }
+
+                    pub fn increment(&mut self) -> u32 {
+                        self.count += 1;
+                        self.count
+                    }
+                }
+            "#,
+        )
+        .file("package.json", r#"{ "type": "module" }"#)
+        .file(
+            "main.js",
+            r#"
+                import * as assert from 'assert';
+                import * as fs from 'fs';
+                import init, { greet, Counter } from './out.js';
+
+                const bytes = fs.readFileSync(new URL('./out_bg.wasm', import.meta.url));
+
+                function check() {
+                    assert.strictEqual(greet('web'), 'Hello, web!');
+                    const counter = Counter.new();
+                    assert.strictEqual(counter.increment(), 1);
+                    assert.strictEqual(counter.increment(), 2);
+                    counter.free();
+                }
+
+                init(bytes)
+                    .then(check)
+                    .then(() => init(new WebAssembly.Module(bytes)))
+                    .then(check)
+                    .then(() => {
+                        if (typeof Response !== 'function') return;
+                        const response = new Response(bytes, {
+                            headers: { 'Content-Type': 'application/wasm' },
+                        });
+                        return init(response).then(check);
+                    })
+                    .catch(e => {
+                        console.error(e);
+                        process.exit(1);
+                    });
+            "#,
+        );
+
+    let (root, _) = p.gen_bindings();
+    let js = p.read_js();
+    assert!(js.contains("export default init;"));
+    assert!(js.contains("WebAssembly.instantiateStreaming"));
+    assert!(js.contains("export function greet("));
+    assert!(js.contains("__exports.greet = greet;"));
+    assert!(js.contains("__exports.Counter = Counter;"));
+    // The wasm module is instantiated by `init`, not imported
+    assert!(!js.contains("_bg'"));
+    assert!(!js.contains("require("));
+
+    let mut cmd = Command::new("node");
+    cmd.arg("main.js").current_dir(&root);
+    run(&mut cmd, "node");
+}
@@ -0,0 +1,98 @@
+use super::project;
+
+fn web_sys(features: &[&str]) -> String {
+    let features = features
+        .iter()
+        .map(|f| format!("'{}'", f))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "web-sys = {{ path = '{}/crates/web-sys', features = [{}] }}",
+        env!("CARGO_MANIFEST_DIR"),
+        features
+    )
+}
+
+#[test]
+fn builds_dom_tree() {
+    project()
+        .headless(true)
+        .depend(&web_sys(&["Document", "Element", "HTMLElement", "Node", "Text"]))
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+                extern crate web_sys;
+
+                use wasm_bindgen::prelude::*;
+                use web_sys::{Document, Element, HTMLElement, Node};
+
+                fn node<T: Into<JsValue>>(t: T) -> Node {
+                    Node::from(t.into())
+                }
+
+                #[wasm_bindgen]
+                pub fn build(document: Document, root: Element) -> bool {
+                    let div = document.create_element("div", JsValue::undefined()).unwrap();
+                    div.set_id("created");
+                    div.set_inner_html("<b>hi</b>").unwrap();
+                    let div = node(div);
+                    div.append_child(node(document.create_text_node(" there"))).unwrap();
+
+                    let root = node(root);
+                    root.append_child(div).unwrap();
+                    root.has_child_nodes()
+                }
+
+                #[wasm_bindgen]
+                pub fn hide(element: Element) {
+                    HTMLElement::from(JsValue::from(element)).set_hidden(true).unwrap();
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as assert from "assert";
+                import * as wasm from "./out";
+
+                export function test() {
+                    const root = document.createElement('div');
+                    assert.strictEqual(wasm.build(document, root), true);
+                    assert.strictEqual(root.innerHTML, '<div id="created"><b>hi</b> there</div>');
+
+                    wasm.hide(root.firstChild);
+                    assert.strictEqual(root.firstChild.hidden, true);
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+fn skips_disabled_interfaces() {
+    // Without the `Text` feature `createTextNode` isn't generated, but the
+    // rest of `Document` still is.
+    project()
+        .rlib(true)
+        .depend(&web_sys(&["Document", "Element"]))
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+                extern crate web_sys;
+
+                use wasm_bindgen::prelude::*;
+                use web_sys::{Document, Element};
+
+                pub fn create(document: &Document) -> Element {
+                    document.create_element("p", JsValue::undefined()).unwrap()
+                }
+            "#,
+        )
+        .cargo_build();
+}
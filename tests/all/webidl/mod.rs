@@ -1,3 +1,4 @@
 use super::project;
 
 mod simple;
+mod types;
@@ -281,3 +281,76 @@ fn static_property() {
         )
         .test();
 }
+
+#[test]
+fn throws() {
+    project()
+        .file(
+            "foo.webidl",
+            r#"
+                [Constructor(double value), Throws]
+                interface Foo {
+                    [Throws]
+                    double checked(double value);
+                    [GetterThrows]
+                    attribute double value;
+                };
+            "#,
+        )
+        .file(
+            "foo.js",
+            r#"
+                export class Foo {
+                    constructor(value) {
+                        if (value < 0)
+                            throw new Error("negative");
+                        this._value = value;
+                    }
+
+                    checked(value) {
+                        if (value < 0)
+                            throw new Error("negative");
+                        return value;
+                    }
+
+                    get value() {
+                        if (this._value > 100)
+                            throw new Error("too big");
+                        return this._value;
+                    }
+
+                    set value(value) {
+                        this._value = value;
+                    }
+                }
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+
+                pub mod foo;
+
+                use foo::Foo;
+
+                #[wasm_bindgen]
+                pub fn test() {
+                    assert!(Foo::new(-1.0).is_err());
+                    let f = Foo::new(1.0).unwrap();
+
+                    assert_eq!(f.checked(2.0).unwrap(), 2.0);
+                    assert!(f.checked(-2.0).is_err());
+
+                    assert_eq!(f.value().unwrap(), 1.0);
+                    f.set_value(200.0);
+                    assert!(f.value().is_err());
+                }
+            "#,
+        )
+        .test();
+}
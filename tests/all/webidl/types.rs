@@ -0,0 +1,158 @@
+use super::project;
+
+#[test]
+fn buffer_sources_and_sequences() {
+    project()
+        .file(
+            "foo.webidl",
+            r#"
+                interface Foo {
+                    static double sum(Float64Array values);
+                    static sequence<long> range(long n);
+                    static Uint8Array bytes();
+                    static unsigned long byteLength(ArrayBuffer buffer);
+                    static Uint8ClampedArray clamp(Uint8ClampedArray pixels);
+                };
+            "#,
+        )
+        .file(
+            "foo.js",
+            r#"
+                export class Foo {
+                    static sum(values) {
+                        if (!(values instanceof Float64Array))
+                            throw new Error("expected a Float64Array");
+                        return values.reduce((a, b) => a + b, 0);
+                    }
+
+                    static range(n) {
+                        const res = [];
+                        for (let i = 0; i < n; i++)
+                            res.push(i);
+                        return res;
+                    }
+
+                    static bytes() {
+                        return new Uint8Array([1, 2, 3]);
+                    }
+
+                    static byteLength(buffer) {
+                        return buffer.byteLength;
+                    }
+
+                    static clamp(pixels) {
+                        if (!(pixels instanceof Uint8ClampedArray))
+                            throw new Error("expected a Uint8ClampedArray");
+                        return new Uint8ClampedArray(Array.from(pixels, p => p * 2));
+                    }
+                }
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+                use wasm_bindgen::js::ArrayBuffer;
+                use wasm_bindgen::Clamped;
+
+                pub mod foo;
+
+                use foo::Foo;
+
+                #[wasm_bindgen]
+                pub fn test() {
+                    assert_eq!(Foo::sum(&[1.0, 2.0, 3.5]), 6.5);
+                    assert_eq!(Foo::range(4), vec![0, 1, 2, 3]);
+                    assert_eq!(Foo::bytes(), vec![1, 2, 3]);
+                    assert_eq!(Foo::byte_length(ArrayBuffer::new(8)), 8);
+                    assert_eq!(Foo::clamp(Clamped(&[1, 100, 200])), Clamped(vec![2, 200, 255]));
+                }
+            "#,
+        )
+        .test();
+}
+
+#[test]
+fn objects_promises_and_unions() {
+    project()
+        .file(
+            "foo.webidl",
+            r#"
+                interface Foo {
+                    static object create(record<DOMString, long> init);
+                    static Promise<double> resolved(double value);
+                    static boolean isString((DOMString or double) value);
+                };
+            "#,
+        )
+        .file(
+            "foo.js",
+            r#"
+                export class Foo {
+                    static create(init) {
+                        return Object.assign({}, init);
+                    }
+
+                    static resolved(value) {
+                        return Promise.resolve(value);
+                    }
+
+                    static isString(value) {
+                        return typeof value === "string";
+                    }
+                }
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"
+                #![feature(proc_macro, wasm_custom_section, wasm_import_module)]
+
+                extern crate wasm_bindgen;
+
+                use wasm_bindgen::prelude::*;
+                use wasm_bindgen::js::{Object, Promise};
+
+                pub mod foo;
+
+                use foo::Foo;
+
+                #[wasm_bindgen(module = "./test")]
+                extern {
+                    #[wasm_bindgen(js_name = isPromise)]
+                    fn is_promise(p: &Promise) -> bool;
+                }
+
+                #[wasm_bindgen]
+                pub fn test() {
+                    let obj = Foo::create(Object::new());
+                    let tmp = obj.has_own_property(&JsValue::from("a"));
+                    assert!(!tmp);
+
+                    assert!(is_promise(&Foo::resolved(3.0)));
+
+                    assert!(Foo::is_string(JsValue::from("hello")));
+                    assert!(!Foo::is_string(JsValue::from(1.0)));
+                }
+            "#,
+        )
+        .file(
+            "test.js",
+            r#"
+                import * as wasm from "./out";
+
+                export function isPromise(p) {
+                    return p instanceof Promise;
+                }
+
+                export function test() {
+                    wasm.test();
+                }
+            "#,
+        )
+        .test();
+}